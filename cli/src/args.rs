@@ -11,6 +11,54 @@ pub struct RefactorArgs {
 pub enum EntityType {
     /// Renames a variable in the specified file
     RenameVariable(RenameVariableCommand),
+    /// Extracts the statements in a byte range into a new method
+    ExtractFunction(ExtractFunctionCommand),
+    /// Replaces every call of a method with its body and removes the method
+    InlineFunction(InlineFunctionCommand),
+    /// Introduces a local variable for the expression in a byte range
+    ExtractVariable(ExtractVariableCommand),
+    /// Replaces the uses of a local with its initializer
+    InlineVariable(InlineVariableCommand),
+    /// Renames a method and updates every call of it
+    RenameMethod(RenameMethodCommand),
+    /// Adds, removes and reorders the parameters of a method
+    ChangeSignature(ChangeSignatureCommand),
+    /// Renames a field or property and every reference to it
+    RenameField(RenameFieldCommand),
+    /// Makes a field private and exposes it through a property
+    EncapsulateField(EncapsulateFieldCommand),
+    /// Replaces a group of parameters with a single parameter of a new record
+    IntroduceParameterObject(IntroduceParameterObjectCommand),
+    /// Flattens the `if`/`else` ending a method into guard clauses
+    GuardClauses(GuardClausesCommand),
+    /// Moves the condition and branches of an `if` into methods of their own
+    DecomposeConditional(DecomposeConditionalCommand),
+    /// Merges `if` statements with the same body, or nested `if` statements, into one
+    ConsolidateConditional(ConsolidateConditionalCommand),
+    /// Negates the condition of an `if` and swaps its branches
+    InvertIf(InvertIfCommand),
+    /// Replaces a switch on a type code with one subclass per case
+    ReplaceConditionalWithPolymorphism(ReplaceConditionalWithPolymorphismCommand),
+    /// Replaces null checks on a class with a special case subclass
+    IntroduceSpecialCase(IntroduceSpecialCaseCommand),
+    /// Declares a new variable for each unrelated assignment of a local
+    SplitVariable(SplitVariableCommand),
+    /// Replaces a local with a private method computing its value
+    ReplaceTempWithQuery(ReplaceTempWithQueryCommand),
+    /// Moves statements up or down within their block
+    SlideStatements(SlideStatementsCommand),
+    /// Splits a loop computing several results into one loop per result
+    SplitLoop(SplitLoopCommand),
+    /// Replaces a loop that filters, maps and accumulates with a LINQ pipeline
+    ReplaceLoopWithPipeline(ReplaceLoopWithPipelineCommand),
+    /// Moves a method to another class and rewrites its callers
+    MoveMethod(MoveMethodCommand),
+    /// Moves a field to a class reached through other fields and rewrites its accesses
+    MoveField(MoveFieldCommand),
+    /// Moves chosen members of a class into a new class held in a field
+    ExtractClass(ExtractClassCommand),
+    /// Folds a class into the one class holding it in a field and deletes it
+    InlineClass(InlineClassCommand),
 }
 
 #[derive(Debug, Args)]
@@ -18,9 +66,322 @@ pub struct RenameVariableCommand {
     /// File path of target file
     pub file_path: String,
 
-    /// Old variable name
-    pub old_name: String,
+    /// Byte offset of the variable's declaration or any reference to it
+    pub offset: usize,
 
     /// New variable name
     pub new_name: String,
 }
+
+#[derive(Debug, Args)]
+pub struct ExtractFunctionCommand {
+    /// File path of target file
+    pub file_path: String,
+
+    /// Byte offset where the selection starts
+    pub start: usize,
+
+    /// Byte offset where the selection ends
+    pub end: usize,
+
+    /// Name of the new method
+    pub name: String,
+}
+
+#[derive(Debug, Args)]
+pub struct InlineFunctionCommand {
+    /// File path of target file
+    pub file_path: String,
+
+    /// Name of the method to inline
+    pub name: String,
+}
+
+#[derive(Debug, Args)]
+pub struct ExtractVariableCommand {
+    /// File path of target file
+    pub file_path: String,
+
+    /// Byte offset where the expression starts
+    pub start: usize,
+
+    /// Byte offset where the expression ends
+    pub end: usize,
+
+    /// Name of the new variable
+    pub name: String,
+
+    /// Also replace identical expressions later in the same block
+    #[clap(long)]
+    pub all: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct InlineVariableCommand {
+    /// File path of target file
+    pub file_path: String,
+
+    /// Byte offset of the variable name in its declaration
+    pub offset: usize,
+}
+
+#[derive(Debug, Args)]
+pub struct RenameMethodCommand {
+    /// File path of target file
+    pub file_path: String,
+
+    /// Current name of the method
+    pub name: String,
+
+    /// New method name
+    pub new_name: String,
+}
+
+#[derive(Debug, Args)]
+pub struct ChangeSignatureCommand {
+    /// File path of target file
+    pub file_path: String,
+
+    /// Name of the method
+    pub name: String,
+
+    /// The new parameters in order: the position of an existing parameter,
+    /// or a new one written as `<declaration>=<default argument>`
+    pub parameters: Vec<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct RenameFieldCommand {
+    /// File path of target file
+    pub file_path: String,
+
+    /// Current name of the field or property
+    pub name: String,
+
+    /// New name
+    pub new_name: String,
+}
+
+#[derive(Debug, Args)]
+pub struct EncapsulateFieldCommand {
+    /// File path of target file
+    pub file_path: String,
+
+    /// Name of the field
+    pub name: String,
+
+    /// Name of the new property
+    pub property_name: String,
+}
+
+#[derive(Debug, Args)]
+pub struct IntroduceParameterObjectCommand {
+    /// File path of target file
+    pub file_path: String,
+
+    /// Name of the method
+    pub method: String,
+
+    /// Name of the new record
+    pub class_name: String,
+
+    /// Name of the parameter that replaces the group
+    pub parameter_name: String,
+
+    /// Names of the parameters to group
+    #[clap(required = true)]
+    pub parameters: Vec<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct GuardClausesCommand {
+    /// File path of target file
+    pub file_path: String,
+
+    /// Name of the method
+    pub name: String,
+}
+
+#[derive(Debug, Args)]
+pub struct DecomposeConditionalCommand {
+    /// File path of target file
+    pub file_path: String,
+
+    /// Byte offset inside the `if` statement
+    pub offset: usize,
+
+    /// Name of the method for the condition
+    pub condition_name: String,
+
+    /// Name of the method for the `if` branch
+    pub then_name: String,
+
+    /// Name of the method for the `else` branch, which otherwise stays in place
+    pub else_name: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct ConsolidateConditionalCommand {
+    /// File path of target file
+    pub file_path: String,
+
+    /// Byte offset where the selection starts
+    pub start: usize,
+
+    /// Byte offset where the selection ends
+    pub end: usize,
+
+    /// Name of a method to extract the combined condition into
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct InvertIfCommand {
+    /// File path of target file
+    pub file_path: String,
+
+    /// Byte offset inside the `if` statement
+    pub offset: usize,
+}
+
+#[derive(Debug, Args)]
+pub struct ReplaceConditionalWithPolymorphismCommand {
+    /// File path of target file
+    pub file_path: String,
+
+    /// Name of the method that dispatches on the type code
+    pub method: String,
+}
+
+#[derive(Debug, Args)]
+pub struct IntroduceSpecialCaseCommand {
+    /// File path of target file
+    pub file_path: String,
+
+    /// Name of the class whose null values get a special case
+    pub class: String,
+}
+
+#[derive(Debug, Args)]
+pub struct SplitVariableCommand {
+    /// File path of target file
+    pub file_path: String,
+
+    /// Byte offset of the variable's declaration or any reference to it
+    pub offset: usize,
+
+    /// Names for the variables introduced by the second and later assignments
+    #[clap(required = true)]
+    pub names: Vec<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct ReplaceTempWithQueryCommand {
+    /// File path of target file
+    pub file_path: String,
+
+    /// Byte offset of the name in the local's declaration
+    pub offset: usize,
+
+    /// Name of the new method or property
+    pub name: String,
+
+    /// Generate a read-only property instead of a method
+    #[clap(long)]
+    pub property: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct SlideStatementsCommand {
+    /// File path of target file
+    pub file_path: String,
+
+    /// Start byte offset of the statements to move
+    pub start: usize,
+
+    /// End byte offset of the statements to move
+    pub end: usize,
+
+    /// Byte offset inside the statement to move next to
+    pub target: usize,
+}
+
+#[derive(Debug, Args)]
+pub struct SplitLoopCommand {
+    /// File path of target file
+    pub file_path: String,
+
+    /// Byte offset inside the loop to split
+    pub offset: usize,
+}
+
+#[derive(Debug, Args)]
+pub struct ReplaceLoopWithPipelineCommand {
+    /// File path of target file
+    pub file_path: String,
+
+    /// Byte offset inside the loop to replace
+    pub offset: usize,
+}
+
+#[derive(Debug, Args)]
+pub struct MoveMethodCommand {
+    /// Name of the class declaring the method
+    pub class: String,
+
+    /// Name of the method to move
+    pub method: String,
+
+    /// Name of the class to move the method to
+    pub target: String,
+
+    /// File paths of every file to search and update
+    #[clap(required = true)]
+    pub files: Vec<String>,
+
+    /// Keep the method in its class, forwarding to the moved one
+    #[clap(long)]
+    pub delegate: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct MoveFieldCommand {
+    /// Name of the class declaring the field
+    pub class: String,
+
+    /// Name of the field to move
+    pub field: String,
+
+    /// Name of the class to move the field to
+    pub target: String,
+
+    /// File paths of every file to search and update
+    #[clap(required = true)]
+    pub files: Vec<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct ExtractClassCommand {
+    /// File path of target file
+    pub file_path: String,
+
+    /// Name of the class to split
+    pub class: String,
+
+    /// Name of the new class
+    pub name: String,
+
+    /// Names of the fields, properties and methods to move
+    #[clap(required = true)]
+    pub members: Vec<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct InlineClassCommand {
+    /// Name of the class to inline
+    pub class: String,
+
+    /// File paths of every file to search and update
+    #[clap(required = true)]
+    pub files: Vec<String>,
+}
@@ -1,9 +1,18 @@
 use args::{EntityType, RefactorArgs};
-use c_sharp::lower_top_level;
+use c_sharp::{lower_compilation_unit, lower_top_level};
 use clap::Parser as ClapParser;
-use core::{apply_refactoring, Refactoring, RenameVariable};
+use core::{
+    apply_refactoring, ChangeSignature, ConsolidateConditional, DecomposeConditional,
+    EncapsulateField, ExtractClass, ExtractFunction, ExtractVariable, InlineClass, InlineFunction,
+    InlineVariable, IntroduceParameterObject, IntroduceSpecialCase, InvertIf, MoveField,
+    MoveMethod, ParameterSpec, Refactoring, RenameField, RenameMethod, RenameVariable,
+    ReplaceConditionalWithPolymorphism, ReplaceLoopWithPipeline,
+    ReplaceNestedConditionalWithGuardClauses, ReplaceTempWithQuery, SlideStatements, SourceFile,
+    SplitLoop, SplitVariable, WorkspaceRefactoring,
+};
 use std::fs;
-use tree_sitter::Parser;
+use tree_sitter::{Node, Parser};
+use uast::TopLevel;
 
 mod args;
 
@@ -12,32 +21,222 @@ fn main() {
 
     match args.entity_type {
         EntityType::RenameVariable(cmd) => {
-            let source_code = fs::read_to_string(&cmd.file_path).expect("Unable to read file");
+            let refactoring = RenameVariable::new(cmd.offset, &cmd.new_name);
+            run(&cmd.file_path, &refactoring);
+        }
+        EntityType::ExtractFunction(cmd) => {
+            let refactoring = ExtractFunction::new(cmd.start, cmd.end, &cmd.name);
+            run(&cmd.file_path, &refactoring);
+        }
+        EntityType::InlineFunction(cmd) => {
+            let refactoring = InlineFunction::new(&cmd.name);
+            run(&cmd.file_path, &refactoring);
+        }
+        EntityType::ExtractVariable(cmd) => {
+            let refactoring = ExtractVariable::new(cmd.start, cmd.end, &cmd.name, cmd.all);
+            run(&cmd.file_path, &refactoring);
+        }
+        EntityType::InlineVariable(cmd) => {
+            let refactoring = InlineVariable::new(cmd.offset);
+            run(&cmd.file_path, &refactoring);
+        }
+        EntityType::RenameMethod(cmd) => {
+            let refactoring = RenameMethod::new(&cmd.name, &cmd.new_name);
+            run(&cmd.file_path, &refactoring);
+        }
+        EntityType::RenameField(cmd) => {
+            let refactoring = RenameField::new(&cmd.name, &cmd.new_name);
+            run(&cmd.file_path, &refactoring);
+        }
+        EntityType::EncapsulateField(cmd) => {
+            let refactoring = EncapsulateField::new(&cmd.name, &cmd.property_name);
+            run_file(&cmd.file_path, &refactoring);
+        }
+        EntityType::IntroduceParameterObject(cmd) => {
+            let parameters: Vec<&str> = cmd.parameters.iter().map(String::as_str).collect();
+            let refactoring = IntroduceParameterObject::new(
+                &cmd.method,
+                &parameters,
+                &cmd.class_name,
+                &cmd.parameter_name,
+            );
+            run(&cmd.file_path, &refactoring);
+        }
+        EntityType::GuardClauses(cmd) => {
+            let refactoring = ReplaceNestedConditionalWithGuardClauses::new(&cmd.name);
+            run(&cmd.file_path, &refactoring);
+        }
+        EntityType::DecomposeConditional(cmd) => {
+            let refactoring = DecomposeConditional::new(
+                cmd.offset,
+                &cmd.condition_name,
+                &cmd.then_name,
+                cmd.else_name.as_deref(),
+            );
+            run(&cmd.file_path, &refactoring);
+        }
+        EntityType::ConsolidateConditional(cmd) => {
+            let refactoring = ConsolidateConditional::new(cmd.start, cmd.end, cmd.name.as_deref());
+            run(&cmd.file_path, &refactoring);
+        }
+        EntityType::InvertIf(cmd) => {
+            let refactoring = InvertIf::new(cmd.offset);
+            run(&cmd.file_path, &refactoring);
+        }
+        EntityType::ReplaceConditionalWithPolymorphism(cmd) => {
+            let refactoring = ReplaceConditionalWithPolymorphism::new(&cmd.method);
+            run(&cmd.file_path, &refactoring);
+        }
+        EntityType::IntroduceSpecialCase(cmd) => {
+            let refactoring = IntroduceSpecialCase::new(&cmd.class);
+            run_file(&cmd.file_path, &refactoring);
+        }
+        EntityType::SplitVariable(cmd) => {
+            let names: Vec<&str> = cmd.names.iter().map(String::as_str).collect();
+            let refactoring = SplitVariable::new(cmd.offset, &names);
+            run(&cmd.file_path, &refactoring);
+        }
+        EntityType::ReplaceTempWithQuery(cmd) => {
+            let refactoring = ReplaceTempWithQuery::new(cmd.offset, &cmd.name, cmd.property);
+            run(&cmd.file_path, &refactoring);
+        }
+        EntityType::SlideStatements(cmd) => {
+            let refactoring = SlideStatements::new(cmd.start, cmd.end, cmd.target);
+            run(&cmd.file_path, &refactoring);
+        }
+        EntityType::SplitLoop(cmd) => {
+            let refactoring = SplitLoop::new(cmd.offset);
+            run(&cmd.file_path, &refactoring);
+        }
+        EntityType::ReplaceLoopWithPipeline(cmd) => {
+            let refactoring = ReplaceLoopWithPipeline::new(cmd.offset);
+            run(&cmd.file_path, &refactoring);
+        }
+        EntityType::MoveMethod(cmd) => {
+            let refactoring = MoveMethod::new(&cmd.class, &cmd.method, &cmd.target, cmd.delegate);
+            run_workspace(&cmd.files, &refactoring);
+        }
+        EntityType::MoveField(cmd) => {
+            let refactoring = MoveField::new(&cmd.class, &cmd.field, &cmd.target);
+            run_workspace(&cmd.files, &refactoring);
+        }
+        EntityType::ExtractClass(cmd) => {
+            let members: Vec<&str> = cmd.members.iter().map(String::as_str).collect();
+            let refactoring = ExtractClass::new(&cmd.class, &cmd.name, &members);
+            run_file(&cmd.file_path, &refactoring);
+        }
+        EntityType::InlineClass(cmd) => {
+            let refactoring = InlineClass::new(&cmd.class);
+            run_workspace(&cmd.files, &refactoring);
+        }
+        EntityType::ChangeSignature(cmd) => {
+            let parameters = cmd.parameters.iter().map(|p| parameter_spec(p)).collect();
+            let refactoring = ChangeSignature::new(&cmd.name, parameters);
+            run(&cmd.file_path, &refactoring);
+        }
+    }
+}
+
+/// Parse a parameter of the `change-signature` command.
+fn parameter_spec(text: &str) -> ParameterSpec {
+    match text.parse() {
+        Ok(position) => ParameterSpec::Existing(position),
+        Err(_) => {
+            let (declaration, default) = text
+                .split_once('=')
+                .expect("a new parameter needs a default argument, as in `int level=0`");
+            ParameterSpec::Added {
+                declaration: declaration.trim().to_string(),
+                default: default.trim().to_string(),
+            }
+        }
+    }
+}
+
+/// Lower the first class of a file, apply the refactoring and write the result back.
+fn run(file_path: &str, refactoring: &dyn Refactoring) {
+    run_with(file_path, refactoring, |root, source| {
+        let mut cursor = root.walk();
+        let class_node = root
+            .children(&mut cursor)
+            .find(|n| n.kind() == "class_declaration");
+        class_node.map(|node| lower_top_level(node, source))
+    });
+}
+
+/// Lower every class of a file, apply the refactoring and write the result back.
+fn run_file(file_path: &str, refactoring: &dyn Refactoring) {
+    run_with(file_path, refactoring, |root, source| {
+        Some(lower_compilation_unit(root, source))
+    });
+}
+
+/// Lower every class of several files, apply the refactoring and write each
+/// changed file back.
+fn run_workspace(file_paths: &[String], refactoring: &dyn WorkspaceRefactoring) {
+    let sources: Vec<String> = file_paths
+        .iter()
+        .map(|path| fs::read_to_string(path).expect("Unable to read file"))
+        .collect();
+
+    let mut parser = Parser::new();
+    parser
+        .set_language(tree_sitter_c_sharp::language())
+        .expect("Error loading C# grammar");
+
+    let uasts: Vec<TopLevel> = sources
+        .iter()
+        .map(|source_code| {
+            let tree = parser.parse(source_code, None).expect("Error parsing");
+            lower_compilation_unit(tree.root_node(), source_code.as_bytes())
+        })
+        .collect();
+    let files: Vec<SourceFile> = file_paths
+        .iter()
+        .zip(&sources)
+        .zip(&uasts)
+        .map(|((path, source), uast)| SourceFile { path, uast, source })
+        .collect();
+
+    match refactoring.apply_workspace(&files) {
+        Ok(changes) => {
+            for change in changes {
+                if let Some(file) = files.iter().find(|file| file.path == change.path) {
+                    let new_code = apply_refactoring(file.source, change.edits);
+                    let _ = fs::write(&change.path, new_code);
+                }
+            }
+        }
+        Err(err) => eprintln!("Refactoring failed: {}", err),
+    }
+}
 
-            let mut parser = Parser::new();
-            parser
-                .set_language(tree_sitter_c_sharp::language())
-                .expect("Error loading C# grammar");
+fn run_with(
+    file_path: &str,
+    refactoring: &dyn Refactoring,
+    lower: impl Fn(Node, &[u8]) -> Option<TopLevel>,
+) {
+    let source_code = fs::read_to_string(file_path).expect("Unable to read file");
 
-            let tree = parser.parse(&source_code, None).expect("Error parsing");
-            let root = tree.root_node();
+    let mut parser = Parser::new();
+    parser
+        .set_language(tree_sitter_c_sharp::language())
+        .expect("Error loading C# grammar");
 
-            let mut cursor = root.walk();
-            let class_node = root
-                .children(&mut cursor)
-                .find(|n| n.kind() == "class_declaration");
+    let tree = parser.parse(&source_code, None).expect("Error parsing");
+    let root = tree.root_node();
 
-            if let Some(node) = class_node {
-                let uast = lower_top_level(node, source_code.as_bytes());
-                let refactoring = RenameVariable::new(&cmd.old_name, &cmd.new_name);
-                let edits = refactoring.apply(&uast);
+    if let Some(uast) = lower(root, source_code.as_bytes()) {
+        match refactoring.apply(&uast, &source_code) {
+            Ok(edits) => {
                 let new_code = apply_refactoring(&source_code, edits);
 
                 // Write to where it came from now, can change later if needed.
-                let _ = fs::write(&cmd.file_path, new_code);
-            } else {
-                eprintln!("No class declaration found in top-level.");
+                let _ = fs::write(file_path, new_code);
             }
+            Err(err) => eprintln!("Refactoring failed: {}", err),
         }
+    } else {
+        eprintln!("No class declaration found in top-level.");
     }
 }
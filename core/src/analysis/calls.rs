@@ -0,0 +1,338 @@
+use crate::analysis::locate::{
+    block_statements, expression_tree, function_blocks, lambda_blocks, statement_expressions,
+    statement_tree,
+};
+use crate::analysis::members::members;
+use crate::analysis::scope::Bindings;
+use crate::analysis::variables::raw_tokens;
+use crate::text::slice;
+use crate::RefactorError;
+use uast::*;
+
+/// The object a call is made on.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Receiver<'a> {
+    /// `Foo()`
+    Implicit,
+    /// `this.Foo()`
+    This,
+    /// `other.Foo()`
+    Other(&'a Expression),
+}
+
+/// Where a call sits relative to the statement that contains it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallPosition {
+    /// The call is the entire expression statement, as in `Foo(a);`.
+    Statement,
+    /// The call is evaluated once as part of a larger statement.
+    Expression,
+    /// The call is part of a loop condition or update and runs every iteration.
+    LoopHeader,
+}
+
+/// A single invocation of a method, with enough context to rewrite it.
+pub struct CallSite<'a> {
+    pub class: Option<&'a ClassDef>,
+    pub caller: &'a FunctionDef,
+    /// The innermost statement whose own expressions contain the call.
+    pub statement: &'a Statement,
+    pub invocation: &'a Invocation,
+    pub receiver: Receiver<'a>,
+    pub position: CallPosition,
+}
+
+/// Find every invocation of a method called `name` in the functions of `node`.
+pub fn find_calls<'a>(node: &'a TopLevel, name: &str) -> Vec<CallSite<'a>> {
+    let mut sites = Vec::new();
+    visit_top_level(node, None, name, &mut sites);
+    sites
+}
+
+/// The calls of the method `name` declared by `class`, leaving out calls made
+/// on instances of other classes, and calls without a receiver or on `this`
+/// made from other classes, which target their own method. A call whose
+/// receiver cannot be typed is an error, since it may or may not target the
+/// method.
+pub fn calls_of<'a>(
+    node: &'a TopLevel,
+    class: Option<&ClassDef>,
+    name: &str,
+    bindings: &Bindings,
+    source: &str,
+) -> Result<Vec<CallSite<'a>>, RefactorError> {
+    let mut calls = Vec::new();
+    for site in find_calls(node, name) {
+        let ours = match site.receiver {
+            Receiver::Other(receiver) => {
+                is_instance_of(receiver, class, bindings).ok_or_else(|| {
+                    RefactorError::Unsupported(format!(
+                        "cannot tell whether the call on `{}` targets `{}`",
+                        slice(source, receiver.span()),
+                        name
+                    ))
+                })?
+            }
+            Receiver::Implicit | Receiver::This => class.is_none_or(|class| {
+                site.class
+                    .is_some_and(|caller_class| std::ptr::eq(caller_class, class))
+            }),
+        };
+        if ours {
+            calls.push(site);
+        }
+    }
+    Ok(calls)
+}
+
+/// Find mentions of `name` in source that was not lowered, or in expressions
+/// outside any statement, where a call could hide without being visible to
+/// [`find_calls`].
+pub fn unlowered_mentions(node: &TopLevel, name: &str) -> Vec<Span> {
+    let mut spans = Vec::new();
+    collect_unlowered(node, name, &mut spans);
+    spans
+}
+
+/// The method name an invocation targets, if it is a plain or member call.
+pub fn invoked_name(inv: &Invocation) -> Option<&str> {
+    match inv.function.as_ref() {
+        Expression::Identifier(name, _) => Some(name),
+        Expression::MemberAccess(ma) => Some(&ma.member),
+        _ => None,
+    }
+}
+
+/// The receiver of an invocation.
+pub fn receiver(inv: &Invocation) -> Receiver<'_> {
+    match inv.function.as_ref() {
+        Expression::MemberAccess(ma) => match ma.expression.as_ref() {
+            this if receiver_is_this(this) => Receiver::This,
+            other => Receiver::Other(other),
+        },
+        _ => Receiver::Implicit,
+    }
+}
+
+/// Whether `receiver` is an instance of `class`, or the class itself, or
+/// `None` when its type cannot be determined.
+///
+/// Locals and parameters are typed by their declarations, and other bare
+/// names and `this.name` by the fields and properties of `class`.
+pub fn is_instance_of(
+    receiver: &Expression,
+    class: Option<&ClassDef>,
+    bindings: &Bindings,
+) -> Option<bool> {
+    let class = class?;
+    let member_type = |name: &str| {
+        members(class)
+            .into_iter()
+            .find(|member| member.name() == name)
+            .and_then(|member| member.var_type())
+            .map(|var_type| var_type == class.name)
+    };
+    match receiver {
+        Expression::Identifier(name, _) if *name == class.name => Some(true),
+        Expression::Identifier(name, span) => {
+            let Some(reference) = bindings
+                .references
+                .iter()
+                .find(|reference| reference.span == *span)
+            else {
+                return member_type(name);
+            };
+            let decl = reference.decl;
+            match decl.var_type.as_deref() {
+                Some("var") => decl
+                    .value
+                    .as_deref()
+                    .and_then(|value| is_instance_of(value, Some(class), bindings)),
+                Some(var_type) => Some(var_type == class.name),
+                None => None,
+            }
+        }
+        Expression::MemberAccess(ma) if receiver_is_this(&ma.expression) => member_type(&ma.member),
+        Expression::Raw { source, .. } if source == "this" => Some(true),
+        Expression::Raw { source, .. } if source == "base" => Some(false),
+        Expression::Raw { source, .. } => {
+            let created = source.strip_prefix("new ")?.trim_start();
+            let type_name = created
+                .split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.'))
+                .next()?;
+            Some(type_name == class.name)
+        }
+        _ => None,
+    }
+}
+
+/// Whether an expression is `this`.
+fn receiver_is_this(expr: &Expression) -> bool {
+    matches!(expr, Expression::Raw { source, .. } if source == "this")
+}
+
+fn visit_top_level<'a>(
+    node: &'a TopLevel,
+    class: Option<&'a ClassDef>,
+    name: &str,
+    sites: &mut Vec<CallSite<'a>>,
+) {
+    match node {
+        TopLevel::Class(class_def) => {
+            for item in class_def.body.iter().flatten() {
+                visit_top_level(item, Some(class_def), name, sites);
+            }
+        }
+        TopLevel::Module(module) => {
+            for item in &module.body {
+                visit_top_level(item, class, name, sites);
+            }
+        }
+        TopLevel::Function(func) => visit_function(func, class, name, sites),
+        TopLevel::Property(property) => {
+            for accessor in &property.accessors {
+                visit_function(accessor, class, name, sites);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn visit_function<'a>(
+    func: &'a FunctionDef,
+    class: Option<&'a ClassDef>,
+    name: &str,
+    sites: &mut Vec<CallSite<'a>>,
+) {
+    for stmt in function_blocks(func).flat_map(block_statements) {
+        for (top, in_loop_header) in statement_expressions(stmt) {
+            for expr in expression_tree(top) {
+                let Expression::Invocation(inv) = expr else {
+                    continue;
+                };
+                if invoked_name(inv) != Some(name) {
+                    continue;
+                }
+                let position = if in_loop_header {
+                    CallPosition::LoopHeader
+                } else if matches!(stmt, Statement::ExpressionStatement(_))
+                    && std::ptr::eq(expr, top)
+                {
+                    CallPosition::Statement
+                } else {
+                    CallPosition::Expression
+                };
+                sites.push(CallSite {
+                    class,
+                    caller: func,
+                    statement: stmt,
+                    invocation: inv,
+                    receiver: receiver(inv),
+                    position,
+                });
+            }
+        }
+    }
+}
+
+fn collect_unlowered(node: &TopLevel, name: &str, spans: &mut Vec<Span>) {
+    match node {
+        TopLevel::Class(class_def) => {
+            for item in class_def.body.iter().flatten() {
+                collect_unlowered(item, name, spans);
+            }
+        }
+        TopLevel::Module(module) => {
+            for item in &module.body {
+                collect_unlowered(item, name, spans);
+            }
+        }
+        TopLevel::Function(func) => visit_unlowered_function(func, name, spans),
+        TopLevel::Field(field) => {
+            for value in field
+                .var_decls
+                .iter()
+                .filter_map(|var| var.value.as_deref())
+            {
+                collect_mentions(value, name, spans);
+            }
+        }
+        TopLevel::Property(property) => {
+            for accessor in &property.accessors {
+                visit_unlowered_function(accessor, name, spans);
+            }
+            if let Some(value) = &property.value {
+                collect_mentions(value, name, spans);
+            }
+        }
+        TopLevel::Statement(stmt) => {
+            collect_unlowered_statements(statement_tree(stmt), name, spans)
+        }
+        TopLevel::Unknown { source, span } => scan(source, span, name, spans),
+    }
+}
+
+fn visit_unlowered_function(func: &FunctionDef, name: &str, spans: &mut Vec<Span>) {
+    for item in func.body.iter().flatten() {
+        match item {
+            FunctionBodyItems::Block(block) => {
+                collect_unlowered_statements(block_statements(block), name, spans)
+            }
+            // Expression bodies have no statement to anchor a call site to.
+            FunctionBodyItems::Expression(expr) => collect_mentions(expr, name, spans),
+            FunctionBodyItems::TopLevel(_) => {}
+        }
+    }
+}
+
+/// Mentions of `name` in the unlowered statements and expressions of `stmts`.
+fn collect_unlowered_statements(stmts: Vec<&Statement>, name: &str, spans: &mut Vec<Span>) {
+    for stmt in stmts {
+        if let Statement::Unknown { source, span } = stmt {
+            scan(source, span, name, spans);
+        }
+        for (top, _) in statement_expressions(stmt) {
+            for expr in expression_tree(top) {
+                if let Expression::Raw { source, span } = expr {
+                    scan(source, span, name, spans);
+                }
+            }
+        }
+    }
+}
+
+/// Every mention of `name` in an expression, lowered or not.
+fn collect_mentions(expr: &Expression, name: &str, spans: &mut Vec<Span>) {
+    for nested in expression_tree(expr) {
+        mentions(nested, name, spans);
+    }
+    for stmt in lambda_blocks(expr).into_iter().flat_map(block_statements) {
+        if let Statement::Unknown { source, span } = stmt {
+            scan(source, span, name, spans);
+        }
+        for (top, _) in statement_expressions(stmt) {
+            for nested in expression_tree(top) {
+                mentions(nested, name, spans);
+            }
+        }
+    }
+}
+
+/// The mentions of `name` by `expr` itself, leaving out nested expressions.
+fn mentions(expr: &Expression, name: &str, spans: &mut Vec<Span>) {
+    match expr {
+        Expression::Identifier(id, span) if id == name => spans.push(span.clone()),
+        Expression::MemberAccess(ma) if ma.member == name => spans.push(ma.member_span.clone()),
+        Expression::Raw { source, span } => scan(source, span, name, spans),
+        _ => {}
+    }
+}
+
+fn scan(source: &str, span: &Span, name: &str, spans: &mut Vec<Span>) {
+    spans.extend(
+        raw_tokens(source, span)
+            .into_iter()
+            .filter(|token| token.text == name)
+            .map(|token| token.span),
+    );
+}
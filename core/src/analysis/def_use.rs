@@ -0,0 +1,329 @@
+use crate::analysis::locate::{function_blocks, subexpressions};
+use crate::analysis::scope::resolve_function;
+use crate::analysis::variables::{expression_usages, raw_tokens, Access};
+use std::collections::BTreeSet;
+use uast::*;
+
+/// A value stored in a variable, and the reads that may observe it.
+#[derive(Debug, Clone)]
+pub struct Definition {
+    /// The identifier written, or the declared name for an initializer or a
+    /// parameter.
+    pub span: Span,
+    /// `ReadWrite` for updates such as `x += 1` that build on the old value.
+    pub access: Access,
+    pub reads: Vec<Span>,
+}
+
+/// The def-use chains of a single variable.
+#[derive(Debug, Default)]
+pub struct DefUse {
+    /// Every definition, in source order. A parameter is defined by its
+    /// declaration when the function is entered.
+    pub definitions: Vec<Definition>,
+    /// Whether the variable is mentioned by source that was not lowered or
+    /// inside a lambda, or the function jumps with `goto`, so the chains may
+    /// be incomplete.
+    pub opaque: bool,
+}
+
+impl DefUse {
+    /// The indices of the definitions that reach the read at `span`.
+    pub fn reaching(&self, span: &Span) -> Vec<usize> {
+        (0..self.definitions.len())
+            .filter(|&i| self.definitions[i].reads.contains(span))
+            .collect()
+    }
+}
+
+/// Compute which assignments of `decl` reach which of its reads in `func`.
+///
+/// Branches are joined, loops are iterated until nothing changes, and
+/// `break`, `continue`, `return` and `throw` leave the path they end.
+pub fn def_use(func: &FunctionDef, decl: &VarDecl) -> DefUse {
+    let bindings = resolve_function(func);
+    let mut analyzer = Analyzer {
+        references: bindings
+            .references_to(decl)
+            .into_iter()
+            .map(|reference| reference.span.clone())
+            .collect(),
+        decl,
+        chains: DefUse::default(),
+        jumps: Vec::new(),
+    };
+
+    let mut state = State::new();
+    if func
+        .parameters
+        .iter()
+        .flatten()
+        .any(|param| std::ptr::eq(param, decl))
+    {
+        state.insert(analyzer.define(&decl.name_span, Access::Write));
+    }
+    for block in function_blocks(func) {
+        state = analyzer.block(block, state);
+    }
+
+    analyzer
+        .chains
+        .definitions
+        .sort_by_key(|definition| definition.span.start);
+    analyzer.chains
+}
+
+type State = BTreeSet<usize>;
+
+/// Definitions that reach the targets of `break` and `continue`.
+struct Jumps {
+    is_loop: bool,
+    breaks: State,
+    continues: State,
+}
+
+struct Analyzer<'a> {
+    references: Vec<Span>,
+    decl: &'a VarDecl,
+    chains: DefUse,
+    jumps: Vec<Jumps>,
+}
+
+impl Analyzer<'_> {
+    fn define(&mut self, span: &Span, access: Access) -> usize {
+        let definitions = &mut self.chains.definitions;
+        match definitions.iter().position(|def| def.span == *span) {
+            Some(i) => i,
+            None => {
+                definitions.push(Definition {
+                    span: span.clone(),
+                    access,
+                    reads: Vec::new(),
+                });
+                definitions.len() - 1
+            }
+        }
+    }
+
+    fn read(&mut self, span: &Span, state: &State) {
+        for &i in state {
+            let reads = &mut self.chains.definitions[i].reads;
+            if !reads.contains(span) {
+                reads.push(span.clone());
+            }
+        }
+    }
+
+    fn block(&mut self, block: &Block, mut state: State) -> State {
+        for stmt in &block.statements {
+            state = self.statement(stmt, state);
+        }
+        state
+    }
+
+    fn expression(&mut self, expr: &Expression, mut state: State) -> State {
+        self.check_raw(expr);
+        for usage in expression_usages(expr) {
+            if !self.references.contains(&usage.span) {
+                continue;
+            }
+            if usage.access.reads() {
+                self.read(&usage.span, &state);
+            }
+            if usage.access.writes() {
+                state = State::from([self.define(&usage.span, usage.access)]);
+            }
+        }
+        state
+    }
+
+    fn statement(&mut self, stmt: &Statement, mut state: State) -> State {
+        match stmt {
+            Statement::DeclStmt(decl_stmt) => {
+                for var in &decl_stmt.var_decls {
+                    if let Some(value) = &var.value {
+                        state = self.expression(value, state);
+                    }
+                    if std::ptr::eq(var, self.decl) {
+                        state = match &var.value {
+                            Some(_) => State::from([self.define(&var.name_span, Access::Write)]),
+                            None => State::new(),
+                        };
+                    }
+                }
+                state
+            }
+            Statement::ExpressionStatement(expr) => self.expression(&expr.expression, state),
+            Statement::ReturnStatement(ret) => {
+                if let Some(value) = &ret.value {
+                    self.expression(value, state);
+                }
+                State::new()
+            }
+            Statement::IfStatement(if_stmt) => {
+                let state = self.expression(&if_stmt.condition, state);
+                let mut after = self.block(&if_stmt.consequence, state.clone());
+                match &if_stmt.alternative {
+                    Some(alt) => after.extend(self.block(alt, state)),
+                    None => after.extend(state),
+                }
+                after
+            }
+            Statement::WhileLoop(w) => self.looping(state, Some(&w.condition), None, &w.body, None),
+            Statement::ForLoop(f) => {
+                if let Some(init) = &f.initializer {
+                    state = self.statement(init, state);
+                }
+                self.looping(
+                    state,
+                    f.condition.as_deref(),
+                    None,
+                    &f.body,
+                    f.update.as_deref(),
+                )
+            }
+            Statement::ForEachLoop(f) => {
+                state = self.expression(&f.collection, state);
+                self.looping(state, None, Some(&f.variable), &f.body, None)
+            }
+            Statement::SwitchStatement(switch) => {
+                state = self.expression(&switch.value, state);
+                for label in switch.sections.iter().flat_map(|section| &section.labels) {
+                    if let SwitchLabel::Case(value) = label {
+                        state = self.expression(value, state);
+                    }
+                }
+                self.jumps.push(Jumps {
+                    is_loop: false,
+                    breaks: State::new(),
+                    continues: State::new(),
+                });
+                let mut after = State::new();
+                for section in &switch.sections {
+                    after.extend(self.block(&section.body, state.clone()));
+                }
+                let has_default = switch
+                    .sections
+                    .iter()
+                    .flat_map(|section| &section.labels)
+                    .any(|label| matches!(label, SwitchLabel::Default(_)));
+                if !has_default {
+                    after.extend(state);
+                }
+                let jumps = self.jumps.pop().unwrap();
+                after.extend(jumps.breaks);
+                after
+            }
+            Statement::Unknown { source, span } => {
+                let mentioned = raw_tokens(source, span)
+                    .iter()
+                    .any(|token| self.references.contains(&token.span));
+                let keyword = source
+                    .split(|c: char| !c.is_alphanumeric())
+                    .next()
+                    .unwrap_or_default();
+                if mentioned || keyword == "goto" {
+                    self.chains.opaque = true;
+                }
+                match keyword {
+                    "break" => {
+                        if let Some(jumps) = self.jumps.last_mut() {
+                            jumps.breaks.extend(state);
+                        }
+                        State::new()
+                    }
+                    "continue" => {
+                        if let Some(jumps) = self.jumps.iter_mut().rev().find(|j| j.is_loop) {
+                            jumps.continues.extend(state);
+                        }
+                        State::new()
+                    }
+                    "throw" => State::new(),
+                    _ => state,
+                }
+            }
+        }
+    }
+
+    /// Run a loop until the definitions reaching its head stop changing.
+    /// A `foreach` loop passes its `variable`, which every iteration defines
+    /// before entering the body.
+    fn looping(
+        &mut self,
+        entry: State,
+        condition: Option<&Expression>,
+        variable: Option<&VarDecl>,
+        body: &Block,
+        update: Option<&Expression>,
+    ) -> State {
+        let mut head = entry;
+        loop {
+            let tested = match condition {
+                Some(condition) => self.expression(condition, head.clone()),
+                None => head.clone(),
+            };
+            self.jumps.push(Jumps {
+                is_loop: true,
+                breaks: State::new(),
+                continues: State::new(),
+            });
+            let entered = match variable {
+                Some(var) if std::ptr::eq(var, self.decl) => {
+                    State::from([self.define(&var.name_span, Access::Write)])
+                }
+                _ => tested.clone(),
+            };
+            let mut end = self.block(body, entered);
+            let jumps = self.jumps.pop().unwrap();
+            end.extend(jumps.continues);
+            if let Some(update) = update {
+                end = self.expression(update, end);
+            }
+
+            let mut next = head.clone();
+            next.extend(end);
+            if next == head {
+                // Without a condition or a collection to run out of, the
+                // loop only ends through `break`.
+                let mut exit = if condition.is_some() || variable.is_some() {
+                    tested
+                } else {
+                    State::new()
+                };
+                exit.extend(jumps.breaks);
+                return exit;
+            }
+            head = next;
+        }
+    }
+
+    /// Flag mentions of the variable inside unlowered expressions, where a
+    /// write cannot be told apart from a read, and inside lambdas.
+    fn check_raw(&mut self, expr: &Expression) {
+        match expr {
+            Expression::Raw { source, span } => {
+                if raw_tokens(source, span)
+                    .iter()
+                    .any(|token| self.references.contains(&token.span))
+                {
+                    self.chains.opaque = true;
+                }
+            }
+            // The body of a lambda runs whenever the lambda is called.
+            Expression::Lambda(lambda) => {
+                if self
+                    .references
+                    .iter()
+                    .any(|reference| lambda.span.contains(reference))
+                {
+                    self.chains.opaque = true;
+                }
+            }
+            _ => {
+                for nested in subexpressions(expr) {
+                    self.check_raw(nested);
+                }
+            }
+        }
+    }
+}
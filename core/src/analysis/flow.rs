@@ -0,0 +1,197 @@
+use crate::analysis::locate::{function_blocks, nested_blocks};
+use crate::analysis::variables::{
+    expression_usages, function_declarations, function_usages, statement_declarations,
+    statement_usages,
+};
+use crate::RefactorError;
+use uast::*;
+
+/// The data flowing into and out of a run of statements.
+pub struct DataFlow<'a> {
+    /// Outer locals the statements read before writing them.
+    pub parameters: Vec<&'a VarDecl>,
+    /// Outer locals the selection overwrites before reading, which the new
+    /// method has to declare for itself.
+    pub locals: Vec<&'a VarDecl>,
+    /// The one local the statements define that the code after them reads.
+    pub returned: Option<&'a VarDecl>,
+    pub returned_declared_inside: bool,
+}
+
+/// Work out which locals of `func` flow into `statements`, which span
+/// `selection`, and which single local flows back out.
+pub fn data_flow<'a>(
+    func: &'a FunctionDef,
+    statements: &'a [Statement],
+    selection: &Span,
+) -> Result<DataFlow<'a>, RefactorError> {
+    let outer_decls: Vec<&VarDecl> = function_declarations(func)
+        .into_iter()
+        .filter(|decl| !selection.contains(&decl.name_span))
+        .collect();
+    let inner_decls: Vec<&VarDecl> = statements.iter().flat_map(statement_declarations).collect();
+    let usages_after: Vec<_> = function_usages(func)
+        .into_iter()
+        .filter(|usage| usage.span.start >= selection.end)
+        .collect();
+
+    // A local declared outside the selection is a parameter unless every path
+    // through the selection writes it before reading it.
+    let mut parameters: Vec<&VarDecl> = Vec::new();
+    let mut written: Vec<&str> = Vec::new();
+    let mut definitely_written: Vec<String> = Vec::new();
+    for stmt in statements {
+        let unconditional = matches!(
+            stmt,
+            Statement::ExpressionStatement(_) | Statement::DeclStmt(_)
+        );
+        for usage in statement_usages(stmt) {
+            let Some(decl) = outer_decls.iter().find(|decl| decl.name == usage.name) else {
+                continue;
+            };
+            if usage.access.reads()
+                && !definitely_written.contains(&usage.name)
+                && !parameters.contains(decl)
+            {
+                parameters.push(decl);
+            }
+            if usage.access.writes() {
+                if !written.contains(&decl.name.as_str()) {
+                    written.push(&decl.name);
+                }
+                if unconditional {
+                    definitely_written.push(usage.name);
+                }
+            }
+        }
+    }
+
+    // Anything the selection defines that is still needed afterwards has to be
+    // handed back to the caller. A loop around the selection reads its values
+    // again on the next iteration, even from before the selection.
+    let loops = enclosing_loops(func, selection);
+    let mut returned: Vec<(&VarDecl, bool)> = Vec::new();
+    for name in &written {
+        let decl = outer_decls.iter().find(|decl| decl.name == *name).unwrap();
+        let read_after = usages_after
+            .iter()
+            .any(|usage| usage.name == *name && usage.access.reads());
+        let carried = loops
+            .iter()
+            .filter(|stmt| !stmt.span().contains(&decl.name_span))
+            .flat_map(|stmt| statement_usages(stmt))
+            .any(|usage| {
+                usage.name == *name && usage.access.reads() && !selection.contains(&usage.span)
+            });
+        if read_after || carried {
+            returned.push((decl, false));
+        }
+    }
+    for decl in &inner_decls {
+        if usages_after.iter().any(|usage| usage.name == decl.name) {
+            returned.push((decl, true));
+        }
+    }
+
+    if returned.len() > 1 {
+        let names = returned
+            .iter()
+            .map(|(decl, _)| decl.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        return Err(RefactorError::Unsupported(format!(
+            "the selection assigns several locals that are used afterwards: {}",
+            names
+        )));
+    }
+
+    let locals: Vec<&VarDecl> = outer_decls
+        .iter()
+        .filter(|decl| written.contains(&decl.name.as_str()) && !parameters.contains(decl))
+        .copied()
+        .collect();
+
+    explicitly_typed(
+        parameters
+            .iter()
+            .chain(locals.iter())
+            .chain(returned.iter().map(|(decl, _)| decl)),
+    )?;
+
+    let (returned, returned_declared_inside) = match returned.pop() {
+        Some((decl, inside)) => (Some(decl), inside),
+        None => (None, false),
+    };
+
+    Ok(DataFlow {
+        parameters,
+        locals,
+        returned,
+        returned_declared_inside,
+    })
+}
+
+/// The loops of `func` that contain `selection` without being part of it.
+fn enclosing_loops<'a>(func: &'a FunctionDef, selection: &Span) -> Vec<&'a Statement> {
+    let mut loops = Vec::new();
+    let mut blocks: Vec<&Block> = function_blocks(func).collect();
+    while let Some(block) = blocks.pop() {
+        for stmt in &block.statements {
+            if !stmt.span().contains(selection) || selection.contains(stmt.span()) {
+                continue;
+            }
+            if matches!(
+                stmt,
+                Statement::WhileLoop(_) | Statement::ForLoop(_) | Statement::ForEachLoop(_)
+            ) {
+                loops.push(stmt);
+            }
+            blocks.extend(nested_blocks(stmt));
+        }
+    }
+    loops
+}
+
+/// The locals of `func` that `condition` reads, in order of first use.
+///
+/// A condition that assigns a local cannot be moved out of the method, since
+/// the assignment would no longer be visible to the code after it.
+pub fn condition_inputs<'a>(
+    func: &'a FunctionDef,
+    condition: &Expression,
+) -> Result<Vec<&'a VarDecl>, RefactorError> {
+    let decls = function_declarations(func);
+    let mut inputs: Vec<&VarDecl> = Vec::new();
+    for usage in expression_usages(condition) {
+        let Some(decl) = decls.iter().find(|decl| decl.name == usage.name) else {
+            continue;
+        };
+        if usage.access.writes() {
+            return Err(RefactorError::Unsupported(format!(
+                "the condition assigns `{}`",
+                usage.name
+            )));
+        }
+        if !inputs.contains(decl) {
+            inputs.push(decl);
+        }
+    }
+    explicitly_typed(inputs.iter())?;
+    Ok(inputs)
+}
+
+/// Fail unless every local in `decls` has a type that can be written in a
+/// method signature.
+fn explicitly_typed<'a, 'b: 'a>(
+    decls: impl Iterator<Item = &'a &'b VarDecl>,
+) -> Result<(), RefactorError> {
+    for decl in decls {
+        if decl.var_type.as_deref().is_none_or(|ty| ty == "var") {
+            return Err(RefactorError::Unsupported(format!(
+                "the type of `{}` is implicit",
+                decl.name
+            )));
+        }
+    }
+    Ok(())
+}
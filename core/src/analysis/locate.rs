@@ -0,0 +1,538 @@
+use crate::analysis::variables::raw_tokens;
+use crate::RefactorError;
+use std::ops::Range;
+use uast::*;
+
+/// A function found in the UAST together with the class that declares it.
+pub struct FunctionContext<'a> {
+    pub class: Option<&'a ClassDef>,
+    pub function: &'a FunctionDef,
+}
+
+/// A contiguous run of statements inside a single block.
+pub struct StatementRange<'a> {
+    pub block: &'a Block,
+    pub range: Range<usize>,
+}
+
+impl<'a> StatementRange<'a> {
+    pub fn statements(&self) -> &'a [Statement] {
+        &self.block.statements[self.range.clone()]
+    }
+
+    /// The span from the start of the first statement to the end of the last.
+    pub fn span(&self) -> Span {
+        let statements = self.statements();
+        Span {
+            start: statements[0].span().start,
+            end: statements[statements.len() - 1].span().end,
+        }
+    }
+}
+
+/// Find the innermost function whose declaration contains `span`.
+pub fn enclosing_function<'a>(node: &'a TopLevel, span: &Span) -> Option<FunctionContext<'a>> {
+    find_function(node, None, &|func| func.span.contains(span))
+}
+
+/// Find the first function matching `predicate`, searching classes and modules depth first.
+pub fn find_function<'a>(
+    node: &'a TopLevel,
+    class: Option<&'a ClassDef>,
+    predicate: &dyn Fn(&FunctionDef) -> bool,
+) -> Option<FunctionContext<'a>> {
+    match node {
+        TopLevel::Class(class_def) => class_def
+            .body
+            .iter()
+            .flatten()
+            .find_map(|item| find_function(item, Some(class_def), predicate)),
+        TopLevel::Module(module) => module
+            .body
+            .iter()
+            .find_map(|item| find_function(item, class, predicate)),
+        TopLevel::Function(func) if predicate(func) => Some(FunctionContext {
+            class,
+            function: func,
+        }),
+        _ => None,
+    }
+}
+
+/// Every function declared anywhere in `node`.
+pub fn functions(node: &TopLevel) -> Vec<&FunctionDef> {
+    match node {
+        TopLevel::Class(class) => class.body.iter().flatten().flat_map(functions).collect(),
+        TopLevel::Module(module) => module.body.iter().flat_map(functions).collect(),
+        TopLevel::Function(func) => vec![func],
+        _ => vec![],
+    }
+}
+
+/// The blocks that make up the body of a function.
+pub fn function_blocks(func: &FunctionDef) -> impl Iterator<Item = &Block> {
+    func.body.iter().flatten().filter_map(|item| match item {
+        FunctionBodyItems::Block(block) => Some(block),
+        _ => None,
+    })
+}
+
+/// The blocks directly nested inside a statement.
+pub fn nested_blocks(stmt: &Statement) -> Vec<&Block> {
+    match stmt {
+        Statement::IfStatement(if_stmt) => {
+            let mut blocks = vec![if_stmt.consequence.as_ref()];
+            if let Some(alt) = &if_stmt.alternative {
+                blocks.push(alt.as_ref());
+            }
+            blocks
+        }
+        Statement::WhileLoop(w) => vec![w.body.as_ref()],
+        Statement::ForLoop(f) => vec![f.body.as_ref()],
+        Statement::ForEachLoop(f) => vec![f.body.as_ref()],
+        Statement::SwitchStatement(switch) => switch
+            .sections
+            .iter()
+            .map(|section| &section.body)
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// The expressions a statement evaluates itself, excluding nested blocks.
+/// Each is paired with whether it belongs to a loop header and is therefore
+/// evaluated on every iteration.
+pub fn statement_expressions(stmt: &Statement) -> Vec<(&Expression, bool)> {
+    match stmt {
+        Statement::DeclStmt(decl_stmt) => decl_stmt
+            .var_decls
+            .iter()
+            .filter_map(|var| var.value.as_deref())
+            .map(|value| (value, false))
+            .collect(),
+        Statement::IfStatement(if_stmt) => vec![(if_stmt.condition.as_ref(), false)],
+        Statement::ReturnStatement(ret) => {
+            ret.value.iter().map(|val| (val.as_ref(), false)).collect()
+        }
+        Statement::ExpressionStatement(expr) => vec![(expr.expression.as_ref(), false)],
+        Statement::WhileLoop(w) => vec![(w.condition.as_ref(), true)],
+        // The collection is evaluated once, before the first iteration.
+        Statement::ForEachLoop(f) => vec![(f.collection.as_ref(), false)],
+        Statement::ForLoop(f) => {
+            let mut exprs: Vec<(&Expression, bool)> = f
+                .initializer
+                .iter()
+                .flat_map(|init| statement_expressions(init))
+                .collect();
+            exprs.extend(f.condition.iter().map(|cond| (cond.as_ref(), true)));
+            exprs.extend(f.update.iter().map(|update| (update.as_ref(), true)));
+            exprs
+        }
+        Statement::SwitchStatement(switch) => {
+            let mut exprs = vec![(switch.value.as_ref(), false)];
+            exprs.extend(
+                switch
+                    .sections
+                    .iter()
+                    .flat_map(|section| &section.labels)
+                    .filter_map(|label| match label {
+                        SwitchLabel::Case(value) => Some((value, false)),
+                        SwitchLabel::Default(_) => None,
+                    }),
+            );
+            exprs
+        }
+        Statement::Unknown { .. } => vec![],
+    }
+}
+
+/// The expressions directly nested inside `expr`.
+pub fn subexpressions(expr: &Expression) -> Vec<&Expression> {
+    match expr {
+        Expression::BinaryOp(op) => vec![&op.left, &op.right],
+        Expression::UnaryOp(op) => vec![&op.operand],
+        Expression::Assignment(assign) => vec![&assign.left, &assign.right],
+        Expression::Invocation(inv) => std::iter::once(inv.function.as_ref())
+            .chain(&inv.arguments)
+            .collect(),
+        Expression::MemberAccess(ma) => vec![&ma.expression],
+        Expression::Conditional(cond) => {
+            vec![&cond.condition, &cond.consequence, &cond.alternative]
+        }
+        Expression::Lambda(lambda) => match &lambda.body {
+            LambdaBody::Expression(body) => vec![body],
+            LambdaBody::Block(_) => vec![],
+        },
+        Expression::Identifier(..) | Expression::Literal(..) | Expression::Raw { .. } => vec![],
+    }
+}
+
+/// `stmt` and every statement inside it, those in the block bodies of its
+/// lambdas included, each before the statements inside it.
+pub fn statement_tree(stmt: &Statement) -> Vec<&Statement> {
+    let mut found = vec![stmt];
+    for (expr, _) in statement_expressions(stmt) {
+        for block in lambda_blocks(expr) {
+            found.extend(block_statements(block));
+        }
+    }
+    for block in nested_blocks(stmt) {
+        found.extend(block_statements(block));
+    }
+    found
+}
+
+/// Every statement in `block`, as with [`statement_tree`].
+pub fn block_statements(block: &Block) -> Vec<&Statement> {
+    block.statements.iter().flat_map(statement_tree).collect()
+}
+
+/// `expr` and every expression nested in it, each before the ones inside it.
+/// The block bodies of lambdas are left out, as their statements are found
+/// by [`statement_tree`].
+pub fn expression_tree(expr: &Expression) -> Vec<&Expression> {
+    let mut found = vec![expr];
+    for nested in subexpressions(expr) {
+        found.extend(expression_tree(nested));
+    }
+    found
+}
+
+/// The block bodies of the lambdas in `expr` that are not inside another
+/// lambda's block body.
+pub fn lambda_blocks(expr: &Expression) -> Vec<&Block> {
+    expression_tree(expr)
+        .into_iter()
+        .filter_map(|expr| match expr {
+            Expression::Lambda(Lambda {
+                body: LambdaBody::Block(block),
+                ..
+            }) => Some(block),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Every expression evaluated anywhere in `stmt`, nested statements and
+/// lambda bodies included.
+pub fn statement_tree_expressions(stmt: &Statement) -> Vec<&Expression> {
+    statement_tree(stmt)
+        .into_iter()
+        .flat_map(statement_expressions)
+        .flat_map(|(expr, _)| expression_tree(expr))
+        .collect()
+}
+
+/// `expr` and every expression inside it, those in the statements of lambda
+/// block bodies included.
+pub fn all_expressions(expr: &Expression) -> Vec<&Expression> {
+    let mut found = expression_tree(expr);
+    for block in lambda_blocks(expr) {
+        for stmt in &block.statements {
+            found.extend(statement_tree_expressions(stmt));
+        }
+    }
+    found
+}
+
+/// Every expression evaluated in the body of `func`, whether it is a block
+/// or a single expression.
+pub fn function_expressions(func: &FunctionDef) -> Vec<&Expression> {
+    func.body
+        .iter()
+        .flatten()
+        .flat_map(|item| match item {
+            FunctionBodyItems::Block(block) => block
+                .statements
+                .iter()
+                .flat_map(statement_tree_expressions)
+                .collect(),
+            FunctionBodyItems::Expression(expr) => all_expressions(expr),
+            FunctionBodyItems::TopLevel(_) => vec![],
+        })
+        .collect()
+}
+
+/// Every expression in the code of `node`: the bodies of its methods and
+/// property accessors, the initializers of its fields and properties, and its
+/// top-level statements.
+pub fn code_expressions(node: &TopLevel) -> Vec<&Expression> {
+    match node {
+        TopLevel::Class(class) => class
+            .body
+            .iter()
+            .flatten()
+            .flat_map(code_expressions)
+            .collect(),
+        TopLevel::Module(module) => module.body.iter().flat_map(code_expressions).collect(),
+        TopLevel::Function(func) => function_expressions(func),
+        TopLevel::Field(field) => field
+            .var_decls
+            .iter()
+            .filter_map(|var| var.value.as_deref())
+            .flat_map(all_expressions)
+            .collect(),
+        TopLevel::Property(property) => property
+            .accessors
+            .iter()
+            .flat_map(function_expressions)
+            .chain(
+                property
+                    .value
+                    .iter()
+                    .flat_map(|value| all_expressions(value)),
+            )
+            .collect(),
+        TopLevel::Statement(stmt) => statement_tree_expressions(stmt),
+        TopLevel::Unknown { .. } => vec![],
+    }
+}
+
+/// An expression found by its span, with the statement that evaluates it.
+pub struct ExpressionLocation<'a> {
+    pub block: &'a Block,
+    /// Index in `block` of `statement`.
+    pub index: usize,
+    /// The innermost statement whose own expressions contain the expression.
+    pub statement: &'a Statement,
+    pub expression: &'a Expression,
+    pub in_loop_header: bool,
+    /// Whether the expression is the target of an assignment or increment.
+    pub is_written: bool,
+}
+
+/// Find the expression spanning exactly `span` in the body of a function.
+pub fn locate_expression<'a>(func: &'a FunctionDef, span: &Span) -> Option<ExpressionLocation<'a>> {
+    function_blocks(func).find_map(|block| locate_in_block(block, span))
+}
+
+fn locate_in_block<'a>(block: &'a Block, span: &Span) -> Option<ExpressionLocation<'a>> {
+    for (index, stmt) in block.statements.iter().enumerate() {
+        if !stmt.span().contains(span) {
+            continue;
+        }
+        for (expr, in_loop_header) in statement_expressions(stmt) {
+            if let Some((expression, is_written)) = find_by_span(expr, span, false) {
+                return Some(ExpressionLocation {
+                    block,
+                    index,
+                    statement: stmt,
+                    expression,
+                    in_loop_header,
+                    is_written,
+                });
+            }
+        }
+        return nested_blocks(stmt)
+            .into_iter()
+            .find_map(|nested| locate_in_block(nested, span));
+    }
+    None
+}
+
+fn find_by_span<'a>(
+    expr: &'a Expression,
+    span: &Span,
+    written: bool,
+) -> Option<(&'a Expression, bool)> {
+    if expr.span() == span {
+        return Some((expr, written));
+    }
+    if !expr.span().contains(span) {
+        return None;
+    }
+    match expr {
+        Expression::BinaryOp(op) => {
+            find_by_span(&op.left, span, false).or_else(|| find_by_span(&op.right, span, false))
+        }
+        Expression::UnaryOp(op) => {
+            let writes = !matches!(op.operator, UnaryOperator::Negate | UnaryOperator::Not);
+            find_by_span(&op.operand, span, writes)
+        }
+        Expression::Assignment(assign) => find_by_span(&assign.left, span, true)
+            .or_else(|| find_by_span(&assign.right, span, false)),
+        Expression::Invocation(inv) => find_by_span(&inv.function, span, false).or_else(|| {
+            inv.arguments
+                .iter()
+                .find_map(|arg| find_by_span(arg, span, false))
+        }),
+        Expression::MemberAccess(ma) => find_by_span(&ma.expression, span, false),
+        Expression::Conditional(cond) => find_by_span(&cond.condition, span, false)
+            .or_else(|| find_by_span(&cond.consequence, span, false))
+            .or_else(|| find_by_span(&cond.alternative, span, false)),
+        _ => None,
+    }
+}
+
+/// A local declaration statement found inside a block.
+pub struct DeclarationLocation<'a> {
+    pub block: &'a Block,
+    /// Index in `block` of `statement`.
+    pub index: usize,
+    pub statement: &'a DeclStmt,
+    pub var: &'a VarDecl,
+}
+
+/// Find the local declaration whose name covers `offset`.
+pub fn locate_declaration(func: &FunctionDef, offset: usize) -> Option<DeclarationLocation<'_>> {
+    function_blocks(func).find_map(|block| declaration_in_block(block, offset))
+}
+
+fn declaration_in_block(block: &Block, offset: usize) -> Option<DeclarationLocation<'_>> {
+    for (index, stmt) in block.statements.iter().enumerate() {
+        let span = stmt.span();
+        if offset < span.start || offset > span.end {
+            continue;
+        }
+        if let Statement::DeclStmt(decl_stmt) = stmt {
+            let var = decl_stmt
+                .var_decls
+                .iter()
+                .find(|var| var.name_span.start <= offset && offset <= var.name_span.end)?;
+            return Some(DeclarationLocation {
+                block,
+                index,
+                statement: decl_stmt,
+                var,
+            });
+        }
+        if let Some(found) = nested_blocks(stmt)
+            .into_iter()
+            .find_map(|nested| declaration_in_block(nested, offset))
+        {
+            return Some(found);
+        }
+    }
+    None
+}
+
+/// The innermost statement in `block` that contains `span`.
+pub fn innermost_statement<'a>(block: &'a Block, span: &Span) -> Option<&'a Statement> {
+    let stmt = block
+        .statements
+        .iter()
+        .find(|stmt| stmt.span().contains(span))?;
+    nested_blocks(stmt)
+        .into_iter()
+        .find_map(|nested| innermost_statement(nested, span))
+        .or(Some(stmt))
+}
+
+/// The innermost `if` statement in `block` containing `span`.
+pub fn innermost_if<'a>(block: &'a Block, span: &Span) -> Option<&'a IfStatement> {
+    let stmt = block
+        .statements
+        .iter()
+        .find(|stmt| stmt.span().contains(span))?;
+    let nested = nested_blocks(stmt)
+        .into_iter()
+        .find_map(|nested| innermost_if(nested, span));
+    match stmt {
+        Statement::IfStatement(if_stmt) => nested.or(Some(if_stmt)),
+        _ => nested,
+    }
+}
+
+/// The innermost `for`, `foreach` or `while` loop in `block` containing
+/// `span`, as the block holding it and its index there.
+pub fn innermost_loop<'a>(block: &'a Block, span: &Span) -> Option<(&'a Block, usize)> {
+    let index = block
+        .statements
+        .iter()
+        .position(|stmt| stmt.span().contains(span))?;
+    let stmt = &block.statements[index];
+    let nested = nested_blocks(stmt)
+        .into_iter()
+        .find_map(|nested| innermost_loop(nested, span));
+    let is_loop = matches!(
+        stmt,
+        Statement::WhileLoop(_) | Statement::ForLoop(_) | Statement::ForEachLoop(_)
+    );
+    nested.or(is_loop.then_some((block, index)))
+}
+
+/// Whether a statement is or contains a `return`, including one mentioned by
+/// unlowered source such as a `try` block.
+pub fn contains_return(stmt: &Statement) -> bool {
+    match stmt {
+        Statement::ReturnStatement(_) => true,
+        Statement::Unknown { source, span } => mentions_keyword(source, span, "return"),
+        _ => nested_blocks(stmt)
+            .into_iter()
+            .any(|block| block.statements.iter().any(contains_return)),
+    }
+}
+
+/// The keyword of a `break` or `continue` in `stmt` that leaves it for an
+/// enclosing loop. Jumps inside nested loops stay there, and so does a
+/// `break` inside a `switch`. Unlowered statements, such as `try` or `lock`,
+/// are assumed to let any jump they mention out.
+pub fn jump_out(stmt: &Statement) -> Option<&'static str> {
+    jump(stmt, false)
+}
+
+fn jump(stmt: &Statement, in_switch: bool) -> Option<&'static str> {
+    match stmt {
+        Statement::Unknown { source, span } => {
+            if !in_switch && mentions_keyword(source, span, "break") {
+                Some("break")
+            } else if mentions_keyword(source, span, "continue") {
+                Some("continue")
+            } else {
+                None
+            }
+        }
+        Statement::WhileLoop(_) | Statement::ForLoop(_) | Statement::ForEachLoop(_) => None,
+        Statement::SwitchStatement(_) => nested_blocks(stmt)
+            .into_iter()
+            .flat_map(|block| &block.statements)
+            .find_map(|nested| jump(nested, true)),
+        _ => nested_blocks(stmt)
+            .into_iter()
+            .flat_map(|block| &block.statements)
+            .find_map(|nested| jump(nested, in_switch)),
+    }
+}
+
+fn mentions_keyword(source: &str, span: &Span, keyword: &str) -> bool {
+    raw_tokens(source, span)
+        .iter()
+        .any(|token| token.text == keyword)
+}
+
+/// Resolve a byte range to the whole statements it covers inside `block`.
+///
+/// The selection may start or end in whitespace between statements, but it
+/// must not cut a statement in half. When the selection falls entirely inside
+/// a single statement the search continues in that statement's blocks.
+pub fn select_statements<'a>(
+    block: &'a Block,
+    selection: &Span,
+) -> Result<Option<StatementRange<'a>>, RefactorError> {
+    let mut selected: Option<Range<usize>> = None;
+
+    for (i, stmt) in block.statements.iter().enumerate() {
+        let span = stmt.span();
+        if selection.contains(span) {
+            selected = Some(match selected {
+                Some(range) => range.start..i + 1,
+                None => i..i + 1,
+            });
+        } else if span.contains(selection) {
+            for nested in nested_blocks(stmt) {
+                if let Some(found) = select_statements(nested, selection)? {
+                    return Ok(Some(found));
+                }
+            }
+            return Err(RefactorError::InvalidSelection(
+                "the selection does not cover whole statements".to_string(),
+            ));
+        } else if span.overlaps(selection) {
+            return Err(RefactorError::InvalidSelection(
+                "the selection does not cover whole statements".to_string(),
+            ));
+        }
+    }
+
+    Ok(selected.map(|range| StatementRange { block, range }))
+}
@@ -0,0 +1,179 @@
+use crate::analysis::locate::code_expressions;
+use crate::analysis::variables::raw_tokens;
+use crate::text::line_start;
+use crate::{RefactorError, SourceFile};
+use uast::*;
+
+/// A field or property declared by a class.
+#[derive(Debug, Clone, Copy)]
+pub enum Member<'a> {
+    /// One of the fields of a field declaration.
+    Field(&'a FieldDef, &'a VarDecl),
+    Property(&'a PropertyDef),
+}
+
+impl<'a> Member<'a> {
+    pub fn name(&self) -> &'a str {
+        match self {
+            Member::Field(_, var) => &var.name,
+            Member::Property(property) => &property.name,
+        }
+    }
+
+    pub fn name_span(&self) -> &'a Span {
+        match self {
+            Member::Field(_, var) => &var.name_span,
+            Member::Property(property) => &property.name_span,
+        }
+    }
+
+    pub fn var_type(&self) -> Option<&'a str> {
+        match self {
+            Member::Field(_, var) => var.var_type.as_deref(),
+            Member::Property(property) => property.var_type.as_deref(),
+        }
+    }
+
+    pub fn modifiers(&self) -> &'a [String] {
+        let modifiers = match self {
+            Member::Field(field, _) => &field.modifiers,
+            Member::Property(property) => &property.modifiers,
+        };
+        modifiers.as_deref().unwrap_or_default()
+    }
+}
+
+/// The fields and properties declared directly by `class`.
+pub fn members(class: &ClassDef) -> Vec<Member<'_>> {
+    class
+        .body
+        .iter()
+        .flatten()
+        .flat_map(|item| match item {
+            TopLevel::Field(field) => field
+                .var_decls
+                .iter()
+                .map(|var| Member::Field(field, var))
+                .collect(),
+            TopLevel::Property(property) => vec![Member::Property(property)],
+            _ => vec![],
+        })
+        .collect()
+}
+
+/// Every class declared in `node`.
+pub fn classes(node: &TopLevel) -> Vec<&ClassDef> {
+    match node {
+        TopLevel::Class(class) => vec![class],
+        TopLevel::Module(module) => module.body.iter().flat_map(classes).collect(),
+        _ => vec![],
+    }
+}
+
+/// The only class called `name` across `files`, with the index of its file.
+pub fn find_class<'a>(
+    files: &[SourceFile<'a>],
+    name: &str,
+) -> Result<(usize, &'a ClassDef), RefactorError> {
+    let found: Vec<(usize, &ClassDef)> = files
+        .iter()
+        .enumerate()
+        .flat_map(|(i, file)| classes(file.uast).into_iter().map(move |class| (i, class)))
+        .filter(|(_, class)| class.name == name)
+        .collect();
+    match found.as_slice() {
+        [] => Err(RefactorError::NotFound(format!("class `{}`", name))),
+        [found] => Ok(*found),
+        _ => Err(RefactorError::Unsupported(format!(
+            "`{}` is declared more than once",
+            name
+        ))),
+    }
+}
+
+/// Find the class declaring a field or property called `name`.
+pub fn find_member<'a>(node: &'a TopLevel, name: &str) -> Option<(&'a ClassDef, Member<'a>)> {
+    match node {
+        TopLevel::Class(class) => members(class)
+            .into_iter()
+            .find(|member| member.name() == name)
+            .map(|member| (class, member))
+            .or_else(|| {
+                class
+                    .body
+                    .iter()
+                    .flatten()
+                    .find_map(|item| find_member(item, name))
+            }),
+        TopLevel::Module(module) => module.body.iter().find_map(|item| find_member(item, name)),
+        _ => None,
+    }
+}
+
+/// The names of every method, field and property declared directly by `class`.
+/// Constructors are not members in this sense.
+pub fn member_names(class: &ClassDef) -> Vec<&str> {
+    let methods = class.body.iter().flatten().filter_map(|item| match item {
+        TopLevel::Function(func) if func.return_type.is_some() || func.name != class.name => {
+            Some(func.name.as_str())
+        }
+        _ => None,
+    });
+    methods
+        .chain(members(class).into_iter().map(|member| member.name()))
+        .collect()
+}
+
+/// The constructors of `class`, which are lowered as functions named after
+/// it without a return type.
+pub fn constructors(class: &ClassDef) -> Vec<&FunctionDef> {
+    class
+        .body
+        .iter()
+        .flatten()
+        .filter_map(|item| match item {
+            TopLevel::Function(func) if func.return_type.is_none() && func.name == class.name => {
+                Some(func)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Mentions of `name` in the parts of `class` that are not lowered at all,
+/// such as nested types and constructors chaining to another constructor.
+/// Comment lines are skipped.
+pub fn unlowered_class_mentions(class: &ClassDef, source: &str, name: &str) -> Vec<Span> {
+    let lowered: Vec<&Span> = class
+        .body
+        .iter()
+        .flatten()
+        .filter_map(|item| match item {
+            TopLevel::Function(func) => Some(&func.span),
+            TopLevel::Field(field) => Some(&field.span),
+            TopLevel::Property(property) => Some(&property.span),
+            _ => None,
+        })
+        .collect();
+    raw_tokens(&source[class.span.start..class.span.end], &class.span)
+        .into_iter()
+        .filter(|token| token.text == name)
+        .filter(|token| !lowered.iter().any(|span| span.contains(&token.span)))
+        .filter(|token| {
+            let line = &source[line_start(source, token.span.start)..token.span.start];
+            !line.contains("//")
+        })
+        .map(|token| token.span)
+        .collect()
+}
+
+/// Every lowered member access of the form `x.name` in the code of `node`.
+pub fn member_accesses<'a>(node: &'a TopLevel, name: &str) -> Vec<&'a MemberAccess> {
+    code_expressions(node)
+        .into_iter()
+        .filter_map(|expr| match expr {
+            Expression::MemberAccess(ma) if ma.member == name => Some(ma),
+            _ => None,
+        })
+        .collect()
+}
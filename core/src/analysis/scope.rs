@@ -0,0 +1,268 @@
+use crate::analysis::locate::{nested_blocks, statement_expressions, subexpressions};
+use crate::analysis::variables::{expression_usages, statement_usages, Access, Usage};
+use uast::*;
+
+/// An identifier resolved to the local variable or parameter it names.
+#[derive(Debug, Clone)]
+pub struct Reference<'a> {
+    pub span: Span,
+    pub decl: &'a VarDecl,
+    /// How the reference uses the variable, or `None` for the name in the
+    /// declaration itself.
+    pub access: Option<Access>,
+}
+
+/// The result of resolving every identifier in a piece of UAST.
+#[derive(Debug, Default)]
+pub struct Bindings<'a> {
+    pub references: Vec<Reference<'a>>,
+    /// Identifiers that do not name a local or parameter, such as fields,
+    /// types and namespaces.
+    pub unresolved: Vec<Usage>,
+}
+
+impl<'a> Bindings<'a> {
+    /// The declaration of the variable named at `offset`, whether the offset
+    /// falls on the declaration or on any reference to it.
+    pub fn declaration_at(&self, offset: usize) -> Option<&'a VarDecl> {
+        self.references
+            .iter()
+            .find(|r| r.span.start <= offset && offset <= r.span.end)
+            .map(|r| r.decl)
+    }
+
+    /// Every reference to `decl`, including its declaration.
+    pub fn references_to(&self, decl: &VarDecl) -> Vec<&Reference<'a>> {
+        self.references
+            .iter()
+            .filter(|r| std::ptr::eq(r.decl, decl))
+            .collect()
+    }
+}
+
+/// Resolve every identifier in `node` to its declaration.
+///
+/// Each function body gets its own scope, seeded with the parameters, and
+/// every block opens a nested scope. A local is visible from its declaration
+/// to the end of the enclosing block, and inner declarations hide outer ones.
+/// Top-level statements share a single scope.
+pub fn resolve(node: &TopLevel) -> Bindings<'_> {
+    let mut resolver = Resolver::default();
+    resolver.visit_top_level(node);
+    resolver.bindings
+}
+
+/// Resolve every identifier in the body of a single function.
+pub fn resolve_function(func: &FunctionDef) -> Bindings<'_> {
+    let mut resolver = Resolver::default();
+    resolver.visit_function(func);
+    resolver.bindings
+}
+
+#[derive(Default)]
+struct Resolver<'a> {
+    scopes: Vec<Vec<&'a VarDecl>>,
+    bindings: Bindings<'a>,
+}
+
+impl<'a> Resolver<'a> {
+    fn visit_top_level(&mut self, node: &'a TopLevel) {
+        match node {
+            TopLevel::Class(class) => {
+                for item in class.body.iter().flatten() {
+                    self.visit_top_level(item);
+                }
+            }
+            TopLevel::Module(module) => {
+                self.scopes.push(Vec::new());
+                for item in &module.body {
+                    self.visit_top_level(item);
+                }
+                self.scopes.pop();
+            }
+            TopLevel::Function(func) => {
+                // Methods cannot see the locals of top-level statements.
+                let outer = std::mem::take(&mut self.scopes);
+                self.visit_function(func);
+                self.scopes = outer;
+            }
+            TopLevel::Field(field) => {
+                let outer = std::mem::take(&mut self.scopes);
+                for value in field
+                    .var_decls
+                    .iter()
+                    .filter_map(|var| var.value.as_deref())
+                {
+                    self.visit_expression(value);
+                }
+                self.scopes = outer;
+            }
+            TopLevel::Property(property) => {
+                let outer = std::mem::take(&mut self.scopes);
+                for accessor in &property.accessors {
+                    self.visit_function(accessor);
+                }
+                if let Some(value) = &property.value {
+                    self.visit_expression(value);
+                }
+                self.scopes = outer;
+            }
+            TopLevel::Statement(stmt) => {
+                if self.scopes.is_empty() {
+                    self.scopes.push(Vec::new());
+                }
+                self.visit_statement(stmt);
+            }
+            TopLevel::Unknown { .. } => {}
+        }
+    }
+
+    fn visit_function(&mut self, func: &'a FunctionDef) {
+        self.scopes.push(Vec::new());
+        for param in func.parameters.iter().flatten() {
+            self.declare(param);
+        }
+        for item in func.body.iter().flatten() {
+            match item {
+                FunctionBodyItems::Block(block) => self.visit_block(block),
+                FunctionBodyItems::Expression(expr) => self.visit_expression(expr),
+                FunctionBodyItems::TopLevel(_) => {}
+            }
+        }
+        self.scopes.pop();
+    }
+
+    fn visit_block(&mut self, block: &'a Block) {
+        self.scopes.push(Vec::new());
+        for stmt in &block.statements {
+            self.visit_statement(stmt);
+        }
+        self.scopes.pop();
+    }
+
+    fn visit_statement(&mut self, stmt: &'a Statement) {
+        match stmt {
+            Statement::DeclStmt(decl_stmt) => {
+                for var in &decl_stmt.var_decls {
+                    if let Some(val) = &var.value {
+                        self.visit_expression(val);
+                    }
+                    self.declare(var);
+                }
+            }
+            Statement::ForEachLoop(f) => {
+                self.visit_expression(&f.collection);
+                self.scopes.push(Vec::new());
+                self.declare(&f.variable);
+                self.visit_block(&f.body);
+                self.scopes.pop();
+            }
+            Statement::ForLoop(f) => {
+                self.scopes.push(Vec::new());
+                if let Some(init) = &f.initializer {
+                    self.visit_statement(init);
+                }
+                if let Some(cond) = &f.condition {
+                    self.visit_expression(cond);
+                }
+                self.visit_block(&f.body);
+                if let Some(update) = &f.update {
+                    self.visit_expression(update);
+                }
+                self.scopes.pop();
+            }
+            Statement::SwitchStatement(switch) => {
+                self.visit_expression(&switch.value);
+                // All sections of a switch share a single declaration space.
+                self.scopes.push(Vec::new());
+                for section in &switch.sections {
+                    for label in &section.labels {
+                        if let SwitchLabel::Case(value) = label {
+                            self.visit_expression(value);
+                        }
+                    }
+                    for stmt in &section.body.statements {
+                        self.visit_statement(stmt);
+                    }
+                }
+                self.scopes.pop();
+            }
+            Statement::Unknown { .. } => {
+                for usage in statement_usages(stmt) {
+                    self.resolve_usage(usage);
+                }
+            }
+            _ => {
+                for (expr, _) in statement_expressions(stmt) {
+                    self.visit_expression(expr);
+                }
+                for block in nested_blocks(stmt) {
+                    self.visit_block(block);
+                }
+            }
+        }
+    }
+
+    fn visit_expression(&mut self, expr: &'a Expression) {
+        let mut lambdas = Vec::new();
+        outermost_lambdas(expr, &mut lambdas);
+        for usage in expression_usages(expr) {
+            if !lambdas
+                .iter()
+                .any(|lambda| lambda.span.contains(&usage.span))
+            {
+                self.resolve_usage(usage);
+            }
+        }
+        // A lambda's parameters are only visible in its body.
+        for lambda in lambdas {
+            self.scopes.push(Vec::new());
+            for param in &lambda.parameters {
+                self.declare(param);
+            }
+            match &lambda.body {
+                LambdaBody::Expression(body) => self.visit_expression(body),
+                LambdaBody::Block(block) => self.visit_block(block),
+            }
+            self.scopes.pop();
+        }
+    }
+
+    fn declare(&mut self, decl: &'a VarDecl) {
+        self.bindings.references.push(Reference {
+            span: decl.name_span.clone(),
+            decl,
+            access: None,
+        });
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.push(decl);
+        }
+    }
+
+    fn resolve_usage(&mut self, usage: Usage) {
+        let decl = self
+            .scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.iter().rev().find(|decl| decl.name == usage.name));
+        match decl {
+            Some(decl) => self.bindings.references.push(Reference {
+                span: usage.span,
+                decl,
+                access: Some(usage.access),
+            }),
+            None => self.bindings.unresolved.push(usage),
+        }
+    }
+}
+
+fn outermost_lambdas<'a>(expr: &'a Expression, lambdas: &mut Vec<&'a Lambda>) {
+    match expr {
+        Expression::Lambda(lambda) => lambdas.push(lambda),
+        _ => {
+            for nested in subexpressions(expr) {
+                outermost_lambdas(nested, lambdas);
+            }
+        }
+    }
+}
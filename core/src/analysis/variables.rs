@@ -0,0 +1,370 @@
+use crate::analysis::locate::{
+    expression_tree, nested_blocks, statement_expressions, statement_tree, subexpressions,
+};
+use uast::*;
+
+/// How an identifier occurrence touches the variable it names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+impl Access {
+    pub fn reads(&self) -> bool {
+        matches!(self, Access::Read | Access::ReadWrite)
+    }
+
+    pub fn writes(&self) -> bool {
+        matches!(self, Access::Write | Access::ReadWrite)
+    }
+}
+
+/// A single occurrence of a variable name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Usage {
+    pub name: String,
+    pub span: Span,
+    pub access: Access,
+}
+
+/// Collect every variable usage in a statement, in evaluation order.
+///
+/// Declarations with an initializer are reported as a write of the declared
+/// name. Method names in invocations and member names in member accesses are
+/// not variables and are skipped. Source that was not lowered (`Raw` and
+/// `Unknown` nodes) is scanned for identifier tokens, which are conservatively
+/// reported as reads.
+pub fn statement_usages(stmt: &Statement) -> Vec<Usage> {
+    let mut usages = Vec::new();
+    visit_statement(stmt, &mut usages);
+    usages
+}
+
+/// Collect every variable usage in a block, in evaluation order.
+pub fn block_usages(block: &Block) -> Vec<Usage> {
+    let mut usages = Vec::new();
+    visit_block(block, &mut usages);
+    usages
+}
+
+/// Collect every variable usage in an expression, in evaluation order.
+pub fn expression_usages(expr: &Expression) -> Vec<Usage> {
+    let mut usages = Vec::new();
+    visit_expression(expr, Access::Read, &mut usages);
+    usages
+}
+
+/// Collect every variable usage in the body of a function, in evaluation order.
+pub fn function_usages(func: &FunctionDef) -> Vec<Usage> {
+    let mut usages = Vec::new();
+    if let Some(body) = &func.body {
+        for item in body {
+            match item {
+                FunctionBodyItems::Block(block) => visit_block(block, &mut usages),
+                FunctionBodyItems::Expression(expr) => {
+                    visit_expression(expr, Access::Read, &mut usages)
+                }
+                FunctionBodyItems::TopLevel(_) => {}
+            }
+        }
+    }
+    usages
+}
+
+/// Collect the local variables declared anywhere inside a statement.
+pub fn statement_declarations(stmt: &Statement) -> Vec<&VarDecl> {
+    let mut decls = Vec::new();
+    collect_declarations(stmt, &mut decls);
+    decls
+}
+
+/// Collect the parameters and local variables declared by a function.
+pub fn function_declarations(func: &FunctionDef) -> Vec<&VarDecl> {
+    let mut decls: Vec<&VarDecl> = func.parameters.iter().flatten().collect();
+    if let Some(body) = &func.body {
+        for item in body {
+            if let FunctionBodyItems::Block(block) = item {
+                for stmt in &block.statements {
+                    collect_declarations(stmt, &mut decls);
+                }
+            }
+        }
+    }
+    decls
+}
+
+fn collect_declarations<'a>(stmt: &'a Statement, decls: &mut Vec<&'a VarDecl>) {
+    for stmt in statement_tree(stmt) {
+        for (expr, _) in statement_expressions(stmt) {
+            for nested in expression_tree(expr) {
+                if let Expression::Lambda(lambda) = nested {
+                    decls.extend(lambda.parameters.iter());
+                }
+            }
+        }
+        match stmt {
+            Statement::DeclStmt(decl_stmt) => decls.extend(decl_stmt.var_decls.iter()),
+            Statement::ForEachLoop(f) => decls.push(&f.variable),
+            Statement::ForLoop(f) => {
+                if let Some(Statement::DeclStmt(init)) = f.initializer.as_deref() {
+                    decls.extend(init.var_decls.iter());
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn visit_block(block: &Block, usages: &mut Vec<Usage>) {
+    for stmt in &block.statements {
+        visit_statement(stmt, usages);
+    }
+}
+
+fn visit_statement(stmt: &Statement, usages: &mut Vec<Usage>) {
+    match stmt {
+        Statement::DeclStmt(decl_stmt) => {
+            for var in &decl_stmt.var_decls {
+                if let Some(val) = &var.value {
+                    visit_expression(val, Access::Read, usages);
+                    usages.push(Usage {
+                        name: var.name.clone(),
+                        span: var.name_span.clone(),
+                        access: Access::Write,
+                    });
+                }
+            }
+        }
+        Statement::ForEachLoop(f) => {
+            visit_expression(&f.collection, Access::Read, usages);
+            // The loop assigns the variable before every iteration.
+            usages.push(Usage {
+                name: f.variable.name.clone(),
+                span: f.variable.name_span.clone(),
+                access: Access::Write,
+            });
+            visit_block(&f.body, usages);
+        }
+        Statement::ForLoop(f) => {
+            if let Some(init) = &f.initializer {
+                visit_statement(init, usages);
+            }
+            if let Some(cond) = &f.condition {
+                visit_expression(cond, Access::Read, usages);
+            }
+            visit_block(&f.body, usages);
+            if let Some(update) = &f.update {
+                visit_expression(update, Access::Read, usages);
+            }
+        }
+        Statement::Unknown { source, span } => scan_raw(source, span, usages),
+        _ => {
+            for (expr, _) in statement_expressions(stmt) {
+                visit_expression(expr, Access::Read, usages);
+            }
+            for block in nested_blocks(stmt) {
+                visit_block(block, usages);
+            }
+        }
+    }
+}
+
+/// Visit an expression. `access` is how the value of the expression itself is
+/// used, which only matters when the expression is a plain identifier.
+fn visit_expression(expr: &Expression, access: Access, usages: &mut Vec<Usage>) {
+    match expr {
+        Expression::Identifier(id, span) => usages.push(Usage {
+            name: id.clone(),
+            span: span.clone(),
+            access,
+        }),
+        Expression::UnaryOp(op) => {
+            let access = match op.operator {
+                UnaryOperator::PreIncrement
+                | UnaryOperator::PostIncrement
+                | UnaryOperator::PreDecrement
+                | UnaryOperator::PostDecrement => Access::ReadWrite,
+                UnaryOperator::Negate | UnaryOperator::Not => Access::Read,
+            };
+            visit_expression(&op.operand, access, usages);
+        }
+        Expression::Assignment(assign) => {
+            visit_expression(&assign.right, Access::Read, usages);
+            let access = match assign.operator {
+                AssignmentOperator::Assign => Access::Write,
+                _ => Access::ReadWrite,
+            };
+            visit_expression(&assign.left, access, usages);
+        }
+        Expression::Invocation(inv) => {
+            if !matches!(*inv.function, Expression::Identifier(..)) {
+                visit_expression(&inv.function, Access::Read, usages);
+            }
+            for arg in &inv.arguments {
+                visit_expression(arg, Access::Read, usages);
+            }
+        }
+        Expression::Lambda(lambda) => {
+            // Every call of the lambda assigns its parameters.
+            usages.extend(lambda.parameters.iter().map(|param| Usage {
+                name: param.name.clone(),
+                span: param.name_span.clone(),
+                access: Access::Write,
+            }));
+            match &lambda.body {
+                LambdaBody::Expression(body) => visit_expression(body, Access::Read, usages),
+                LambdaBody::Block(block) => visit_block(block, usages),
+            }
+        }
+        Expression::Raw { source, span } => scan_raw(source, span, usages),
+        _ => {
+            for nested in subexpressions(expr) {
+                visit_expression(nested, Access::Read, usages);
+            }
+        }
+    }
+}
+
+/// Report every identifier-like token of unlowered source as a read, except
+/// member names that directly follow a `.`.
+fn scan_raw(source: &str, span: &Span, usages: &mut Vec<Usage>) {
+    usages.extend(
+        raw_tokens(source, span)
+            .into_iter()
+            .filter(|token| !token.after_dot)
+            .map(|token| Usage {
+                name: token.text,
+                span: token.span,
+                access: Access::Read,
+            }),
+    );
+}
+
+/// An identifier-like token found in source that was not lowered.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawToken {
+    pub text: String,
+    pub span: Span,
+    /// Whether the token directly follows a `.`, i.e. names a member.
+    pub after_dot: bool,
+}
+
+/// Split unlowered source into identifier-like tokens.
+///
+/// String and character literals are skipped, except for interpolated strings
+/// whose holes may reference variables.
+pub fn raw_tokens(source: &str, span: &Span) -> Vec<RawToken> {
+    let mut tokens = Vec::new();
+    let bytes = source.as_bytes();
+    let mut i = 0;
+    let mut after_dot = false;
+
+    while i < bytes.len() {
+        let c = bytes[i];
+        if c == b'"' || c == b'\'' {
+            let interpolated = c == b'"'
+                && source[..i]
+                    .bytes()
+                    .rev()
+                    .take_while(|b| *b == b'$' || *b == b'@')
+                    .any(|b| b == b'$');
+            let mut j = i + 1;
+            while j < bytes.len() && bytes[j] != c {
+                if bytes[j] == b'\\' {
+                    j += 1;
+                }
+                j += 1;
+            }
+            let end = j.min(bytes.len());
+            if interpolated {
+                tokens.extend(raw_tokens(
+                    &source[i + 1..end],
+                    &Span {
+                        start: span.start + i + 1,
+                        end: span.start + end,
+                    },
+                ));
+            }
+            i = j + 1;
+            after_dot = false;
+        } else if c.is_ascii_alphabetic() || c == b'_' {
+            let start = i;
+            while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                i += 1;
+            }
+            tokens.push(RawToken {
+                text: source[start..i].to_string(),
+                span: Span {
+                    start: span.start + start,
+                    end: span.start + i,
+                },
+                after_dot,
+            });
+            after_dot = false;
+        } else if c.is_ascii_digit() {
+            while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'.') {
+                i += 1;
+            }
+            after_dot = false;
+        } else {
+            if !c.is_ascii_whitespace() {
+                after_dot = c == b'.';
+            }
+            i += 1;
+        }
+    }
+
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assignment_reads_right_before_writing_left() {
+        // x += y
+        let expr = Expression::Assignment(Assignment {
+            left: Box::new(Expression::Identifier(
+                "x".to_string(),
+                Span { start: 0, end: 1 },
+            )),
+            operator: AssignmentOperator::AddAssign,
+            right: Box::new(Expression::Identifier(
+                "y".to_string(),
+                Span { start: 5, end: 6 },
+            )),
+            span: Span { start: 0, end: 6 },
+        });
+
+        let usages = expression_usages(&expr);
+
+        assert_eq!(usages.len(), 2);
+        assert_eq!(usages[0].name, "y");
+        assert_eq!(usages[0].access, Access::Read);
+        assert_eq!(usages[1].name, "x");
+        assert_eq!(usages[1].access, Access::ReadWrite);
+    }
+
+    #[test]
+    fn test_raw_source_skips_strings_and_members() {
+        // Console.WriteLine("total " + total, $"{count}")
+        let source = r#"Console.WriteLine("total " + total, $"{count}")"#;
+        let expr = Expression::Raw {
+            source: source.to_string(),
+            span: Span {
+                start: 10,
+                end: 10 + source.len(),
+            },
+        };
+
+        let names: Vec<String> = expression_usages(&expr)
+            .into_iter()
+            .map(|usage| usage.name)
+            .collect();
+
+        assert_eq!(names, vec!["Console", "total", "count"]);
+    }
+}
@@ -1,10 +1,72 @@
+use std::fmt;
 use uast::TopLevel;
 
+pub mod analysis {
+    pub mod calls;
+    pub mod def_use;
+    pub mod flow;
+    pub mod locate;
+    pub mod members;
+    pub mod scope;
+    pub mod variables;
+}
+
 pub mod refactorings {
+    pub mod change_signature;
+    pub mod consolidate_conditional;
+    pub mod decompose_conditional;
+    pub mod encapsulate_field;
+    pub mod extract_class;
+    pub mod extract_function;
+    pub mod extract_variable;
+    pub mod inline_class;
+    pub mod inline_function;
+    pub mod inline_variable;
+    pub mod introduce_parameter_object;
+    pub mod introduce_special_case;
+    pub mod invert_if;
+    pub mod move_field;
+    pub mod move_method;
+    pub mod rename_field;
+    pub mod rename_method;
     pub mod rename_variable;
+    pub mod replace_conditional_with_polymorphism;
+    pub mod replace_loop_with_pipeline;
+    pub mod replace_nested_conditional_with_guard_clauses;
+    pub mod replace_temp_with_query;
+    pub mod slide_statements;
+    pub mod split_loop;
+    pub mod split_variable;
 }
 
+mod syntax;
+mod text;
+
+pub use refactorings::change_signature::{ChangeSignature, ParameterSpec};
+pub use refactorings::consolidate_conditional::ConsolidateConditional;
+pub use refactorings::decompose_conditional::DecomposeConditional;
+pub use refactorings::encapsulate_field::EncapsulateField;
+pub use refactorings::extract_class::ExtractClass;
+pub use refactorings::extract_function::ExtractFunction;
+pub use refactorings::extract_variable::ExtractVariable;
+pub use refactorings::inline_class::InlineClass;
+pub use refactorings::inline_function::InlineFunction;
+pub use refactorings::inline_variable::InlineVariable;
+pub use refactorings::introduce_parameter_object::IntroduceParameterObject;
+pub use refactorings::introduce_special_case::IntroduceSpecialCase;
+pub use refactorings::invert_if::InvertIf;
+pub use refactorings::move_field::MoveField;
+pub use refactorings::move_method::MoveMethod;
+pub use refactorings::rename_field::RenameField;
+pub use refactorings::rename_method::RenameMethod;
 pub use refactorings::rename_variable::RenameVariable;
+pub use refactorings::replace_conditional_with_polymorphism::ReplaceConditionalWithPolymorphism;
+pub use refactorings::replace_loop_with_pipeline::ReplaceLoopWithPipeline;
+pub use refactorings::replace_nested_conditional_with_guard_clauses::ReplaceNestedConditionalWithGuardClauses;
+pub use refactorings::replace_temp_with_query::ReplaceTempWithQuery;
+pub use refactorings::slide_statements::SlideStatements;
+pub use refactorings::split_loop::SplitLoop;
+pub use refactorings::split_variable::SplitVariable;
 
 #[derive(Debug, Clone)]
 pub struct TextEdit {
@@ -13,13 +75,77 @@ pub struct TextEdit {
     pub replacement: String,
 }
 
+/// The reason a refactoring refused to produce any edits.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RefactorError {
+    InvalidSelection(String),
+    NotFound(String),
+    Conflict(String),
+    Unsupported(String),
+}
+
+impl fmt::Display for RefactorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RefactorError::InvalidSelection(reason) => write!(f, "invalid selection: {}", reason),
+            RefactorError::NotFound(reason) => write!(f, "not found: {}", reason),
+            RefactorError::Conflict(reason) => write!(f, "name conflict: {}", reason),
+            RefactorError::Unsupported(reason) => write!(f, "unsupported: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for RefactorError {}
+
 pub trait Refactoring {
-    fn apply(&self, uast: &TopLevel) -> Vec<TextEdit>;
+    fn apply(&self, uast: &TopLevel, source: &str) -> Result<Vec<TextEdit>, RefactorError>;
 }
 
-pub fn apply_refactoring(source: &str, edits: Vec<TextEdit>) -> String {
+/// One file taking part in a refactoring that spans several files.
+#[derive(Debug, Clone, Copy)]
+pub struct SourceFile<'a> {
+    pub path: &'a str,
+    pub uast: &'a TopLevel,
+    pub source: &'a str,
+}
+
+/// The edits a refactoring makes to one of its files.
+#[derive(Debug, Clone)]
+pub struct FileEdits {
+    pub path: String,
+    pub edits: Vec<TextEdit>,
+}
+
+/// A refactoring that may read and change several files at once, such as
+/// moving a member to a class declared in another file.
+///
+/// Every such refactoring can also run on a single file on its own.
+pub trait WorkspaceRefactoring {
+    fn apply_workspace(&self, files: &[SourceFile]) -> Result<Vec<FileEdits>, RefactorError>;
+}
+
+impl<T: WorkspaceRefactoring> Refactoring for T {
+    fn apply(&self, uast: &TopLevel, source: &str) -> Result<Vec<TextEdit>, RefactorError> {
+        let file = SourceFile {
+            path: "",
+            uast,
+            source,
+        };
+        Ok(self
+            .apply_workspace(&[file])?
+            .into_iter()
+            .flat_map(|file| file.edits)
+            .collect())
+    }
+}
+
+pub fn apply_refactoring(source: &str, mut edits: Vec<TextEdit>) -> String {
     let mut new_source = source.to_string();
 
+    // Edits are applied back to front so earlier offsets stay valid. The sort is
+    // stable, so insertions at the same offset keep the order they were produced in.
+    edits.sort_by_key(|edit| edit.start);
+
     edits.into_iter().rev().for_each(|edit| {
         if edit.end <= new_source.len() && edit.start <= edit.end {
             new_source.replace_range(edit.start..edit.end, &edit.replacement);
@@ -0,0 +1,236 @@
+use crate::analysis::calls::{calls_of, unlowered_mentions};
+use crate::analysis::locate::{find_function, functions};
+use crate::analysis::scope::{resolve, resolve_function};
+use crate::analysis::variables::{function_declarations, function_usages};
+use crate::syntax::has_side_effects;
+use crate::text::{argument_span, replace_list, slice};
+use crate::{RefactorError, Refactoring, TextEdit};
+use uast::*;
+
+/// One parameter of the signature a method is changed to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParameterSpec {
+    /// The parameter currently at this position.
+    Existing(usize),
+    /// A new parameter declared as `declaration`, such as `int level`, with
+    /// `default` passed for it at every call site.
+    Added {
+        declaration: String,
+        default: String,
+    },
+}
+
+/// Add, remove and reorder the parameters of a method, rewriting the argument
+/// list of every call to match.
+///
+/// Parameters of the current signature that are not mentioned are removed,
+/// which is only allowed when the body no longer uses them.
+pub struct ChangeSignature {
+    pub name: String,
+    pub parameters: Vec<ParameterSpec>,
+}
+
+impl ChangeSignature {
+    pub fn new(name: &str, parameters: Vec<ParameterSpec>) -> Self {
+        ChangeSignature {
+            name: name.to_string(),
+            parameters,
+        }
+    }
+}
+
+impl Refactoring for ChangeSignature {
+    fn apply(&self, uast: &TopLevel, source: &str) -> Result<Vec<TextEdit>, RefactorError> {
+        let context = find_function(uast, None, &|func| func.name == self.name)
+            .ok_or_else(|| RefactorError::NotFound(format!("method `{}`", self.name)))?;
+        let target = context.function;
+
+        let overloads = functions(uast)
+            .into_iter()
+            .filter(|func| func.name == self.name)
+            .count();
+        if overloads > 1 {
+            return Err(RefactorError::Unsupported(format!(
+                "`{}` is overloaded",
+                self.name
+            )));
+        }
+        if let Some(modifier) = target
+            .modifiers
+            .iter()
+            .flatten()
+            .find(|modifier| matches!(modifier.as_str(), "virtual" | "override" | "abstract"))
+        {
+            return Err(RefactorError::Unsupported(format!(
+                "`{}` is {} and its signature is shared with other classes",
+                self.name, modifier
+            )));
+        }
+
+        let parameters: Vec<&VarDecl> = target.parameters.iter().flatten().collect();
+        self.check_parameters(target, &parameters)?;
+
+        let bindings = resolve(uast);
+        if bindings
+            .unresolved
+            .iter()
+            .any(|usage| usage.name == self.name)
+        {
+            return Err(RefactorError::Unsupported(format!(
+                "`{}` is used as a delegate",
+                self.name
+            )));
+        }
+        if unlowered_mentions(uast, &self.name)
+            .iter()
+            .any(|span| !target.span.contains(span))
+        {
+            return Err(RefactorError::Unsupported(format!(
+                "`{}` is referenced from code that could not be analyzed",
+                self.name
+            )));
+        }
+
+        let declaration: Vec<&str> = self
+            .parameters
+            .iter()
+            .map(|spec| match spec {
+                ParameterSpec::Existing(i) => slice(source, &parameters[*i].span),
+                ParameterSpec::Added { declaration, .. } => declaration.as_str(),
+            })
+            .collect();
+        let mut edits = vec![replace_list(
+            source,
+            parameters.iter().map(|param| param.span.clone()).collect(),
+            target.name_span.end,
+            declaration.join(", "),
+        )];
+
+        for site in calls_of(uast, context.class, &self.name, &bindings, source)? {
+            edits.push(self.rewrite_call(site.invocation, parameters.len(), source)?);
+        }
+
+        Ok(edits)
+    }
+}
+
+impl ChangeSignature {
+    fn check_parameters(
+        &self,
+        target: &FunctionDef,
+        parameters: &[&VarDecl],
+    ) -> Result<(), RefactorError> {
+        let mut kept = Vec::new();
+        for spec in &self.parameters {
+            match spec {
+                ParameterSpec::Existing(i) if *i >= parameters.len() => {
+                    return Err(RefactorError::InvalidSelection(format!(
+                        "`{}` has no parameter at position {}",
+                        self.name, i
+                    )));
+                }
+                ParameterSpec::Existing(i) if kept.contains(i) => {
+                    return Err(RefactorError::InvalidSelection(format!(
+                        "parameter `{}` is listed more than once",
+                        parameters[*i].name
+                    )));
+                }
+                ParameterSpec::Existing(i) => kept.push(*i),
+                ParameterSpec::Added { declaration, .. } => {
+                    let name = declaration.split_whitespace().last().unwrap_or_default();
+                    let taken = function_declarations(target)
+                        .into_iter()
+                        .map(|decl| decl.name.clone())
+                        .chain(function_usages(target).into_iter().map(|usage| usage.name))
+                        .any(|existing| existing == name);
+                    if taken {
+                        return Err(RefactorError::Conflict(format!(
+                            "`{}` is already used in `{}`",
+                            name, self.name
+                        )));
+                    }
+                }
+            }
+        }
+
+        let bindings = resolve_function(target);
+        for (i, param) in parameters.iter().enumerate() {
+            // The declaration itself is one of the references.
+            if !kept.contains(&i) && bindings.references_to(param).len() > 1 {
+                return Err(RefactorError::Unsupported(format!(
+                    "parameter `{}` is still used by `{}`",
+                    param.name, self.name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn rewrite_call(
+        &self,
+        invocation: &Invocation,
+        arity: usize,
+        source: &str,
+    ) -> Result<TextEdit, RefactorError> {
+        if invocation.arguments.len() != arity {
+            return Err(RefactorError::Unsupported(format!(
+                "a call of `{}` does not pass every parameter",
+                self.name
+            )));
+        }
+        let arguments: Vec<Span> = invocation
+            .arguments
+            .iter()
+            .map(|arg| argument_span(source, arg.span()))
+            .collect();
+        let named = invocation
+            .arguments
+            .iter()
+            .zip(&arguments)
+            .any(|(arg, span)| source[span.start..arg.span().start].contains(':'));
+        if named {
+            return Err(RefactorError::Unsupported(format!(
+                "a call of `{}` uses named arguments",
+                self.name
+            )));
+        }
+
+        let kept: Vec<usize> = self
+            .parameters
+            .iter()
+            .filter_map(|spec| match spec {
+                ParameterSpec::Existing(i) => Some(*i),
+                ParameterSpec::Added { .. } => None,
+            })
+            .collect();
+        let effectful = |i: &usize| has_side_effects(&invocation.arguments[*i]);
+        if (0..arity).any(|i| !kept.contains(&i) && effectful(&i)) {
+            return Err(RefactorError::Unsupported(format!(
+                "a call of `{}` passes an argument with side effects to a removed parameter",
+                self.name
+            )));
+        }
+        let order: Vec<&usize> = kept.iter().filter(|i| effectful(i)).collect();
+        if order.windows(2).any(|pair| pair[0] > pair[1]) {
+            return Err(RefactorError::Unsupported(format!(
+                "a call of `{}` would evaluate arguments with side effects in a different order",
+                self.name
+            )));
+        }
+
+        let text: Vec<&str> = self
+            .parameters
+            .iter()
+            .map(|spec| match spec {
+                ParameterSpec::Existing(i) => slice(source, &arguments[*i]),
+                ParameterSpec::Added { default, .. } => default.as_str(),
+            })
+            .collect();
+        Ok(replace_list(
+            source,
+            arguments,
+            invocation.function.span().end,
+            text.join(", "),
+        ))
+    }
+}
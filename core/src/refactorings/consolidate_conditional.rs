@@ -0,0 +1,189 @@
+use crate::analysis::flow::condition_inputs;
+use crate::analysis::locate::{enclosing_function, function_blocks, select_statements};
+use crate::analysis::members::member_names;
+use crate::refactorings::extract_function::{call, method_header};
+use crate::syntax::{binary_precedence, precedence, wrap};
+use crate::text::{line_indent, reindent, slice, MethodStyle};
+use crate::{RefactorError, Refactoring, TextEdit};
+use uast::*;
+
+/// Merge conditionals that lead to the same result into a single `if`.
+///
+/// Selecting several consecutive `if` statements with identical bodies joins
+/// their conditions with `||`. The bodies must end in a `return` or `throw`,
+/// so the shared body still runs at most once. Selecting a single `if` whose
+/// body is nothing but another `if` joins the nested conditions with `&&`.
+/// Given a `name`, the combined condition is then extracted into a method.
+pub struct ConsolidateConditional {
+    pub selection: Span,
+    pub name: Option<String>,
+}
+
+impl ConsolidateConditional {
+    pub fn new(start: usize, end: usize, name: Option<&str>) -> Self {
+        ConsolidateConditional {
+            selection: Span { start, end },
+            name: name.map(str::to_string),
+        }
+    }
+}
+
+impl Refactoring for ConsolidateConditional {
+    fn apply(&self, uast: &TopLevel, source: &str) -> Result<Vec<TextEdit>, RefactorError> {
+        let context = enclosing_function(uast, &self.selection).ok_or_else(|| {
+            RefactorError::InvalidSelection("the selection is not inside a method".to_string())
+        })?;
+        let func = context.function;
+
+        let mut selected = None;
+        for block in function_blocks(func) {
+            if let Some(found) = select_statements(block, &self.selection)? {
+                selected = Some(found);
+                break;
+            }
+        }
+        let statements = selected
+            .ok_or_else(|| {
+                RefactorError::InvalidSelection("the selection contains no statements".to_string())
+            })?
+            .statements();
+        let conditionals = statements
+            .iter()
+            .map(|stmt| match stmt {
+                Statement::IfStatement(if_stmt) if if_stmt.alternative.is_none() => Ok(if_stmt),
+                _ => Err(RefactorError::InvalidSelection(
+                    "the selection must consist of `if` statements without `else`".to_string(),
+                )),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let (conditions, operator, kept) = if conditionals.len() == 1 {
+            nested(conditionals[0])?
+        } else {
+            sequence(&conditionals, source)?
+        };
+
+        let required = binary_precedence(&BinaryOperator::Logical(operator.clone()));
+        let mut combined = conditions
+            .iter()
+            .map(|condition| {
+                wrap(
+                    slice(source, condition.span()),
+                    precedence(condition),
+                    required,
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(match operator {
+                LogicalOperator::CondAnd => " && ",
+                _ => " || ",
+            });
+
+        let mut edits = Vec::new();
+        if let Some(name) = &self.name {
+            let class = context.class.ok_or_else(|| {
+                RefactorError::Unsupported("the method is not declared in a class".to_string())
+            })?;
+            if member_names(class).contains(&name.as_str()) {
+                return Err(RefactorError::Conflict(format!(
+                    "`{}` already declares a member named `{}`",
+                    class.name, name
+                )));
+            }
+
+            let mut inputs: Vec<&VarDecl> = Vec::new();
+            for condition in &conditions {
+                for decl in condition_inputs(func, condition)? {
+                    if !inputs.contains(&decl) {
+                        inputs.push(decl);
+                    }
+                }
+            }
+            let style = MethodStyle::of(source, func);
+            let method = style.render(
+                &method_header(func, "bool", name, &inputs),
+                &format!("{}return {};", style.body_indent, combined),
+            );
+            edits.push(TextEdit {
+                start: func.span.end,
+                end: func.span.end,
+                replacement: format!("\n\n{}", method),
+            });
+            combined = call(name, &inputs);
+        }
+
+        // Everything after the last condition, from the closing parenthesis of
+        // the header to the end of the body, is kept as written.
+        let first = conditionals[0];
+        let rest = &source[kept.condition.span().end..kept.span.end];
+        let rest = reindent(
+            rest,
+            line_indent(source, kept.span.start),
+            line_indent(source, first.span.start),
+        );
+        let rest = &rest[line_indent(source, first.span.start).len()..];
+        let last = conditionals[conditionals.len() - 1];
+        edits.push(TextEdit {
+            start: first.condition.span().start,
+            end: last.span.end,
+            replacement: format!("{}{}", combined, rest),
+        });
+        Ok(edits)
+    }
+}
+
+/// The conditions of an `if` whose body is only another `if`, all the way
+/// down, and the innermost `if`, whose body survives.
+fn nested(
+    if_stmt: &IfStatement,
+) -> Result<(Vec<&Expression>, LogicalOperator, &IfStatement), RefactorError> {
+    let mut conditions = vec![if_stmt.condition.as_ref()];
+    let mut innermost = if_stmt;
+    while let [Statement::IfStatement(inner)] = innermost.consequence.statements.as_slice() {
+        if inner.alternative.is_some() {
+            break;
+        }
+        conditions.push(&inner.condition);
+        innermost = inner;
+    }
+    if conditions.len() == 1 {
+        return Err(RefactorError::Unsupported(
+            "the body of the `if` is not a single nested `if`".to_string(),
+        ));
+    }
+    Ok((conditions, LogicalOperator::CondAnd, innermost))
+}
+
+/// The conditions of consecutive `if` statements sharing a body, and the
+/// first of them, whose body survives.
+fn sequence<'a>(
+    conditionals: &[&'a IfStatement],
+    source: &str,
+) -> Result<(Vec<&'a Expression>, LogicalOperator, &'a IfStatement), RefactorError> {
+    let first = conditionals[0];
+    let body = slice(source, &first.consequence.span);
+    for if_stmt in &conditionals[1..] {
+        let other = slice(source, &if_stmt.consequence.span);
+        if !body.split_whitespace().eq(other.split_whitespace()) {
+            return Err(RefactorError::Unsupported(
+                "the `if` statements do not share the same body".to_string(),
+            ));
+        }
+    }
+    let exits = match first.consequence.statements.last() {
+        Some(Statement::ReturnStatement(_)) => true,
+        Some(Statement::Unknown { source, .. }) => source.starts_with("throw"),
+        _ => false,
+    };
+    if !exits {
+        return Err(RefactorError::Unsupported(
+            "the shared body does not end in `return` or `throw`, so merging would change how often it runs"
+                .to_string(),
+        ));
+    }
+    let conditions = conditionals
+        .iter()
+        .map(|if_stmt| if_stmt.condition.as_ref())
+        .collect();
+    Ok((conditions, LogicalOperator::CondOr, first))
+}
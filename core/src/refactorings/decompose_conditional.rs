@@ -0,0 +1,133 @@
+use crate::analysis::flow::condition_inputs;
+use crate::analysis::locate::{contains_return, enclosing_function, function_blocks, innermost_if};
+use crate::analysis::members::member_names;
+use crate::refactorings::extract_function::{call, extract_statements, method_header};
+use crate::text::{slice, MethodStyle};
+use crate::{RefactorError, Refactoring, TextEdit};
+use uast::*;
+
+/// Move the condition and the branches of an `if` statement into private
+/// methods of their own, so the `if` reads as a summary of what it decides.
+///
+/// The condition becomes a method returning `bool`. Each branch becomes a
+/// method taking the locals it reads, and returning the one local it assigns
+/// that is used afterwards, exactly as [`ExtractFunction`] would. Without an
+/// `else_name` the `else` branch is left where it is.
+///
+/// [`ExtractFunction`]: crate::ExtractFunction
+pub struct DecomposeConditional {
+    pub offset: usize,
+    pub condition_name: String,
+    pub then_name: String,
+    pub else_name: Option<String>,
+}
+
+impl DecomposeConditional {
+    pub fn new(
+        offset: usize,
+        condition_name: &str,
+        then_name: &str,
+        else_name: Option<&str>,
+    ) -> Self {
+        DecomposeConditional {
+            offset,
+            condition_name: condition_name.to_string(),
+            then_name: then_name.to_string(),
+            else_name: else_name.map(str::to_string),
+        }
+    }
+}
+
+impl Refactoring for DecomposeConditional {
+    fn apply(&self, uast: &TopLevel, source: &str) -> Result<Vec<TextEdit>, RefactorError> {
+        let at = Span {
+            start: self.offset,
+            end: self.offset,
+        };
+        let context = enclosing_function(uast, &at).ok_or_else(|| {
+            RefactorError::InvalidSelection("the offset is not inside a method".to_string())
+        })?;
+        let class = context.class.ok_or_else(|| {
+            RefactorError::Unsupported("the method is not declared in a class".to_string())
+        })?;
+        let func = context.function;
+        let if_stmt = function_blocks(func)
+            .find_map(|block| innermost_if(block, &at))
+            .ok_or_else(|| {
+                RefactorError::InvalidSelection("the offset is not inside an `if`".to_string())
+            })?;
+
+        let alternative = match (&self.else_name, &if_stmt.alternative) {
+            (Some(name), Some(block)) => Some((name, block.as_ref())),
+            (Some(_), None) => {
+                return Err(RefactorError::InvalidSelection(
+                    "the `if` has no `else` branch".to_string(),
+                ))
+            }
+            (None, _) => None,
+        };
+        let mut names = vec![&self.condition_name, &self.then_name];
+        names.extend(alternative.map(|(name, _)| name));
+        for (i, name) in names.iter().enumerate() {
+            let declared = member_names(class).contains(&name.as_str());
+            if declared || names[..i].contains(name) {
+                return Err(RefactorError::Conflict(format!(
+                    "`{}` already declares a member named `{}`",
+                    class.name, name
+                )));
+            }
+        }
+
+        let style = MethodStyle::of(source, func);
+        let inputs = condition_inputs(func, &if_stmt.condition)?;
+        let condition = if_stmt.condition.span();
+        let mut edits = vec![TextEdit {
+            start: condition.start,
+            end: condition.end,
+            replacement: call(&self.condition_name, &inputs),
+        }];
+        let mut methods = vec![style.render(
+            &method_header(func, "bool", &self.condition_name, &inputs),
+            &format!("{}return {};", style.body_indent, slice(source, condition)),
+        )];
+
+        let branches =
+            std::iter::once((&self.then_name, if_stmt.consequence.as_ref())).chain(alternative);
+        for (name, block) in branches {
+            let (Some(first), Some(last)) = (block.statements.first(), block.statements.last())
+            else {
+                return Err(RefactorError::Unsupported(format!(
+                    "there is nothing to move into `{}`",
+                    name
+                )));
+            };
+            if block.statements.iter().any(contains_return) {
+                return Err(RefactorError::Unsupported(format!(
+                    "the branch for `{}` contains a return statement",
+                    name
+                )));
+            }
+            let span = Span {
+                start: first.span().start,
+                end: last.span().end,
+            };
+            let (call, method) = extract_statements(source, func, &block.statements, &span, name)?;
+            edits.push(TextEdit {
+                start: span.start,
+                end: span.end,
+                replacement: call,
+            });
+            methods.push(method);
+        }
+
+        edits.push(TextEdit {
+            start: func.span.end,
+            end: func.span.end,
+            replacement: methods
+                .iter()
+                .map(|method| format!("\n\n{}", method))
+                .collect(),
+        });
+        Ok(edits)
+    }
+}
@@ -0,0 +1,172 @@
+use crate::analysis::calls::is_instance_of;
+use crate::analysis::members::{find_member, member_accesses, member_names, Member};
+use crate::analysis::scope::resolve;
+use crate::text::{slice, MethodStyle};
+use crate::{RefactorError, Refactoring, TextEdit};
+use uast::*;
+
+/// Make a field private and expose it through a property.
+///
+/// The class itself keeps using the field, while accesses through other
+/// instances, such as `order.total`, are switched to the property.
+pub struct EncapsulateField {
+    pub name: String,
+    pub property_name: String,
+}
+
+impl EncapsulateField {
+    pub fn new(name: &str, property_name: &str) -> Self {
+        EncapsulateField {
+            name: name.to_string(),
+            property_name: property_name.to_string(),
+        }
+    }
+}
+
+impl Refactoring for EncapsulateField {
+    fn apply(&self, uast: &TopLevel, source: &str) -> Result<Vec<TextEdit>, RefactorError> {
+        let (class, field, var) = match find_member(uast, &self.name) {
+            Some((class, Member::Field(field, var))) => (class, field, var),
+            Some((_, Member::Property(_))) => {
+                return Err(RefactorError::Unsupported(format!(
+                    "`{}` is already a property",
+                    self.name
+                )))
+            }
+            None => return Err(RefactorError::NotFound(format!("field `{}`", self.name))),
+        };
+
+        let modifiers = field.modifiers.as_deref().unwrap_or_default();
+        if modifiers.iter().any(|modifier| modifier == "const") {
+            return Err(RefactorError::Unsupported(format!(
+                "`{}` is a constant",
+                self.name
+            )));
+        }
+        if field.var_decls.len() > 1 {
+            return Err(RefactorError::Unsupported(format!(
+                "`{}` is declared together with other fields",
+                self.name
+            )));
+        }
+        if class.name == self.property_name
+            || member_names(class).contains(&self.property_name.as_str())
+        {
+            return Err(RefactorError::Conflict(format!(
+                "`{}` already has a member named `{}`",
+                class.name, self.property_name
+            )));
+        }
+
+        let mut edits = vec![self.declaration(source, class, field, var, modifiers)];
+
+        let bindings = resolve(uast);
+        for access in member_accesses(uast, &self.name) {
+            if matches!(access.expression.as_ref(), Expression::Raw { source, .. } if source == "this")
+            {
+                continue;
+            }
+            let ours =
+                is_instance_of(&access.expression, Some(class), &bindings).ok_or_else(|| {
+                    RefactorError::Unsupported(format!(
+                        "cannot tell whether `{}` refers to `{}.{}`",
+                        slice(source, &access.span),
+                        class.name,
+                        self.name
+                    ))
+                })?;
+            if !ours {
+                continue;
+            }
+
+            let before = source[..access.span.start].trim_end();
+            let by_reference = ["ref", "out", "in"].iter().any(|keyword| {
+                before
+                    .strip_suffix(keyword)
+                    .is_some_and(|rest| rest.trim_end().ends_with(['(', ',']))
+            });
+            if by_reference {
+                return Err(RefactorError::Unsupported(format!(
+                    "`{}` is passed by reference, which a property does not allow",
+                    slice(source, &access.span)
+                )));
+            }
+            edits.push(TextEdit {
+                start: access.member_span.start,
+                end: access.member_span.end,
+                replacement: self.property_name.clone(),
+            });
+        }
+
+        Ok(edits)
+    }
+}
+
+impl EncapsulateField {
+    /// The private field followed by the property that exposes it.
+    fn declaration(
+        &self,
+        source: &str,
+        class: &ClassDef,
+        field: &FieldDef,
+        var: &VarDecl,
+        modifiers: &[String],
+    ) -> TextEdit {
+        let is_access = |modifier: &&String| {
+            matches!(
+                modifier.as_str(),
+                "public" | "protected" | "internal" | "private"
+            )
+        };
+        let access: Vec<&str> = modifiers
+            .iter()
+            .filter(is_access)
+            .map(String::as_str)
+            .collect();
+        let others: Vec<&str> = modifiers
+            .iter()
+            .filter(|modifier| !is_access(modifier))
+            .map(String::as_str)
+            .collect();
+        let var_type = var.var_type.as_deref().unwrap_or("var");
+
+        let mut field_modifiers = vec!["private"];
+        field_modifiers.extend(&others);
+        let field_text = format!(
+            "{} {} {}",
+            field_modifiers.join(" "),
+            var_type,
+            &source[var.name_span.start..field.span.end]
+        );
+
+        let mut property_modifiers = access;
+        if others.contains(&"static") {
+            property_modifiers.push("static");
+        }
+        let header = property_modifiers
+            .iter()
+            .chain([&var_type, &self.property_name.as_str()])
+            .copied()
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let style = MethodStyle::beside(source, class, &field.span);
+        let mut accessors = vec![format!(
+            "{}get {{ return {}; }}",
+            style.body_indent, self.name
+        )];
+        if !others.contains(&"readonly") {
+            accessors.push(format!(
+                "{}set {{ {} = value; }}",
+                style.body_indent, self.name
+            ));
+        }
+        let property = style.render(&header, &accessors.join("\n"));
+
+        TextEdit {
+            start: field.span.start,
+            end: field.span.end,
+            replacement: format!("{}\n{}", field_text, property),
+        }
+    }
+}
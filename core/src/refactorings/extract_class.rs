@@ -0,0 +1,472 @@
+use crate::analysis::calls::{find_calls, is_instance_of, unlowered_mentions, Receiver};
+use crate::analysis::members::{classes, member_accesses, member_names, unlowered_class_mentions};
+use crate::analysis::scope::{resolve, resolve_function};
+use crate::analysis::variables::expression_usages;
+use crate::text::{
+    declares_type, doc_comment_start, line_indent, lower_first, member_after, reindent,
+    removal_span, slice, splice, widen_access, MethodStyle,
+};
+use crate::{RefactorError, Refactoring, TextEdit};
+use uast::*;
+
+/// Move some fields, properties and methods of a class into a new class, and
+/// give the old class a field holding an instance of it.
+///
+/// The field is named after the new class, so extracting `TelephoneNumber`
+/// adds `private readonly TelephoneNumber telephoneNumber`, and every
+/// reference to a moved member goes through it: `areaCode` becomes
+/// `telephoneNumber.areaCode`. The moved members may only use each other,
+/// since the new class has no way back to the old one. Moved members that
+/// are private but still used from the old class become internal.
+pub struct ExtractClass {
+    pub class: String,
+    pub name: String,
+    pub members: Vec<String>,
+}
+
+impl ExtractClass {
+    pub fn new(class: &str, name: &str, members: &[&str]) -> Self {
+        ExtractClass {
+            class: class.to_string(),
+            name: name.to_string(),
+            members: members.iter().map(|member| member.to_string()).collect(),
+        }
+    }
+}
+
+/// A member chosen for the new class.
+struct Extracted<'a> {
+    name: &'a str,
+    item: &'a TopLevel,
+    /// The declaration along with its documentation comment.
+    region: Span,
+}
+
+impl Refactoring for ExtractClass {
+    fn apply(&self, uast: &TopLevel, source: &str) -> Result<Vec<TextEdit>, RefactorError> {
+        let class = classes(uast)
+            .into_iter()
+            .find(|class| class.name == self.class)
+            .ok_or_else(|| RefactorError::NotFound(format!("class `{}`", self.class)))?;
+        if self.members.is_empty() {
+            return Err(RefactorError::InvalidSelection(
+                "no members were chosen".to_string(),
+            ));
+        }
+        if declares_type(source, &self.name) {
+            return Err(RefactorError::Conflict(format!(
+                "a class named `{}` already exists",
+                self.name
+            )));
+        }
+        let field = lower_first(&self.name);
+        if member_names(class).contains(&field.as_str()) {
+            return Err(RefactorError::Conflict(format!(
+                "`{}` already has a member named `{}`",
+                self.class, field
+            )));
+        }
+
+        let extracted = self.extracted(class, source)?;
+        let spans: Vec<&Span> = extracted.iter().map(|member| &member.region).collect();
+        let moved = |span: &Span| spans.iter().any(|region| region.contains(span));
+        self.check_self_contained(uast, class, &extracted, source)?;
+
+        // Rewrite every reference to a moved member that stays behind.
+        let bindings = resolve(uast);
+        let hidden = bindings
+            .references
+            .iter()
+            .any(|reference| reference.decl.name == field && class.span.contains(&reference.span));
+        let prefix = if hidden {
+            format!("this.{}.", field)
+        } else {
+            format!("{}.", field)
+        };
+        let chosen = |name: &str| self.members.iter().any(|member| member == name);
+        let mut edits = Vec::new();
+        let mut handled: Vec<Span> = Vec::new();
+        let mut reached: Vec<&str> = Vec::new();
+        let mut outside = false;
+        for usage in &bindings.unresolved {
+            if !chosen(&usage.name) || !class.span.contains(&usage.span) || moved(&usage.span) {
+                continue;
+            }
+            edits.push(TextEdit {
+                start: usage.span.start,
+                end: usage.span.start,
+                replacement: prefix.clone(),
+            });
+            handled.push(usage.span.clone());
+            reached.push(usage.name.as_str());
+        }
+        for member in &extracted {
+            let TopLevel::Function(func) = member.item else {
+                continue;
+            };
+            for site in find_calls(uast, &func.name) {
+                let ours = site.receiver == Receiver::Implicit
+                    && site.class.is_some_and(|other| std::ptr::eq(other, class))
+                    && !moved(&site.invocation.span);
+                if ours {
+                    let start = site.invocation.function.span().start;
+                    edits.push(TextEdit {
+                        start,
+                        end: start,
+                        replacement: prefix.clone(),
+                    });
+                    reached.push(member.name);
+                }
+            }
+        }
+        for name in &self.members {
+            for access in member_accesses(uast, name) {
+                let inside = class.span.contains(&access.span);
+                handled.push(access.member_span.clone());
+                if moved(&access.span) {
+                    continue;
+                }
+                let this = matches!(
+                    access.expression.as_ref(),
+                    Expression::Raw { source, .. } if source == "this"
+                );
+                if this && !inside {
+                    continue;
+                }
+                let ours = is_instance_of(&access.expression, Some(class), &bindings).ok_or_else(
+                    || {
+                        RefactorError::Unsupported(format!(
+                            "cannot tell whether `{}` refers to `{}.{}`",
+                            slice(source, &access.span),
+                            self.class,
+                            name
+                        ))
+                    },
+                )?;
+                if ours {
+                    outside |= !inside;
+                    edits.push(TextEdit {
+                        start: access.member_span.start,
+                        end: access.member_span.start,
+                        replacement: format!("{}.", field),
+                    });
+                    reached.push(name);
+                }
+            }
+        }
+        for name in &self.members {
+            let unlowered = unlowered_mentions(uast, name)
+                .iter()
+                .any(|span| !handled.contains(span) && !moved(span))
+                || classes(uast)
+                    .into_iter()
+                    .any(|other| !unlowered_class_mentions(other, source, name).is_empty());
+            if unlowered {
+                return Err(RefactorError::Unsupported(format!(
+                    "`{}` is referenced from code that could not be analyzed",
+                    name
+                )));
+            }
+        }
+
+        // The new class, with the members in their original order.
+        let class_indent = line_indent(source, class.span.start);
+        let member_indent = line_indent(source, extracted[0].region.start).to_string();
+        let widened = widen_access(class, None, &reached, "internal", source);
+        let mut body = String::new();
+        for (i, member) in extracted.iter().enumerate() {
+            if i > 0 {
+                let fields = matches!(extracted[i - 1].item, TopLevel::Field(_))
+                    && matches!(member.item, TopLevel::Field(_));
+                body.push_str(if fields { "\n" } else { "\n\n" });
+            }
+            let access: Vec<TextEdit> = widened
+                .iter()
+                .filter(|edit| member.region.start <= edit.start && edit.end <= member.region.end)
+                .cloned()
+                .collect();
+            let indent = line_indent(source, member.region.start);
+            body.push_str(&reindent(
+                &splice(source, &member.region, &access),
+                indent,
+                &member_indent,
+            ));
+        }
+        let open = source[class.span.start..class.span.end]
+            .find('{')
+            .map_or(class.span.start, |i| class.span.start + i);
+        let style = MethodStyle {
+            member_indent: class_indent.to_string(),
+            body_indent: member_indent.clone(),
+            allman: source[..open].ends_with(&format!("\n{}", class_indent)),
+        };
+        let class_access = class
+            .modifiers
+            .iter()
+            .flatten()
+            .find(|modifier| matches!(modifier.as_str(), "public" | "internal"))
+            .map_or(String::new(), |modifier| format!("{} ", modifier));
+        edits.push(TextEdit {
+            start: class.span.end,
+            end: class.span.end,
+            replacement: format!(
+                "\n\n{}",
+                style.render(&format!("{}class {}", class_access, self.name), &body)
+            ),
+        });
+
+        let declaration = format!(
+            "{} readonly {} {} = new {}();",
+            if outside { "internal" } else { "private" },
+            self.name,
+            field,
+            self.name
+        );
+        edits.extend(self.replace_members(class, &extracted, declaration, source));
+        Ok(edits)
+    }
+}
+
+impl ExtractClass {
+    /// The chosen members of `class`, in the order they are declared.
+    fn extracted<'a>(
+        &self,
+        class: &'a ClassDef,
+        source: &str,
+    ) -> Result<Vec<Extracted<'a>>, RefactorError> {
+        let mut extracted = Vec::new();
+        for item in class.body.iter().flatten() {
+            let (names, span, modifiers): (Vec<&str>, &Span, &Option<Vec<String>>) = match item {
+                TopLevel::Function(func) => (vec![func.name.as_str()], &func.span, &func.modifiers),
+                TopLevel::Field(field) => (
+                    field
+                        .var_decls
+                        .iter()
+                        .map(|var| var.name.as_str())
+                        .collect(),
+                    &field.span,
+                    &field.modifiers,
+                ),
+                TopLevel::Property(property) => (
+                    vec![property.name.as_str()],
+                    &property.span,
+                    &property.modifiers,
+                ),
+                _ => continue,
+            };
+            let picked = names
+                .iter()
+                .filter(|name| self.members.iter().any(|member| member == *name))
+                .count();
+            if picked == 0 {
+                continue;
+            }
+            if picked < names.len() {
+                return Err(RefactorError::Unsupported(format!(
+                    "`{}` is declared together with fields that stay",
+                    names[0]
+                )));
+            }
+            if let Some(modifier) = modifiers.iter().flatten().find(|modifier| {
+                matches!(
+                    modifier.as_str(),
+                    "static" | "const" | "virtual" | "override" | "abstract" | "protected"
+                )
+            }) {
+                return Err(RefactorError::Unsupported(format!(
+                    "`{}` is {} and belongs to `{}`",
+                    names[0], modifier, self.class
+                )));
+            }
+            extracted.push(Extracted {
+                name: names[0],
+                item,
+                region: Span {
+                    start: doc_comment_start(source, span.start),
+                    end: span.end,
+                },
+            });
+        }
+
+        if let Some(missing) = self
+            .members
+            .iter()
+            .find(|name| !member_names(class).contains(&name.as_str()))
+        {
+            return Err(RefactorError::NotFound(format!(
+                "member `{}` in `{}`",
+                missing, self.class
+            )));
+        }
+        Ok(extracted)
+    }
+
+    /// Refuse members that use the members staying behind, or the instance
+    /// itself, since the new class cannot reach them.
+    fn check_self_contained(
+        &self,
+        uast: &TopLevel,
+        class: &ClassDef,
+        extracted: &[Extracted],
+        source: &str,
+    ) -> Result<(), RefactorError> {
+        let staying: Vec<&str> = member_names(class)
+            .into_iter()
+            .filter(|name| !self.members.iter().any(|member| member == name))
+            .collect();
+        let stays = |name: &str, user: &str| {
+            RefactorError::Unsupported(format!(
+                "`{}` uses `{}`, which stays in `{}`",
+                user, name, self.class
+            ))
+        };
+
+        for member in extracted {
+            let (functions, values): (Vec<&FunctionDef>, Vec<&Expression>) = match member.item {
+                TopLevel::Function(func) => (vec![func], vec![]),
+                TopLevel::Field(field) => (
+                    vec![],
+                    field
+                        .var_decls
+                        .iter()
+                        .filter_map(|var| var.value.as_deref())
+                        .collect(),
+                ),
+                TopLevel::Property(property) => (
+                    property.accessors.iter().collect(),
+                    property.value.as_deref().into_iter().collect(),
+                ),
+                _ => (vec![], vec![]),
+            };
+            for value in values {
+                if let Some(usage) = expression_usages(value)
+                    .into_iter()
+                    .find(|usage| staying.contains(&usage.name.as_str()))
+                {
+                    return Err(stays(&usage.name, member.name));
+                }
+            }
+            for func in functions {
+                for usage in &resolve_function(func).unresolved {
+                    let name = usage.name.as_str();
+                    if name == "this" {
+                        match member_after(source, usage.span.end) {
+                            Some(after) if staying.contains(&after) => {
+                                return Err(stays(after, member.name))
+                            }
+                            Some(_) => {}
+                            None => {
+                                return Err(RefactorError::Unsupported(format!(
+                                    "`{}` passes on `this`, which would be the new `{}`",
+                                    member.name, self.name
+                                )))
+                            }
+                        }
+                    } else if name == "base" {
+                        return Err(RefactorError::Unsupported(format!(
+                            "`{}` uses the base class of `{}`",
+                            member.name, self.class
+                        )));
+                    } else if staying.contains(&name) {
+                        return Err(stays(name, member.name));
+                    }
+                }
+                for name in &staying {
+                    let called = find_calls(uast, name).into_iter().any(|site| {
+                        std::ptr::eq(site.caller, func) && site.receiver == Receiver::Implicit
+                    });
+                    if called {
+                        return Err(stays(name, member.name));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove the moved members from `class` and declare the field holding
+    /// the new instance: in place of the first moved field, after the last
+    /// field that stays, or before the first member that stays.
+    fn replace_members(
+        &self,
+        class: &ClassDef,
+        extracted: &[Extracted],
+        declaration: String,
+        source: &str,
+    ) -> Vec<TextEdit> {
+        let moved = |item: &TopLevel| {
+            extracted
+                .iter()
+                .any(|member| std::ptr::eq(member.item, item))
+        };
+        let remaining: Vec<&TopLevel> = class
+            .body
+            .iter()
+            .flatten()
+            .filter(|item| !moved(item))
+            .collect();
+        let last_field = remaining.iter().rev().find_map(|item| match item {
+            TopLevel::Field(field) => Some(field),
+            _ => None,
+        });
+        let first_field = extracted
+            .iter()
+            .position(|member| matches!(member.item, TopLevel::Field(_)));
+
+        let mut edits = Vec::new();
+        let replaced = match (first_field, last_field, remaining.first()) {
+            (Some(i), _, _) => Some(i),
+            (None, Some(field), _) => {
+                edits.push(TextEdit {
+                    start: field.span.end,
+                    end: field.span.end,
+                    replacement: format!(
+                        "\n{}{}",
+                        line_indent(source, field.span.start),
+                        declaration
+                    ),
+                });
+                None
+            }
+            (None, None, Some(first)) => {
+                let start = doc_comment_start(source, item_span(first).start);
+                edits.push(TextEdit {
+                    start,
+                    end: start,
+                    replacement: format!("{}\n\n{}", declaration, line_indent(source, start)),
+                });
+                None
+            }
+            (None, None, None) => Some(0),
+        };
+        for (i, member) in extracted.iter().enumerate() {
+            if Some(i) == replaced {
+                edits.push(TextEdit {
+                    start: member.region.start,
+                    end: member.region.end,
+                    replacement: declaration.clone(),
+                });
+            } else {
+                let removal = removal_span(source, &member.region);
+                edits.push(TextEdit {
+                    start: removal.start,
+                    end: removal.end,
+                    replacement: String::new(),
+                });
+            }
+        }
+        edits
+    }
+}
+
+fn item_span(item: &TopLevel) -> &Span {
+    match item {
+        TopLevel::Function(func) => &func.span,
+        TopLevel::Field(field) => &field.span,
+        TopLevel::Property(property) => &property.span,
+        TopLevel::Class(class) => &class.span,
+        TopLevel::Module(module) => &module.span,
+        TopLevel::Statement(stmt) => stmt.span(),
+        TopLevel::Unknown { span, .. } => span,
+    }
+}
@@ -0,0 +1,177 @@
+use crate::analysis::flow::data_flow;
+use crate::analysis::locate::{
+    contains_return, enclosing_function, function_blocks, jump_out, select_statements,
+};
+use crate::analysis::members::member_names;
+use crate::text::{line_indent, reindent, slice, MethodStyle};
+use crate::{RefactorError, Refactoring, TextEdit};
+use uast::*;
+
+/// Turn a run of statements inside a method into a new private method.
+///
+/// Locals that are read by the selection but declared outside of it become
+/// parameters. A single local that is written by the selection and read after
+/// it becomes the return value.
+pub struct ExtractFunction {
+    pub selection: Span,
+    pub name: String,
+}
+
+impl ExtractFunction {
+    pub fn new(start: usize, end: usize, name: &str) -> Self {
+        ExtractFunction {
+            selection: Span { start, end },
+            name: name.to_string(),
+        }
+    }
+}
+
+impl Refactoring for ExtractFunction {
+    fn apply(&self, uast: &TopLevel, source: &str) -> Result<Vec<TextEdit>, RefactorError> {
+        let context = enclosing_function(uast, &self.selection).ok_or_else(|| {
+            RefactorError::InvalidSelection("the selection is not inside a method".to_string())
+        })?;
+        let class = context.class.ok_or_else(|| {
+            RefactorError::Unsupported("the method is not declared in a class".to_string())
+        })?;
+        let func = context.function;
+
+        if member_names(class).contains(&self.name.as_str()) {
+            return Err(RefactorError::Conflict(format!(
+                "`{}` already declares a member named `{}`",
+                class.name, self.name
+            )));
+        }
+
+        let mut selected = None;
+        for block in function_blocks(func) {
+            if let Some(found) = select_statements(block, &self.selection)? {
+                selected = Some(found);
+                break;
+            }
+        }
+        let selected = selected.ok_or_else(|| {
+            RefactorError::InvalidSelection("the selection contains no statements".to_string())
+        })?;
+        let statements = selected.statements();
+        let selection_span = selected.span();
+
+        if statements.iter().any(contains_return) {
+            return Err(RefactorError::Unsupported(
+                "the selection contains a return statement".to_string(),
+            ));
+        }
+
+        let (call, method) =
+            extract_statements(source, func, statements, &selection_span, &self.name)?;
+        Ok(vec![
+            TextEdit {
+                start: selection_span.start,
+                end: selection_span.end,
+                replacement: call,
+            },
+            TextEdit {
+                start: func.span.end,
+                end: func.span.end,
+                replacement: format!("\n\n{}", method),
+            },
+        ])
+    }
+}
+
+/// The call replacing `statements`, which span `selection`, and the private
+/// method called `name` that now holds them.
+pub(crate) fn extract_statements(
+    source: &str,
+    func: &FunctionDef,
+    statements: &[Statement],
+    selection: &Span,
+    name: &str,
+) -> Result<(String, String), RefactorError> {
+    if let Some(keyword) = statements.iter().find_map(jump_out) {
+        return Err(RefactorError::Unsupported(format!(
+            "the statements contain a `{}` that leaves them",
+            keyword
+        )));
+    }
+    let flow = data_flow(func, statements, selection)?;
+    let style = MethodStyle::of(source, func);
+
+    let return_type = flow
+        .returned
+        .map(|decl| decl.var_type.clone().unwrap_or_default())
+        .unwrap_or_else(|| "void".to_string());
+    let header = method_header(func, &return_type, name, &flow.parameters);
+
+    let mut body_lines: Vec<String> = flow
+        .locals
+        .iter()
+        .map(|decl| {
+            format!(
+                "{}{} {};",
+                style.body_indent,
+                decl.var_type.as_deref().unwrap_or(""),
+                decl.name
+            )
+        })
+        .collect();
+    body_lines.push(reindent(
+        slice(source, selection),
+        line_indent(source, selection.start),
+        &style.body_indent,
+    ));
+    if let Some(returned) = flow.returned {
+        body_lines.push(format!("{}return {};", style.body_indent, returned.name));
+    }
+
+    let call = call(name, &flow.parameters);
+    let call = match flow.returned {
+        Some(returned) if flow.returned_declared_inside => format!(
+            "{} {} = {};",
+            returned.var_type.as_deref().unwrap_or(""),
+            returned.name,
+            call
+        ),
+        Some(returned) => format!("{} = {};", returned.name, call),
+        None => format!("{};", call),
+    };
+    Ok((call, style.render(&header, &body_lines.join("\n"))))
+}
+
+/// The header of a private method extracted from `func`, static if `func` is.
+pub(crate) fn method_header(
+    func: &FunctionDef,
+    return_type: &str,
+    name: &str,
+    parameters: &[&VarDecl],
+) -> String {
+    let static_modifier = if func
+        .modifiers
+        .iter()
+        .flatten()
+        .any(|modifier| modifier == "static")
+    {
+        "static "
+    } else {
+        ""
+    };
+    let parameters = parameters
+        .iter()
+        .map(|decl| format!("{} {}", decl.var_type.as_deref().unwrap_or(""), decl.name))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(
+        "private {}{} {}({})",
+        static_modifier, return_type, name, parameters
+    )
+}
+
+/// A call of the method `name` passing `parameters` by name.
+pub(crate) fn call(name: &str, parameters: &[&VarDecl]) -> String {
+    let arguments = parameters
+        .iter()
+        .map(|decl| decl.name.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("{}({})", name, arguments)
+}
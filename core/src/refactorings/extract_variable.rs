@@ -0,0 +1,231 @@
+use crate::analysis::locate::{
+    enclosing_function, locate_expression, nested_blocks, statement_expressions, ExpressionLocation,
+};
+use crate::analysis::variables::{
+    expression_usages, function_declarations, function_usages, statement_usages,
+};
+use crate::syntax::{evaluated_conditionally, same_expression};
+use crate::text::{grouping_parens, line_indent, slice};
+use crate::{RefactorError, Refactoring, TextEdit};
+use uast::*;
+
+/// Introduce a local for the value of a selected expression.
+///
+/// The declaration goes right before the statement that evaluates the
+/// expression. With `replace_all`, later structurally identical expressions in
+/// the same block are replaced as well, up to the first statement that writes
+/// a variable the expression reads.
+pub struct ExtractVariable {
+    pub selection: Span,
+    pub name: String,
+    pub replace_all: bool,
+}
+
+impl ExtractVariable {
+    pub fn new(start: usize, end: usize, name: &str, replace_all: bool) -> Self {
+        ExtractVariable {
+            selection: Span { start, end },
+            name: name.to_string(),
+            replace_all,
+        }
+    }
+}
+
+impl Refactoring for ExtractVariable {
+    fn apply(&self, uast: &TopLevel, source: &str) -> Result<Vec<TextEdit>, RefactorError> {
+        let selection = trim_selection(source, &self.selection);
+        let func = enclosing_function(uast, &selection)
+            .ok_or_else(|| {
+                RefactorError::InvalidSelection("the selection is not inside a method".to_string())
+            })?
+            .function;
+
+        let location = locate_expression(func, &selection).ok_or_else(|| {
+            RefactorError::InvalidSelection("the selection is not an expression".to_string())
+        })?;
+
+        if location.in_loop_header {
+            return Err(RefactorError::Unsupported(
+                "the expression is re-evaluated on every iteration of a loop".to_string(),
+            ));
+        }
+        if evaluated_conditionally(location.statement, &selection) {
+            return Err(RefactorError::Unsupported(
+                "the expression may not be evaluated every time its statement runs".to_string(),
+            ));
+        }
+        if location.is_written || matches!(location.expression, Expression::Assignment(_)) {
+            return Err(RefactorError::Unsupported(
+                "the expression is the target of an assignment".to_string(),
+            ));
+        }
+
+        let anchor = location.statement;
+        if location.block.span == *anchor.span() {
+            return Err(RefactorError::Unsupported(
+                "the expression is in a statement without braces around it".to_string(),
+            ));
+        }
+
+        let taken = function_declarations(func)
+            .into_iter()
+            .map(|decl| decl.name.clone())
+            .chain(function_usages(func).into_iter().map(|usage| usage.name))
+            .any(|name| name == self.name);
+        if taken {
+            return Err(RefactorError::Conflict(format!(
+                "`{}` is already used in `{}`",
+                self.name, func.name
+            )));
+        }
+
+        let indent = line_indent(source, anchor.span().start);
+        let mut edits = vec![TextEdit {
+            start: anchor.span().start,
+            end: anchor.span().start,
+            replacement: format!(
+                "var {} = {};\n{}",
+                self.name,
+                slice(source, location.expression.span()),
+                indent
+            ),
+        }];
+
+        let occurrences = if self.replace_all {
+            occurrences(&location)
+        } else {
+            vec![location.expression]
+        };
+        edits.extend(occurrences.into_iter().map(|expr| {
+            let span = grouping_parens(source, expr.span());
+            TextEdit {
+                start: span.start,
+                end: span.end,
+                replacement: self.name.clone(),
+            }
+        }));
+
+        Ok(edits)
+    }
+}
+
+/// Narrow a selection to the expression it is meant to cover by dropping
+/// surrounding whitespace and parentheses.
+fn trim_selection(source: &str, selection: &Span) -> Span {
+    let mut start = selection.start;
+    let mut end = selection.end.min(source.len());
+    loop {
+        let text = &source[start..end];
+        let trimmed = text.trim_start();
+        start += text.len() - trimmed.len();
+        end -= trimmed.len() - trimmed.trim_end().len();
+
+        let text = &source[start..end];
+        if text.starts_with('(') && closing_paren(text) == Some(text.len() - 1) {
+            start += 1;
+            end -= 1;
+        } else {
+            return Span { start, end };
+        }
+    }
+}
+
+/// The offset of the parenthesis closing the one `text` starts with.
+fn closing_paren(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The occurrences of the selected expression that can share one local.
+fn occurrences<'a>(location: &ExpressionLocation<'a>) -> Vec<&'a Expression> {
+    let inputs: Vec<String> = expression_usages(location.expression)
+        .into_iter()
+        .map(|usage| usage.name)
+        .collect();
+    let writes_input = |stmt: &Statement| {
+        statement_usages(stmt)
+            .iter()
+            .any(|usage| usage.access.writes() && inputs.contains(&usage.name))
+    };
+
+    // Only the enclosing statement's own expressions are evaluated before any
+    // write it performs, so its nested blocks are left alone.
+    let mut found = Vec::new();
+    for (expr, in_loop_header) in statement_expressions(location.statement) {
+        if !in_loop_header {
+            collect_same(expr, location.expression, &mut found);
+        }
+    }
+    if writes_input(location.statement) {
+        return found;
+    }
+
+    for stmt in &location.block.statements[location.index + 1..] {
+        if writes_input(stmt) {
+            break;
+        }
+        collect_in_statement(stmt, location.expression, &mut found);
+    }
+    found
+}
+
+fn collect_in_statement<'a>(
+    stmt: &'a Statement,
+    target: &Expression,
+    found: &mut Vec<&'a Expression>,
+) {
+    for (expr, in_loop_header) in statement_expressions(stmt) {
+        if !in_loop_header {
+            collect_same(expr, target, found);
+        }
+    }
+    for block in nested_blocks(stmt) {
+        for nested in &block.statements {
+            collect_in_statement(nested, target, found);
+        }
+    }
+}
+
+fn collect_same<'a>(expr: &'a Expression, target: &Expression, found: &mut Vec<&'a Expression>) {
+    if same_expression(expr, target) {
+        found.push(expr);
+        return;
+    }
+    match expr {
+        Expression::BinaryOp(op) => {
+            collect_same(&op.left, target, found);
+            collect_same(&op.right, target, found);
+        }
+        Expression::UnaryOp(op) => {
+            if matches!(op.operator, UnaryOperator::Negate | UnaryOperator::Not) {
+                collect_same(&op.operand, target, found);
+            }
+        }
+        Expression::Assignment(assign) => collect_same(&assign.right, target, found),
+        Expression::Invocation(inv) => {
+            collect_same(&inv.function, target, found);
+            for arg in &inv.arguments {
+                collect_same(arg, target, found);
+            }
+        }
+        Expression::MemberAccess(ma) => collect_same(&ma.expression, target, found),
+        Expression::Conditional(cond) => {
+            collect_same(&cond.condition, target, found);
+            collect_same(&cond.consequence, target, found);
+            collect_same(&cond.alternative, target, found);
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,534 @@
+use crate::analysis::calls::{find_calls, is_instance_of, unlowered_mentions, Receiver};
+use crate::analysis::locate::enclosing_function;
+use crate::analysis::members::{
+    classes, find_class, member_accesses, member_names, members, unlowered_class_mentions, Member,
+};
+use crate::analysis::scope::{resolve, resolve_function};
+use crate::analysis::variables::{expression_usages, raw_tokens};
+use crate::text::{
+    declares_constructor, doc_comment_start, line_indent, line_start, member_after, reindent,
+    removal_span, slice, MemberSlot,
+};
+use crate::{FileEdits, RefactorError, SourceFile, TextEdit, WorkspaceRefactoring};
+use uast::*;
+
+/// Fold a class into the one class that holds an instance of it in a field,
+/// and delete it.
+///
+/// This is the inverse of [`ExtractClass`](crate::ExtractClass). The members
+/// of the inlined class are appended to the holder, the field is removed, and
+/// every access through it loses a step: `telephoneNumber.areaCode` in the
+/// holder becomes `areaCode`, and `person.telephoneNumber.areaCode` elsewhere
+/// becomes `person.areaCode`.
+///
+/// The holder must be the only class that mentions the inlined class, and
+/// must create its instance in the field initializer with `new`. The field
+/// may only be used to reach members, never passed on as a whole.
+pub struct InlineClass {
+    pub class: String,
+}
+
+impl InlineClass {
+    pub fn new(class: &str) -> Self {
+        InlineClass {
+            class: class.to_string(),
+        }
+    }
+}
+
+impl WorkspaceRefactoring for InlineClass {
+    fn apply_workspace(&self, files: &[SourceFile]) -> Result<Vec<FileEdits>, RefactorError> {
+        let (home, class) = find_class(files, &self.class)?;
+        self.check_class(class, files[home].source)?;
+        let (there, holder) = self.holder(files, class)?;
+        let (field, var) = self.holding_field(holder, files[there].source)?;
+        self.check_names(
+            files[home].uast,
+            class,
+            holder,
+            &var.name,
+            files[home].source,
+        )?;
+
+        let mut edits: Vec<Vec<TextEdit>> = vec![Vec::new(); files.len()];
+
+        // The members of the class, in their original order, go to the end
+        // of the holder. When the field is the last member they take its place.
+        let source = files[home].source;
+        let slot = MemberSlot::of(files[there].source, holder);
+        let mut body = String::new();
+        let mut previous_field = false;
+        for item in class.body.iter().flatten() {
+            let (span, is_field) = match item {
+                TopLevel::Function(func) => (&func.span, false),
+                TopLevel::Field(field) => (&field.span, true),
+                TopLevel::Property(property) => (&property.span, false),
+                _ => continue,
+            };
+            if !body.is_empty() {
+                body.push_str(if previous_field && is_field {
+                    "\n"
+                } else {
+                    "\n\n"
+                });
+            }
+            previous_field = is_field;
+            let region = Span {
+                start: doc_comment_start(source, span.start),
+                end: span.end,
+            };
+            body.push_str(&reindent(
+                slice(source, &region),
+                line_indent(source, span.start),
+                &slot.indent,
+            ));
+        }
+        let holder_source = files[there].source;
+        let region = Span {
+            start: doc_comment_start(holder_source, field.span.start),
+            end: field.span.end,
+        };
+        let last =
+            holder.body.iter().flatten().last().is_some_and(
+                |item| matches!(item, TopLevel::Field(last) if last.span == field.span),
+            );
+        if last {
+            edits[there].push(TextEdit {
+                start: line_start(holder_source, region.start),
+                end: region.end,
+                replacement: body,
+            });
+        } else {
+            let mut removal = removal_span(holder_source, &region);
+            // As the first member, the field takes the blank line after it along.
+            let rest = &holder_source[removal.end..];
+            if holder_source[..removal.start].trim_end().ends_with('{')
+                && rest.find('\n').is_some_and(|i| rest[..i].trim().is_empty())
+            {
+                removal.end += rest.find('\n').unwrap_or_default() + 1;
+            }
+            edits[there].push(TextEdit {
+                start: removal.start,
+                end: removal.end,
+                replacement: String::new(),
+            });
+            if !body.is_empty() {
+                edits[there].push(slot.insert(&body));
+            }
+        }
+
+        let region = Span {
+            start: doc_comment_start(source, class.span.start),
+            end: class.span.end,
+        };
+        let mut removal = removal_span(source, &region);
+        if removal.end == source.len() {
+            // The last type in the file takes the line break before it along.
+            removal.start = source[..removal.start].trim_end().len();
+        }
+        edits[home].push(TextEdit {
+            start: removal.start,
+            end: removal.end,
+            replacement: String::new(),
+        });
+
+        for (i, file) in files.iter().enumerate() {
+            let bindings = resolve(file.uast);
+            let mut handled = Vec::new();
+            let step = |span: &Span| -> Result<Span, RefactorError> {
+                member_after(file.source, span.end)
+                    .map(|_| Span {
+                        start: span.start,
+                        end: span.end + 1,
+                    })
+                    .ok_or_else(|| {
+                        RefactorError::Unsupported(format!(
+                            "`{}` is used as a whole, not only to reach its members",
+                            var.name
+                        ))
+                    })
+            };
+
+            if i == there {
+                for usage in &bindings.unresolved {
+                    if usage.name != var.name
+                        || !holder.span.contains(&usage.span)
+                        || field.span.contains(&usage.span)
+                    {
+                        continue;
+                    }
+                    let through = step(&usage.span)?;
+                    let member = member_after(file.source, usage.span.end).unwrap_or_default();
+                    // A local named like the member would capture the bare name.
+                    let hidden =
+                        enclosing_function(file.uast, &usage.span).is_some_and(|context| {
+                            bindings.references.iter().any(|reference| {
+                                reference.decl.name == member
+                                    && context.function.span.contains(&reference.span)
+                            })
+                        });
+                    edits[i].push(TextEdit {
+                        start: through.start,
+                        end: through.end,
+                        replacement: if hidden {
+                            "this.".to_string()
+                        } else {
+                            String::new()
+                        },
+                    });
+                    handled.push(usage.span.clone());
+                }
+                handled.push(var.name_span.clone());
+            }
+
+            for access in member_accesses(file.uast, &var.name) {
+                let inside = i == there && holder.span.contains(&access.span);
+                let this = matches!(
+                    access.expression.as_ref(),
+                    Expression::Raw { source, .. } if source == "this"
+                );
+                if this && !inside {
+                    handled.push(access.member_span.clone());
+                    continue;
+                }
+                let ours = is_instance_of(&access.expression, Some(holder), &bindings).ok_or_else(
+                    || {
+                        RefactorError::Unsupported(format!(
+                            "cannot tell whether `{}` refers to `{}.{}`",
+                            slice(file.source, &access.span),
+                            holder.name,
+                            var.name
+                        ))
+                    },
+                )?;
+                if ours {
+                    let through = step(&access.member_span)?;
+                    edits[i].push(TextEdit {
+                        start: through.start,
+                        end: through.end,
+                        replacement: String::new(),
+                    });
+                }
+                handled.push(access.member_span.clone());
+            }
+
+            let unlowered = unlowered_mentions(file.uast, &var.name)
+                .iter()
+                .any(|span| !handled.contains(span))
+                || classes(file.uast).into_iter().any(|other| {
+                    !unlowered_class_mentions(other, file.source, &var.name).is_empty()
+                });
+            if unlowered {
+                return Err(RefactorError::Unsupported(format!(
+                    "`{}` is referenced from code that could not be analyzed",
+                    var.name
+                )));
+            }
+        }
+
+        Ok(files
+            .iter()
+            .zip(edits)
+            .filter(|(_, edits)| !edits.is_empty())
+            .map(|(file, edits)| FileEdits {
+                path: file.path.to_string(),
+                edits,
+            })
+            .collect())
+    }
+}
+
+impl InlineClass {
+    /// Refuse classes whose members would not survive the move on their own.
+    fn check_class(&self, class: &ClassDef, source: &str) -> Result<(), RefactorError> {
+        if declares_constructor(source, class) {
+            return Err(RefactorError::Unsupported(format!(
+                "`{}` has a constructor",
+                self.class
+            )));
+        }
+        let text = slice(source, &class.span);
+        let open = text.find('{').unwrap_or(text.len());
+        if text[..open].contains(':') {
+            return Err(RefactorError::Unsupported(format!(
+                "`{}` derives from another type",
+                self.class
+            )));
+        }
+        let mentions = mentions(source, &class.span, &self.class);
+        if mentions.len() > 1 {
+            return Err(RefactorError::Unsupported(format!(
+                "`{}` refers to itself by name",
+                self.class
+            )));
+        }
+        // Constructors, nested types and the like are not lowered, and would
+        // be left behind.
+        let lowered: Vec<&Span> = class
+            .body
+            .iter()
+            .flatten()
+            .filter_map(|item| match item {
+                TopLevel::Function(func) => Some(&func.span),
+                TopLevel::Field(field) => Some(&field.span),
+                TopLevel::Property(property) => Some(&property.span),
+                _ => None,
+            })
+            .collect();
+        let inside = Span {
+            start: class.span.start + open + 1,
+            end: class.span.end - 1,
+        };
+        let unlowered = raw_tokens(slice(source, &inside), &inside)
+            .into_iter()
+            .filter(|token| !lowered.iter().any(|span| span.contains(&token.span)))
+            .any(|token| {
+                let line = &source[line_start(source, token.span.start)..token.span.start];
+                !line.contains("//")
+            });
+        if unlowered {
+            return Err(RefactorError::Unsupported(format!(
+                "`{}` has members that could not be analyzed",
+                self.class
+            )));
+        }
+        for item in class.body.iter().flatten() {
+            let (name, modifiers) = match item {
+                TopLevel::Function(func) => (func.name.as_str(), &func.modifiers),
+                TopLevel::Field(field) => (field.var_decls[0].name.as_str(), &field.modifiers),
+                TopLevel::Property(property) => (property.name.as_str(), &property.modifiers),
+                _ => continue,
+            };
+            if let Some(modifier) = modifiers.iter().flatten().find(|modifier| {
+                matches!(
+                    modifier.as_str(),
+                    "virtual" | "override" | "abstract" | "protected"
+                )
+            }) {
+                return Err(RefactorError::Unsupported(format!(
+                    "`{}` is {} and belongs to `{}`",
+                    name, modifier, self.class
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// The only class mentioning `class` by name, with the index of its file.
+    fn holder<'a>(
+        &self,
+        files: &[SourceFile<'a>],
+        class: &ClassDef,
+    ) -> Result<(usize, &'a ClassDef), RefactorError> {
+        let mut holders: Vec<(usize, &ClassDef)> = Vec::new();
+        for (i, file) in files.iter().enumerate() {
+            let whole = Span {
+                start: 0,
+                end: file.source.len(),
+            };
+            for span in mentions(file.source, &whole, &self.class) {
+                if class.span.contains(&span) {
+                    continue;
+                }
+                let Some(other) = classes(file.uast)
+                    .into_iter()
+                    .find(|other| other.span.contains(&span))
+                else {
+                    return Err(RefactorError::Unsupported(format!(
+                        "`{}` is referenced outside of any class",
+                        self.class
+                    )));
+                };
+                if !holders.iter().any(|(_, seen)| std::ptr::eq(*seen, other)) {
+                    holders.push((i, other));
+                }
+            }
+        }
+        match holders.as_slice() {
+            [] => Err(RefactorError::Unsupported(format!(
+                "`{}` is not referenced from any other class",
+                self.class
+            ))),
+            [holder] => Ok(*holder),
+            [first, second, ..] => Err(RefactorError::Unsupported(format!(
+                "`{}` is referenced from both `{}` and `{}`",
+                self.class, first.1.name, second.1.name
+            ))),
+        }
+    }
+
+    /// The field of `holder` that holds the instance, which must be the only
+    /// mention of the class in `holder`.
+    fn holding_field<'a>(
+        &self,
+        holder: &'a ClassDef,
+        source: &str,
+    ) -> Result<(&'a FieldDef, &'a VarDecl), RefactorError> {
+        let held: Vec<Member> = members(holder)
+            .into_iter()
+            .filter(|member| {
+                member
+                    .var_type()
+                    .is_some_and(|var_type| var_type.trim_end_matches('?') == self.class)
+            })
+            .collect();
+        let (field, var) = match held.as_slice() {
+            [] => {
+                return Err(RefactorError::Unsupported(format!(
+                    "`{}` refers to `{}` without holding one in a field",
+                    holder.name, self.class
+                )))
+            }
+            [Member::Field(field, var)] => (*field, *var),
+            [Member::Property(property)] => {
+                return Err(RefactorError::Unsupported(format!(
+                    "`{}` is a property",
+                    property.name
+                )))
+            }
+            _ => {
+                return Err(RefactorError::Unsupported(format!(
+                    "`{}` holds more than one `{}`",
+                    holder.name, self.class
+                )))
+            }
+        };
+        if field.var_decls.len() > 1 {
+            return Err(RefactorError::Unsupported(format!(
+                "`{}` is declared together with other fields",
+                var.name
+            )));
+        }
+        if field
+            .modifiers
+            .iter()
+            .flatten()
+            .any(|modifier| modifier == "static")
+        {
+            return Err(RefactorError::Unsupported(format!(
+                "`{}` is static and shared by every `{}`",
+                var.name, holder.name
+            )));
+        }
+        let created = format!("new {}()", self.class);
+        if var
+            .value
+            .as_deref()
+            .is_none_or(|value| slice(source, value.span()) != created)
+        {
+            return Err(RefactorError::Unsupported(format!(
+                "`{}` is not initialized with `{}`",
+                var.name, created
+            )));
+        }
+        if mentions(source, &holder.span, &self.class)
+            .iter()
+            .any(|span| !field.span.contains(span))
+        {
+            return Err(RefactorError::Unsupported(format!(
+                "`{}` mentions `{}` outside of `{}`",
+                holder.name, self.class, var.name
+            )));
+        }
+        Ok((field, var))
+    }
+
+    /// Refuse members whose names are taken in `holder`, and members that use
+    /// names which would start to refer to members of `holder`.
+    fn check_names(
+        &self,
+        uast: &TopLevel,
+        class: &ClassDef,
+        holder: &ClassDef,
+        field: &str,
+        source: &str,
+    ) -> Result<(), RefactorError> {
+        let own = member_names(class);
+        let taken: Vec<&str> = member_names(holder)
+            .into_iter()
+            .filter(|name| *name != field)
+            .collect();
+        if let Some(name) = own.iter().find(|name| taken.contains(name)) {
+            return Err(RefactorError::Conflict(format!(
+                "`{}` already has a member called `{}`",
+                holder.name, name
+            )));
+        }
+        let captured = |name: &str, user: &str| {
+            RefactorError::Conflict(format!(
+                "`{}` in `{}` would refer to `{}.{}`",
+                name, user, holder.name, name
+            ))
+        };
+
+        for item in class.body.iter().flatten() {
+            let (user, functions, values): (&str, Vec<&FunctionDef>, Vec<&Expression>) = match item
+            {
+                TopLevel::Function(func) => (&func.name, vec![func], vec![]),
+                TopLevel::Field(field) => (
+                    &field.var_decls[0].name,
+                    vec![],
+                    field
+                        .var_decls
+                        .iter()
+                        .filter_map(|var| var.value.as_deref())
+                        .collect(),
+                ),
+                TopLevel::Property(property) => (
+                    &property.name,
+                    property.accessors.iter().collect(),
+                    property.value.as_deref().into_iter().collect(),
+                ),
+                _ => continue,
+            };
+            for value in values {
+                if let Some(usage) = expression_usages(value)
+                    .into_iter()
+                    .find(|usage| taken.contains(&usage.name.as_str()))
+                {
+                    return Err(captured(&usage.name, user));
+                }
+            }
+            for func in functions {
+                for usage in &resolve_function(func).unresolved {
+                    let name = usage.name.as_str();
+                    if name == "this" && member_after(source, usage.span.end).is_none() {
+                        return Err(RefactorError::Unsupported(format!(
+                            "`{}` passes on `this`, which would be the `{}`",
+                            user, holder.name
+                        )));
+                    } else if name == "base" {
+                        return Err(RefactorError::Unsupported(format!(
+                            "`{}` uses the base class of `{}`",
+                            user, self.class
+                        )));
+                    } else if taken.contains(&name) {
+                        return Err(captured(name, user));
+                    }
+                }
+                for name in &taken {
+                    let called = find_calls(uast, name).into_iter().any(|site| {
+                        std::ptr::eq(site.caller, func) && site.receiver == Receiver::Implicit
+                    });
+                    if called {
+                        return Err(captured(name, user));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The spans of `name` in `span` of `source`, outside strings and comment lines.
+fn mentions(source: &str, span: &Span, name: &str) -> Vec<Span> {
+    raw_tokens(slice(source, span), span)
+        .into_iter()
+        .filter(|token| token.text == name)
+        .filter(|token| {
+            let line = &source[line_start(source, token.span.start)..token.span.start];
+            !line.contains("//")
+        })
+        .map(|token| token.span)
+        .collect()
+}
@@ -0,0 +1,402 @@
+use crate::analysis::calls::{calls_of, unlowered_mentions, CallPosition, CallSite, Receiver};
+use crate::analysis::locate::{contains_return, find_function, function_blocks, functions};
+use crate::analysis::scope::resolve;
+use crate::analysis::variables::{
+    expression_usages, function_declarations, function_usages, statement_declarations,
+    statement_usages, Usage,
+};
+use crate::syntax::{
+    evaluated_conditionally, evaluated_first, evaluated_first_in, has_side_effects,
+    has_unknown_effects, precedence, required_precedence, required_precedence_in, wrap,
+};
+use crate::text::{line_indent, reindent, removal_span, slice, splice};
+use crate::{RefactorError, Refactoring, TextEdit};
+use uast::*;
+
+/// Replace every call of a method with the method's body and delete the
+/// method.
+///
+/// Arguments are substituted for parameters directly when that cannot change
+/// behaviour, and bound to locals named after the parameters otherwise.
+pub struct InlineFunction {
+    pub name: String,
+}
+
+impl InlineFunction {
+    pub fn new(name: &str) -> Self {
+        InlineFunction {
+            name: name.to_string(),
+        }
+    }
+}
+
+impl Refactoring for InlineFunction {
+    fn apply(&self, uast: &TopLevel, source: &str) -> Result<Vec<TextEdit>, RefactorError> {
+        let context = find_function(uast, None, &|func| func.name == self.name)
+            .ok_or_else(|| RefactorError::NotFound(format!("method `{}`", self.name)))?;
+        let target = context.function;
+
+        let members = match context.class {
+            Some(class) => class.body.iter().flatten().flat_map(functions).collect(),
+            None => functions(uast),
+        };
+        let overloads = members
+            .into_iter()
+            .filter(|func| func.name == self.name)
+            .count();
+        if overloads > 1 {
+            return Err(RefactorError::Unsupported(format!(
+                "`{}` is overloaded",
+                self.name
+            )));
+        }
+
+        if let Some(modifier) = target
+            .modifiers
+            .iter()
+            .flatten()
+            .find(|modifier| matches!(modifier.as_str(), "virtual" | "override" | "abstract"))
+        {
+            return Err(RefactorError::Unsupported(format!(
+                "`{}` is {} and may be overridden",
+                self.name, modifier
+            )));
+        }
+
+        let body = function_blocks(target)
+            .next()
+            .ok_or_else(|| RefactorError::Unsupported(format!("`{}` has no body", self.name)))?;
+        let inlined = InlinedBody::new(target, body)?;
+
+        let bindings = resolve(uast);
+        let sites = calls_of(uast, context.class, &self.name, &bindings, source)?;
+        for site in &sites {
+            if std::ptr::eq(site.caller, target) {
+                return Err(RefactorError::Unsupported(format!(
+                    "`{}` is recursive",
+                    self.name
+                )));
+            }
+            if let Receiver::Other(_) = site.receiver {
+                return Err(RefactorError::Unsupported(format!(
+                    "`{}` is called on another object",
+                    self.name
+                )));
+            }
+            if site.position == CallPosition::LoopHeader {
+                return Err(RefactorError::Unsupported(format!(
+                    "`{}` is called from a loop condition",
+                    self.name
+                )));
+            }
+            if site.invocation.arguments.len() != inlined.parameters.len() {
+                return Err(RefactorError::Unsupported(format!(
+                    "a call of `{}` does not pass every parameter",
+                    self.name
+                )));
+            }
+            if site.position == CallPosition::Expression && inlined.value.is_none() {
+                return Err(RefactorError::Unsupported(format!(
+                    "`{}` does not return a value",
+                    self.name
+                )));
+            }
+        }
+
+        for (i, site) in sites.iter().enumerate() {
+            for other in &sites[i + 1..] {
+                let nested = site.invocation.span.overlaps(&other.invocation.span);
+                let shared = std::ptr::eq(site.statement, other.statement);
+                if nested || (shared && !inlined.statements.is_empty()) {
+                    return Err(RefactorError::Unsupported(format!(
+                        "`{}` is called more than once in a single statement",
+                        self.name
+                    )));
+                }
+            }
+        }
+
+        if unlowered_mentions(uast, &self.name)
+            .iter()
+            .any(|span| !target.span.contains(span))
+        {
+            return Err(RefactorError::Unsupported(format!(
+                "`{}` is referenced from code that could not be analyzed",
+                self.name
+            )));
+        }
+
+        let mut edits = Vec::new();
+        for site in &sites {
+            edits.extend(inlined.inline_at(site, source)?);
+        }
+
+        let removal = removal_span(source, &target.span);
+        edits.push(TextEdit {
+            start: removal.start,
+            end: removal.end,
+            replacement: String::new(),
+        });
+
+        Ok(edits)
+    }
+}
+
+/// The pieces of the inlined method that get copied to each call site.
+struct InlinedBody<'a> {
+    parameters: Vec<&'a VarDecl>,
+    /// Statements before the trailing `return`, if any.
+    statements: &'a [Statement],
+    /// The value of the trailing `return`, if any.
+    value: Option<&'a Expression>,
+    /// Locals declared by the body.
+    locals: Vec<&'a VarDecl>,
+}
+
+impl<'a> InlinedBody<'a> {
+    fn new(func: &'a FunctionDef, body: &'a Block) -> Result<Self, RefactorError> {
+        let (statements, value) = match body.statements.split_last() {
+            Some((Statement::ReturnStatement(ret), rest)) => (rest, ret.value.as_deref()),
+            _ => (body.statements.as_slice(), None),
+        };
+
+        if statements.iter().any(contains_return) {
+            return Err(RefactorError::Unsupported(format!(
+                "`{}` returns from somewhere other than its last statement",
+                func.name
+            )));
+        }
+
+        Ok(InlinedBody {
+            parameters: func.parameters.iter().flatten().collect(),
+            statements,
+            value,
+            locals: statements.iter().flat_map(statement_declarations).collect(),
+        })
+    }
+
+    fn inline_at(&self, site: &CallSite, source: &str) -> Result<Vec<TextEdit>, RefactorError> {
+        let caller_names: Vec<String> = function_declarations(site.caller)
+            .into_iter()
+            .map(|decl| decl.name.clone())
+            .chain(
+                function_usages(site.caller)
+                    .into_iter()
+                    .map(|usage| usage.name),
+            )
+            .collect();
+
+        if let Some(clash) = self
+            .locals
+            .iter()
+            .find(|decl| caller_names.contains(&decl.name))
+        {
+            return Err(RefactorError::Conflict(format!(
+                "`{}` is already used in `{}`",
+                clash.name, site.caller.name
+            )));
+        }
+
+        let indent = line_indent(source, site.statement.span().start).to_string();
+
+        // Decide how each argument reaches the body: substituted in place of
+        // the parameter, or bound to a local of the same name.
+        let mut bindings = Vec::new();
+        let mut substitutions = Vec::new();
+        let effectful = site
+            .invocation
+            .arguments
+            .iter()
+            .filter(|arg| has_side_effects(arg))
+            .count();
+        for (param, arg) in self.parameters.iter().zip(&site.invocation.arguments) {
+            if matches!(arg, Expression::Identifier(name, _) if *name == param.name) {
+                continue;
+            }
+
+            let reads = self.usages_of(&param.name);
+            let written = reads.iter().any(|usage| usage.access.writes());
+            let simple = matches!(arg, Expression::Identifier(..) | Expression::Literal(..));
+            // An argument with side effects moves into its single read only
+            // when it would still run exactly once and before anything else,
+            // as it did at the call.
+            let once = reads.len() == 1
+                && (!has_side_effects(arg) || effectful == 1 && self.runs_first(&reads[0].span));
+            if !written && (simple || once) {
+                substitutions.push((param.name.as_str(), arg));
+                continue;
+            }
+
+            if caller_names.contains(&param.name) {
+                return Err(RefactorError::Conflict(format!(
+                    "parameter `{}` would clash with a local of `{}`",
+                    param.name, site.caller.name
+                )));
+            }
+            bindings.push(format!(
+                "{}{} {} = {};",
+                indent,
+                param.var_type.as_deref().unwrap_or("var"),
+                param.name,
+                slice(source, arg.span())
+            ));
+        }
+
+        let mut lines = bindings;
+        if let (Some(first), Some(last)) = (self.statements.first(), self.statements.last()) {
+            let region = Span {
+                start: first.span().start,
+                end: last.span().end,
+            };
+            let edits = self.substitute(&region, &substitutions, source);
+            lines.push(reindent(
+                &splice(source, &region, &edits),
+                line_indent(source, region.start),
+                &indent,
+            ));
+        }
+        let value = self.value.map(|value| {
+            let edits = self.substitute(value.span(), &substitutions, source);
+            splice(source, value.span(), &edits)
+        });
+
+        let statement_span = site.statement.span();
+        match site.position {
+            CallPosition::Statement => {
+                if let (Some(value), Some(text)) = (self.value, &value) {
+                    if can_stand_alone(value) {
+                        lines.push(format!("{}{};", indent, text));
+                    }
+                }
+                if lines.is_empty() {
+                    let removal = removal_span(source, statement_span);
+                    return Ok(vec![TextEdit {
+                        start: removal.start,
+                        end: removal.end,
+                        replacement: String::new(),
+                    }]);
+                }
+                let replacement = lines.join("\n");
+                Ok(vec![TextEdit {
+                    start: statement_span.start,
+                    end: statement_span.end,
+                    replacement: replacement[indent.len()..].to_string(),
+                }])
+            }
+            _ => {
+                let value_expr = self.value.unwrap();
+                let text = wrap(
+                    &value.unwrap(),
+                    precedence(value_expr),
+                    required_precedence(site.statement, &site.invocation.span),
+                );
+
+                if !lines.is_empty()
+                    && evaluated_conditionally(site.statement, &site.invocation.span)
+                {
+                    return Err(RefactorError::Unsupported(format!(
+                        "a call in `{}` may not run every time its statement does, so the body cannot go before it",
+                        site.caller.name
+                    )));
+                }
+                let mut edits = vec![TextEdit {
+                    start: site.invocation.span.start,
+                    end: site.invocation.span.end,
+                    replacement: text,
+                }];
+                if !lines.is_empty() {
+                    let preamble = lines.join("\n");
+                    edits.insert(
+                        0,
+                        TextEdit {
+                            start: statement_span.start,
+                            end: statement_span.start,
+                            replacement: format!("{}\n{}", &preamble[indent.len()..], indent),
+                        },
+                    );
+                }
+                Ok(edits)
+            }
+        }
+    }
+
+    /// Whether the code at `span` in the body runs exactly once, before any
+    /// other effect of the body.
+    fn runs_first(&self, span: &Span) -> bool {
+        match self.value.filter(|value| value.span().contains(span)) {
+            Some(value) => {
+                !self.statements.iter().any(has_unknown_effects) && evaluated_first_in(value, span)
+            }
+            None => evaluated_first(self.statements, span),
+        }
+    }
+
+    fn usages_of(&self, name: &str) -> Vec<Usage> {
+        self.statements
+            .iter()
+            .flat_map(statement_usages)
+            .chain(self.value.into_iter().flat_map(expression_usages))
+            .filter(|usage| usage.name == name)
+            .collect()
+    }
+
+    /// Edits replacing parameter references inside `region` with arguments.
+    fn substitute(
+        &self,
+        region: &Span,
+        substitutions: &[(&str, &Expression)],
+        source: &str,
+    ) -> Vec<TextEdit> {
+        let mut edits = Vec::new();
+        let statements = self
+            .statements
+            .iter()
+            .filter(|stmt| region.contains(stmt.span()));
+
+        for stmt in statements {
+            for usage in statement_usages(stmt) {
+                if let Some((_, arg)) = substitutions.iter().find(|(name, _)| *name == usage.name) {
+                    edits.push(TextEdit {
+                        start: usage.span.start,
+                        end: usage.span.end,
+                        replacement: wrap(
+                            slice(source, arg.span()),
+                            precedence(arg),
+                            required_precedence(stmt, &usage.span),
+                        ),
+                    });
+                }
+            }
+        }
+
+        if let Some(value) = self.value.filter(|value| region == value.span()) {
+            for usage in expression_usages(value) {
+                if let Some((_, arg)) = substitutions.iter().find(|(name, _)| *name == usage.name) {
+                    edits.push(TextEdit {
+                        start: usage.span.start,
+                        end: usage.span.end,
+                        replacement: wrap(
+                            slice(source, arg.span()),
+                            precedence(arg),
+                            required_precedence_in(value, &usage.span),
+                        ),
+                    });
+                }
+            }
+        }
+
+        edits
+    }
+}
+
+/// Whether `expr` can be written as a statement of its own, which only calls,
+/// assignments and increments can.
+fn can_stand_alone(expr: &Expression) -> bool {
+    match expr {
+        Expression::Invocation(_) | Expression::Assignment(_) => true,
+        Expression::UnaryOp(op) => {
+            !matches!(op.operator, UnaryOperator::Negate | UnaryOperator::Not)
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,136 @@
+use crate::analysis::locate::{enclosing_function, innermost_statement, locate_declaration};
+use crate::analysis::variables::{expression_usages, statement_usages};
+use crate::syntax::{evaluated_first, has_side_effects, precedence, required_precedence, wrap};
+use crate::text::{grouping_parens, removal_span, slice};
+use crate::{RefactorError, Refactoring, TextEdit};
+use uast::*;
+
+/// Replace every reference to a local with its initializer and delete the
+/// declaration.
+///
+/// The local is picked by a byte offset inside the name of its declaration.
+pub struct InlineVariable {
+    pub offset: usize,
+}
+
+impl InlineVariable {
+    pub fn new(offset: usize) -> Self {
+        InlineVariable { offset }
+    }
+}
+
+impl Refactoring for InlineVariable {
+    fn apply(&self, uast: &TopLevel, source: &str) -> Result<Vec<TextEdit>, RefactorError> {
+        let position = Span {
+            start: self.offset,
+            end: self.offset,
+        };
+        let func = enclosing_function(uast, &position)
+            .ok_or_else(|| {
+                RefactorError::InvalidSelection("the offset is not inside a method".to_string())
+            })?
+            .function;
+        let location = locate_declaration(func, self.offset).ok_or_else(|| {
+            RefactorError::InvalidSelection(
+                "the offset is not on the name of a local declaration".to_string(),
+            )
+        })?;
+        let var = location.var;
+
+        let value = var.value.as_deref().ok_or_else(|| {
+            RefactorError::Unsupported(format!("`{}` has no initializer", var.name))
+        })?;
+        if location.statement.var_decls.len() > 1 {
+            return Err(RefactorError::Unsupported(format!(
+                "`{}` is declared together with other variables",
+                var.name
+            )));
+        }
+
+        // C# does not allow a nested scope to redeclare a local, so every
+        // later use of the name in the declaring block refers to this local.
+        let scope = &location.block.statements[location.index + 1..];
+        let inputs: Vec<String> = expression_usages(value)
+            .into_iter()
+            .map(|usage| usage.name)
+            .collect();
+
+        let mut references = Vec::new();
+        let mut inputs_changed = false;
+        for stmt in scope {
+            let usages = statement_usages(stmt);
+            inputs_changed |= usages
+                .iter()
+                .any(|usage| usage.access.writes() && inputs.contains(&usage.name));
+
+            // A statement that both uses the local and changes an input is
+            // refused as well, since it may do so in a loop or before the use.
+            let mut references_here = usages
+                .iter()
+                .filter(|usage| usage.name == var.name)
+                .peekable();
+            if references_here.peek().is_some() && inputs_changed {
+                return Err(RefactorError::Unsupported(format!(
+                    "a variable read by the initializer of `{}` changes before it is used",
+                    var.name
+                )));
+            }
+            for usage in references_here {
+                if usage.access.writes() {
+                    return Err(RefactorError::Unsupported(format!(
+                        "`{}` is assigned after its declaration",
+                        var.name
+                    )));
+                }
+                references.push(usage.clone());
+            }
+        }
+
+        if references.len() > 1 && has_side_effects(value) {
+            return Err(RefactorError::Unsupported(format!(
+                "the initializer of `{}` has side effects and would run more than once",
+                var.name
+            )));
+        }
+        // Its single use must also run unconditionally, once, and before any
+        // other effect, as the declaration did.
+        if has_side_effects(value)
+            && references
+                .iter()
+                .any(|usage| !evaluated_first(scope, &usage.span))
+        {
+            return Err(RefactorError::Unsupported(format!(
+                "the initializer of `{}` has side effects and would not run where it did",
+                var.name
+            )));
+        }
+
+        let text = slice(source, value.span());
+        let mut edits: Vec<TextEdit> = references
+            .iter()
+            .map(|usage| {
+                let replacement = if grouping_parens(source, &usage.span) != usage.span {
+                    text.to_string()
+                } else {
+                    let required = innermost_statement(location.block, &usage.span)
+                        .map_or(0, |stmt| required_precedence(stmt, &usage.span));
+                    wrap(text, precedence(value), required)
+                };
+                TextEdit {
+                    start: usage.span.start,
+                    end: usage.span.end,
+                    replacement,
+                }
+            })
+            .collect();
+
+        let removal = removal_span(source, &location.statement.span);
+        edits.push(TextEdit {
+            start: removal.start,
+            end: removal.end,
+            replacement: String::new(),
+        });
+
+        Ok(edits)
+    }
+}
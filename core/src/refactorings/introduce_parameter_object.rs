@@ -0,0 +1,322 @@
+use crate::analysis::calls::{calls_of, unlowered_mentions};
+use crate::analysis::locate::{find_function, functions};
+use crate::analysis::members::member_names;
+use crate::analysis::scope::{resolve, resolve_function};
+use crate::analysis::variables::{function_declarations, function_usages};
+use crate::syntax::has_side_effects;
+use crate::text::{argument_span, line_indent, replace_list, slice};
+use crate::{RefactorError, Refactoring, TextEdit};
+use uast::*;
+
+/// Replace a group of parameters that travel together with a single
+/// parameter of a new record type.
+///
+/// The record is declared after the class, with a property per parameter
+/// named after it in PascalCase. The body reads the values from the new
+/// parameter and every call constructs the record from the old arguments.
+pub struct IntroduceParameterObject {
+    pub method: String,
+    pub parameters: Vec<String>,
+    pub class_name: String,
+    pub parameter_name: String,
+}
+
+impl IntroduceParameterObject {
+    pub fn new(method: &str, parameters: &[&str], class_name: &str, parameter_name: &str) -> Self {
+        IntroduceParameterObject {
+            method: method.to_string(),
+            parameters: parameters.iter().map(|name| name.to_string()).collect(),
+            class_name: class_name.to_string(),
+            parameter_name: parameter_name.to_string(),
+        }
+    }
+}
+
+impl Refactoring for IntroduceParameterObject {
+    fn apply(&self, uast: &TopLevel, source: &str) -> Result<Vec<TextEdit>, RefactorError> {
+        let context = find_function(uast, None, &|func| func.name == self.method)
+            .ok_or_else(|| RefactorError::NotFound(format!("method `{}`", self.method)))?;
+        let target = context.function;
+
+        let overloads = functions(uast)
+            .into_iter()
+            .filter(|func| func.name == self.method)
+            .count();
+        if overloads > 1 {
+            return Err(RefactorError::Unsupported(format!(
+                "`{}` is overloaded",
+                self.method
+            )));
+        }
+        if let Some(modifier) = target
+            .modifiers
+            .iter()
+            .flatten()
+            .find(|modifier| matches!(modifier.as_str(), "virtual" | "override" | "abstract"))
+        {
+            return Err(RefactorError::Unsupported(format!(
+                "`{}` is {} and its signature is shared with other classes",
+                self.method, modifier
+            )));
+        }
+
+        let parameters: Vec<&VarDecl> = target.parameters.iter().flatten().collect();
+        let grouped = self.grouped_parameters(&parameters, source)?;
+        let properties: Vec<String> = grouped
+            .iter()
+            .map(|&i| pascal_case(&parameters[i].name))
+            .collect();
+        self.check_names(uast, context.class, target, &properties)?;
+
+        let bindings = resolve_function(target);
+        for &i in &grouped {
+            let param = parameters[i];
+            let references = bindings.references_to(param);
+            if references
+                .iter()
+                .any(|reference| reference.access.is_some_and(|access| access.writes()))
+            {
+                return Err(RefactorError::Unsupported(format!(
+                    "parameter `{}` is assigned in `{}`",
+                    param.name, self.method
+                )));
+            }
+        }
+
+        let uast_bindings = resolve(uast);
+        if uast_bindings
+            .unresolved
+            .iter()
+            .any(|usage| usage.name == self.method)
+        {
+            return Err(RefactorError::Unsupported(format!(
+                "`{}` is used as a delegate",
+                self.method
+            )));
+        }
+        if unlowered_mentions(uast, &self.method)
+            .iter()
+            .any(|span| !target.span.contains(span))
+        {
+            return Err(RefactorError::Unsupported(format!(
+                "`{}` is referenced from code that could not be analyzed",
+                self.method
+            )));
+        }
+
+        // The new parameter takes the place of the first grouped one.
+        let position = grouped[0];
+        let declaration = format!("{} {}", self.class_name, self.parameter_name);
+        let mut list: Vec<&str> = Vec::new();
+        for (i, param) in parameters.iter().enumerate() {
+            if i == position {
+                list.push(&declaration);
+            }
+            if !grouped.contains(&i) {
+                list.push(slice(source, &param.span));
+            }
+        }
+        let mut edits = vec![replace_list(
+            source,
+            parameters.iter().map(|param| param.span.clone()).collect(),
+            target.name_span.end,
+            list.join(", "),
+        )];
+
+        for (&i, property) in grouped.iter().zip(&properties) {
+            for reference in bindings.references_to(parameters[i]) {
+                if reference.access.is_some() {
+                    edits.push(TextEdit {
+                        start: reference.span.start,
+                        end: reference.span.end,
+                        replacement: format!("{}.{}", self.parameter_name, property),
+                    });
+                }
+            }
+        }
+
+        for site in calls_of(uast, context.class, &self.method, &uast_bindings, source)? {
+            edits.push(self.rewrite_call(site.invocation, &grouped, parameters.len(), source)?);
+        }
+
+        let fields: Vec<String> = grouped
+            .iter()
+            .zip(&properties)
+            .map(|(&i, property)| {
+                format!(
+                    "{} {}",
+                    parameters[i].var_type.as_deref().unwrap_or("object"),
+                    property
+                )
+            })
+            .collect();
+        let anchor = context.class.map_or(&target.span, |class| &class.span);
+        edits.push(TextEdit {
+            start: anchor.end,
+            end: anchor.end,
+            replacement: format!(
+                "\n\n{}public record {}({});",
+                line_indent(source, anchor.start),
+                self.class_name,
+                fields.join(", ")
+            ),
+        });
+
+        Ok(edits)
+    }
+}
+
+impl IntroduceParameterObject {
+    /// The positions of the parameters to group, in declaration order.
+    fn grouped_parameters(
+        &self,
+        parameters: &[&VarDecl],
+        source: &str,
+    ) -> Result<Vec<usize>, RefactorError> {
+        if self.parameters.is_empty() {
+            return Err(RefactorError::InvalidSelection(
+                "no parameters were given to group".to_string(),
+            ));
+        }
+        let mut grouped = Vec::new();
+        for name in &self.parameters {
+            let i = parameters
+                .iter()
+                .position(|param| param.name == *name)
+                .ok_or_else(|| {
+                    RefactorError::NotFound(format!("parameter `{}` of `{}`", name, self.method))
+                })?;
+            let text = slice(source, &parameters[i].span);
+            if ["ref ", "out ", "in ", "params ", "this "]
+                .iter()
+                .any(|modifier| text.starts_with(modifier))
+            {
+                return Err(RefactorError::Unsupported(format!(
+                    "parameter `{}` cannot become a property",
+                    name
+                )));
+            }
+            if !grouped.contains(&i) {
+                grouped.push(i);
+            }
+        }
+        grouped.sort();
+        Ok(grouped)
+    }
+
+    fn check_names(
+        &self,
+        uast: &TopLevel,
+        class: Option<&ClassDef>,
+        target: &FunctionDef,
+        properties: &[String],
+    ) -> Result<(), RefactorError> {
+        let class_taken = class.is_some_and(|class| {
+            class.name == self.class_name || member_names(class).contains(&self.class_name.as_str())
+        }) || matches!(uast, TopLevel::Class(class) if class.name == self.class_name);
+        if class_taken {
+            return Err(RefactorError::Conflict(format!(
+                "`{}` is already declared",
+                self.class_name
+            )));
+        }
+
+        for (i, property) in properties.iter().enumerate() {
+            if *property == self.class_name || properties[..i].contains(property) {
+                return Err(RefactorError::Conflict(format!(
+                    "the record cannot have two members named `{}`",
+                    property
+                )));
+            }
+        }
+
+        let parameter_taken = function_declarations(target)
+            .into_iter()
+            .map(|decl| decl.name.clone())
+            .chain(function_usages(target).into_iter().map(|usage| usage.name))
+            .any(|name| name == self.parameter_name && !self.parameters.contains(&name));
+        if parameter_taken {
+            return Err(RefactorError::Conflict(format!(
+                "`{}` is already used in `{}`",
+                self.parameter_name, self.method
+            )));
+        }
+        Ok(())
+    }
+
+    fn rewrite_call(
+        &self,
+        invocation: &Invocation,
+        grouped: &[usize],
+        arity: usize,
+        source: &str,
+    ) -> Result<TextEdit, RefactorError> {
+        if invocation.arguments.len() != arity {
+            return Err(RefactorError::Unsupported(format!(
+                "a call of `{}` does not pass every parameter",
+                self.method
+            )));
+        }
+        let arguments: Vec<Span> = invocation
+            .arguments
+            .iter()
+            .map(|arg| argument_span(source, arg.span()))
+            .collect();
+        let named = invocation
+            .arguments
+            .iter()
+            .zip(&arguments)
+            .any(|(arg, span)| source[span.start..arg.span().start].contains(':'));
+        if named {
+            return Err(RefactorError::Unsupported(format!(
+                "a call of `{}` uses named arguments",
+                self.method
+            )));
+        }
+
+        // Grouped arguments move up to the first grouped position, so any
+        // argument they jump over must not have side effects.
+        let (first, last) = (grouped[0], grouped[grouped.len() - 1]);
+        let jumped = (first..last).any(|i| {
+            !grouped.contains(&i)
+                && has_side_effects(&invocation.arguments[i])
+                && grouped
+                    .iter()
+                    .any(|&g| g > i && has_side_effects(&invocation.arguments[g]))
+        });
+        if jumped {
+            return Err(RefactorError::Unsupported(format!(
+                "a call of `{}` would evaluate arguments with side effects in a different order",
+                self.method
+            )));
+        }
+
+        let values: Vec<&str> = grouped
+            .iter()
+            .map(|&i| slice(source, &arguments[i]))
+            .collect();
+        let object = format!("new {}({})", self.class_name, values.join(", "));
+        let mut list: Vec<&str> = Vec::new();
+        for (i, span) in arguments.iter().enumerate() {
+            if i == first {
+                list.push(&object);
+            }
+            if !grouped.contains(&i) {
+                list.push(slice(source, span));
+            }
+        }
+        Ok(replace_list(
+            source,
+            arguments,
+            invocation.function.span().end,
+            list.join(", "),
+        ))
+    }
+}
+
+fn pascal_case(name: &str) -> String {
+    let mut chars = name.chars();
+    chars.next().map_or_else(String::new, |first| {
+        first.to_uppercase().chain(chars).collect()
+    })
+}
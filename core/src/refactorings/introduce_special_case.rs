@@ -0,0 +1,811 @@
+use crate::analysis::calls::invoked_name;
+use crate::analysis::locate::{
+    block_statements, function_blocks, function_expressions, nested_blocks, statement_expressions,
+    subexpressions,
+};
+use crate::analysis::members::{classes, member_names, members, Member};
+use crate::analysis::scope::{resolve_function, Bindings};
+use crate::analysis::variables::raw_tokens;
+use crate::syntax::same_expression;
+use crate::text::{declares_constructor, declares_type, line_indent, slice, MethodStyle};
+use crate::{RefactorError, Refactoring, TextEdit};
+use uast::*;
+
+/// Replace `null` standing in for a missing instance of a class with a
+/// `Null` subclass that behaves the way every caller expected.
+///
+/// A conditional such as `customer == null ? "occupant" : customer.Name`
+/// becomes `customer.Name`, and `NullCustomer` overrides `Name` to return
+/// `"occupant"`, which makes `Name` virtual in `Customer`. Any other `== null`
+/// or `!= null` test becomes a check of a new `IsNull` property. A `null`
+/// assigned to or initializing a checked variable is replaced with a
+/// `NullCustomer`; other `null`s never reach a check and are left alone.
+///
+/// Every value a checked variable can hold must be one of those `null`s or a
+/// newly created instance, so parameters, properties, fields that are never
+/// initialized or can be set elsewhere, and variables assigned any other value
+/// are refused. So are variables also read by `??`, `?.`, `is null`,
+/// `ReferenceEquals` or code that is not understood, which would take the
+/// special case for a present value.
+pub struct IntroduceSpecialCase {
+    pub class: String,
+}
+
+impl IntroduceSpecialCase {
+    pub fn new(class: &str) -> Self {
+        IntroduceSpecialCase {
+            class: class.to_string(),
+        }
+    }
+}
+
+/// Where a value of the class is kept.
+#[derive(Clone, Copy)]
+enum Holder<'a> {
+    Local(&'a VarDecl),
+    Field(&'a ClassDef, &'a str),
+}
+
+impl PartialEq for Holder<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Holder::Local(a), Holder::Local(b)) => std::ptr::eq(*a, *b),
+            (Holder::Field(a, x), Holder::Field(b, y)) => std::ptr::eq(*a, *b) && x == y,
+            _ => false,
+        }
+    }
+}
+
+/// Code relying on values of the class being `null`.
+enum NullCheck<'a> {
+    /// A conditional that picks `default` when the subject is `null` and
+    /// reads `member` from it otherwise. `access` is the text of that read.
+    Default {
+        span: Span,
+        member: String,
+        invoked: bool,
+        default: &'a Expression,
+        access: String,
+        holder: Holder<'a>,
+    },
+    /// A comparison of `subject` with `null`.
+    Test {
+        span: Span,
+        subject: String,
+        is_null: bool,
+        holder: Holder<'a>,
+    },
+    /// A `null` stored or returned where the class is expected, and the
+    /// variable it is stored in.
+    Producer {
+        span: Span,
+        holder: Option<Holder<'a>>,
+    },
+}
+
+impl<'a> NullCheck<'a> {
+    /// The variable whose `null`s this checks.
+    fn checked(&self) -> Option<Holder<'a>> {
+        match self {
+            NullCheck::Default { holder, .. } | NullCheck::Test { holder, .. } => Some(*holder),
+            NullCheck::Producer { .. } => None,
+        }
+    }
+}
+
+impl Refactoring for IntroduceSpecialCase {
+    fn apply(&self, uast: &TopLevel, source: &str) -> Result<Vec<TextEdit>, RefactorError> {
+        let target = classes(uast)
+            .into_iter()
+            .find(|class| class.name == self.class)
+            .ok_or_else(|| RefactorError::NotFound(format!("class `{}`", self.class)))?;
+        if let Some(modifier) = target
+            .modifiers
+            .iter()
+            .flatten()
+            .find(|modifier| matches!(modifier.as_str(), "sealed" | "static"))
+        {
+            return Err(RefactorError::Unsupported(format!(
+                "`{}` is {} and cannot have subclasses",
+                self.class, modifier
+            )));
+        }
+        if declares_constructor(source, target) {
+            return Err(RefactorError::Unsupported(format!(
+                "`{}` declares constructors, which the special case would have to call",
+                self.class
+            )));
+        }
+        let special = format!("Null{}", self.class);
+        if declares_type(source, &special) {
+            return Err(RefactorError::Conflict(format!(
+                "a class named `{}` already exists",
+                special
+            )));
+        }
+
+        let mut checks = Vec::new();
+        for class in classes(uast) {
+            collect_checks(class, &self.class, source, &mut checks)?;
+        }
+        if checks.is_empty() {
+            return Err(RefactorError::NotFound(format!(
+                "null checks on `{}`",
+                self.class
+            )));
+        }
+
+        // Every conditional reading the same member has to agree on the value
+        // that stands in for it.
+        let mut defaults: Vec<(&str, bool, &Expression)> = Vec::new();
+        for check in &checks {
+            if let NullCheck::Default {
+                member,
+                invoked,
+                default,
+                ..
+            } = check
+            {
+                match defaults.iter().find(|(name, ..)| name == member) {
+                    Some((_, other_invoked, other)) => {
+                        if other_invoked != invoked || !same_expression(other, default) {
+                            return Err(RefactorError::Conflict(format!(
+                                "the null checks disagree on what a missing `{}` gives for `{}`",
+                                self.class, member
+                            )));
+                        }
+                    }
+                    None => defaults.push((member, *invoked, default)),
+                }
+            }
+        }
+
+        let mut edits = Vec::new();
+        let mut overrides = Vec::new();
+        let tests = checks
+            .iter()
+            .any(|check| matches!(check, NullCheck::Test { .. }));
+        if tests {
+            if member_names(target).contains(&"IsNull") {
+                return Err(RefactorError::Conflict(format!(
+                    "`{}` already declares a member named `IsNull`",
+                    self.class
+                )));
+            }
+            overrides.push("public override bool IsNull => true;".to_string());
+        }
+        for (member, invoked, default) in &defaults {
+            let (declaration, overriding) = overridable(target, member, *invoked, source)?;
+            edits.extend(declaration);
+            overrides.push(format!(
+                "{} => {};",
+                overriding,
+                slice(source, default.span())
+            ));
+        }
+
+        let checked: Vec<Holder> = checks.iter().filter_map(NullCheck::checked).collect();
+        for check in &checks {
+            let (span, replacement) = match check {
+                NullCheck::Default { span, access, .. } => (span, access.clone()),
+                NullCheck::Test {
+                    span,
+                    subject,
+                    is_null,
+                    ..
+                } => (
+                    span,
+                    format!("{}{}.IsNull", if *is_null { "" } else { "!" }, subject),
+                ),
+                NullCheck::Producer { span, holder } => {
+                    if !holder.is_some_and(|holder| checked.contains(&holder)) {
+                        continue;
+                    }
+                    (span, format!("new {}()", special))
+                }
+            };
+            edits.push(TextEdit {
+                start: span.start,
+                end: span.end,
+                replacement,
+            });
+        }
+
+        let class_indent = line_indent(source, target.span.start);
+        let open = source[target.span.start..target.span.end]
+            .find('{')
+            .map_or(target.span.start, |i| target.span.start + i);
+        let allman = source[..open].ends_with(&format!("\n{}", class_indent));
+        let last = target.body.iter().flatten().last().map(|item| match item {
+            TopLevel::Function(func) => func.span.clone(),
+            TopLevel::Field(field) => field.span.clone(),
+            TopLevel::Property(property) => property.span.clone(),
+            _ => target.span.clone(),
+        });
+        let member_indent = last
+            .as_ref()
+            .map_or(format!("{}    ", class_indent), |span| {
+                line_indent(source, span.start).to_string()
+            });
+        if tests {
+            let (at, separator) = match &last {
+                Some(span) => (span.end, "\n\n"),
+                None => (open + 1, "\n"),
+            };
+            edits.push(TextEdit {
+                start: at,
+                end: at,
+                replacement: format!(
+                    "{}{}public virtual bool IsNull => false;",
+                    separator, member_indent
+                ),
+            });
+        }
+
+        let style = MethodStyle {
+            member_indent: class_indent.to_string(),
+            body_indent: member_indent.clone(),
+            allman,
+        };
+        let access = target
+            .modifiers
+            .iter()
+            .flatten()
+            .find(|modifier| matches!(modifier.as_str(), "public" | "internal"))
+            .map_or(String::new(), |modifier| format!("{} ", modifier));
+        let body = overrides
+            .iter()
+            .map(|member| format!("{}{}", member_indent, member))
+            .collect::<Vec<_>>()
+            .join("\n");
+        edits.push(TextEdit {
+            start: target.span.end,
+            end: target.span.end,
+            replacement: format!(
+                "\n\n{}",
+                style.render(
+                    &format!("{}class {} : {}", access, special, self.class),
+                    &body
+                )
+            ),
+        });
+        Ok(edits)
+    }
+}
+
+fn is_type(var_type: Option<&str>, name: &str) -> bool {
+    var_type.is_some_and(|var_type| var_type.trim_end_matches('?') == name)
+}
+
+fn is_null(expr: &Expression) -> bool {
+    matches!(expr, Expression::Raw { source, .. } if source == "null")
+}
+
+fn is_created(expr: &Expression) -> bool {
+    matches!(expr, Expression::Raw { source, .. } if source.starts_with("new "))
+}
+
+/// The local variable or field of `class` that `expr` reads.
+fn holder_of<'a>(
+    class: &'a ClassDef,
+    bindings: &Bindings<'a>,
+    expr: &Expression,
+) -> Option<Holder<'a>> {
+    let name = match expr {
+        Expression::Identifier(name, span) => {
+            if let Some(decl) = bindings.declaration_at(span.start) {
+                return Some(Holder::Local(decl));
+            }
+            name
+        }
+        Expression::MemberAccess(ma) if matches!(ma.expression.as_ref(), Expression::Raw { source, .. } if source == "this") => {
+            &ma.member
+        }
+        _ => return None,
+    };
+    members(class).into_iter().find_map(|member| match member {
+        Member::Field(_, var) if var.name == *name => Some(Holder::Field(class, &var.name)),
+        _ => None,
+    })
+}
+
+/// The methods, constructors and property accessors of `class`.
+fn class_functions(class: &ClassDef) -> Vec<&FunctionDef> {
+    class
+        .body
+        .iter()
+        .flatten()
+        .flat_map(|item| match item {
+            TopLevel::Function(func) => vec![func],
+            TopLevel::Property(property) => property.accessors.iter().collect(),
+            _ => vec![],
+        })
+        .collect()
+}
+
+/// Collect the null checks in the methods and field initializers of `class`
+/// that concern values of type `target`.
+fn collect_checks<'a>(
+    class: &'a ClassDef,
+    target: &str,
+    source: &str,
+    checks: &mut Vec<NullCheck<'a>>,
+) -> Result<(), RefactorError> {
+    for item in class.body.iter().flatten() {
+        match item {
+            TopLevel::Function(func) => {
+                let mut finder = Finder {
+                    class,
+                    func,
+                    bindings: resolve_function(func),
+                    target,
+                    source,
+                    checks: Vec::new(),
+                };
+                for block in function_blocks(func) {
+                    finder.visit_block(block)?;
+                }
+                checks.append(&mut finder.checks);
+            }
+            TopLevel::Field(field) => {
+                for var in &field.var_decls {
+                    if let Some(value) = var.value.as_deref() {
+                        if is_null(value) && is_type(var.var_type.as_deref(), target) {
+                            checks.push(NullCheck::Producer {
+                                span: value.span().clone(),
+                                holder: Some(Holder::Field(class, &var.name)),
+                            });
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+struct Finder<'a, 'b> {
+    class: &'a ClassDef,
+    func: &'a FunctionDef,
+    bindings: Bindings<'a>,
+    target: &'b str,
+    source: &'b str,
+    checks: Vec<NullCheck<'a>>,
+}
+
+impl<'a> Finder<'a, '_> {
+    fn visit_block(&mut self, block: &'a Block) -> Result<(), RefactorError> {
+        for stmt in &block.statements {
+            self.visit_statement(stmt)?;
+        }
+        Ok(())
+    }
+
+    fn visit_statement(&mut self, stmt: &'a Statement) -> Result<(), RefactorError> {
+        match stmt {
+            Statement::DeclStmt(decl_stmt) => {
+                for var in &decl_stmt.var_decls {
+                    if let Some(value) = var.value.as_deref() {
+                        if is_null(value) && is_type(var.var_type.as_deref(), self.target) {
+                            self.produce(value, Some(Holder::Local(var)));
+                        }
+                    }
+                }
+            }
+            Statement::ReturnStatement(ReturnStatement {
+                value: Some(value), ..
+            }) if is_null(value) && is_type(self.func.return_type.as_deref(), self.target) => {
+                self.produce(value, None)
+            }
+            _ => {}
+        }
+        for (expr, _) in statement_expressions(stmt) {
+            self.visit_expression(expr)?;
+        }
+        for block in nested_blocks(stmt) {
+            self.visit_block(block)?;
+        }
+        Ok(())
+    }
+
+    fn visit_expression(&mut self, expr: &'a Expression) -> Result<(), RefactorError> {
+        match expr {
+            Expression::Conditional(cond) => {
+                if let Some(check) = self.default(cond)? {
+                    self.checks.push(check);
+                    return Ok(());
+                }
+            }
+            Expression::BinaryOp(op) => {
+                if let Some((subject, is_null, holder)) = self.null_test(op)? {
+                    self.checks.push(NullCheck::Test {
+                        span: op.span.clone(),
+                        subject: slice(self.source, subject.span()).to_string(),
+                        is_null,
+                        holder,
+                    });
+                    return Ok(());
+                }
+            }
+            Expression::Assignment(assign)
+                if assign.operator == AssignmentOperator::Assign
+                    && is_null(&assign.right)
+                    && self.is_target(&assign.left) =>
+            {
+                self.produce(
+                    &assign.right,
+                    holder_of(self.class, &self.bindings, &assign.left),
+                );
+                return Ok(());
+            }
+            _ => {}
+        }
+        for nested in subexpressions(expr) {
+            self.visit_expression(nested)?;
+        }
+        Ok(())
+    }
+
+    fn produce(&mut self, value: &Expression, holder: Option<Holder<'a>>) {
+        self.checks.push(NullCheck::Producer {
+            span: value.span().clone(),
+            holder,
+        });
+    }
+
+    /// Whether `expr` names a variable, field or property of the target type.
+    fn is_target(&self, expr: &Expression) -> bool {
+        let name = match expr {
+            Expression::Identifier(name, span) => {
+                if let Some(decl) = self.bindings.declaration_at(span.start) {
+                    return is_type(decl.var_type.as_deref(), self.target);
+                }
+                name
+            }
+            Expression::MemberAccess(ma) if matches!(ma.expression.as_ref(), Expression::Raw { source, .. } if source == "this") => {
+                &ma.member
+            }
+            _ => return false,
+        };
+        members(self.class)
+            .iter()
+            .any(|member| member.name() == name && is_type(member.var_type(), self.target))
+    }
+
+    /// The subject of `subject == null` or `subject != null`, whether the
+    /// comparison holds when it is `null`, and the variable it reads.
+    #[allow(clippy::type_complexity)]
+    fn null_test(
+        &self,
+        op: &'a BinaryOp,
+    ) -> Result<Option<(&'a Expression, bool, Holder<'a>)>, RefactorError> {
+        let is_null_test = match op.operator {
+            BinaryOperator::Equal => true,
+            BinaryOperator::NotEqual => false,
+            _ => return Ok(None),
+        };
+        let subject = match (is_null(&op.left), is_null(&op.right)) {
+            (false, true) => &op.left,
+            (true, false) => &op.right,
+            _ => return Ok(None),
+        };
+        if !self.is_target(subject) {
+            return Ok(None);
+        }
+        let holder = self.never_left_null(subject)?;
+        self.only_checked(holder, subject)?;
+        Ok(Some((subject.as_ref(), is_null_test, holder)))
+    }
+
+    /// Refuse a subject that may hold a `null` the special case does not
+    /// replace, since the check on it would then throw.
+    fn never_left_null(&self, subject: &Expression) -> Result<Holder<'a>, RefactorError> {
+        let text = slice(self.source, subject.span());
+        let refuse = |reason: String| Err(RefactorError::Unsupported(reason));
+        let mut values: Vec<&Expression> = Vec::new();
+        let local = match subject {
+            Expression::Identifier(_, span) => self.bindings.declaration_at(span.start),
+            _ => None,
+        };
+        let holder;
+        if let Some(decl) = local {
+            if self
+                .func
+                .parameters
+                .iter()
+                .flatten()
+                .any(|param| std::ptr::eq(param, decl))
+            {
+                return refuse(format!(
+                    "`{}` is a parameter, so callers may still pass `null`",
+                    text
+                ));
+            }
+            for expr in function_expressions(self.func) {
+                if let Expression::Assignment(assign) = expr {
+                    if matches!(assign.left.as_ref(), Expression::Identifier(_, span)
+                        if self.bindings.declaration_at(span.start).is_some_and(|d| std::ptr::eq(d, decl)))
+                    {
+                        values.push(&assign.right);
+                    }
+                }
+            }
+            let writes = self
+                .bindings
+                .references_to(decl)
+                .iter()
+                .filter(|reference| reference.access.is_some_and(|access| access.writes()))
+                .count();
+            if writes > values.len() {
+                return refuse(format!(
+                    "`{}` is written in a way that may leave it `null`",
+                    text
+                ));
+            }
+            values.extend(decl.value.as_deref());
+            holder = Holder::Local(decl);
+        } else {
+            let name = match subject {
+                Expression::Identifier(name, _) => name,
+                Expression::MemberAccess(ma) => &ma.member,
+                _ => return refuse(format!("`{}` may still be `null`", text)),
+            };
+            let field = members(self.class)
+                .into_iter()
+                .find(|member| member.name() == name);
+            let Some(Member::Field(field, var)) = field else {
+                return refuse(format!(
+                    "`{}` is a property, so it may still return `null`",
+                    text
+                ));
+            };
+            if field
+                .modifiers
+                .iter()
+                .flatten()
+                .any(|modifier| matches!(modifier.as_str(), "public" | "protected" | "internal"))
+            {
+                return refuse(format!(
+                    "`{}` is not private, so code elsewhere may set it to `null`",
+                    text
+                ));
+            }
+            let Some(value) = var.value.as_deref() else {
+                return refuse(format!(
+                    "`{}` is never initialized, so it may still be `null`",
+                    text
+                ));
+            };
+            values.push(value);
+            holder = Holder::Field(self.class, &var.name);
+            for func in class_functions(self.class) {
+                let bindings = resolve_function(func);
+                for expr in function_expressions(func) {
+                    let Expression::Assignment(assign) = expr else {
+                        continue;
+                    };
+                    let assigned = match assign.left.as_ref() {
+                        Expression::Identifier(left, span) => {
+                            left == name && bindings.declaration_at(span.start).is_none()
+                        }
+                        Expression::MemberAccess(ma) => {
+                            ma.member == *name
+                                && matches!(ma.expression.as_ref(), Expression::Raw { source, .. } if source == "this")
+                        }
+                        _ => false,
+                    };
+                    if assigned {
+                        values.push(&assign.right);
+                    }
+                }
+            }
+        }
+        match values
+            .iter()
+            .find(|value| !is_null(value) && !is_created(value))
+        {
+            Some(value) => refuse(format!(
+                "`{}` may be set to `{}`, which may still be `null`",
+                text,
+                slice(self.source, value.span())
+            )),
+            None => Ok(holder),
+        }
+    }
+
+    /// Refuse a subject that is also read in a way that tells `null` apart
+    /// without one of the checks being replaced, since the special case would
+    /// then pass for a present value there.
+    fn only_checked(&self, holder: Holder<'a>, subject: &Expression) -> Result<(), RefactorError> {
+        let (name, funcs) = match holder {
+            Holder::Local(decl) => (decl.name.as_str(), vec![self.func]),
+            Holder::Field(_, name) => (name, class_functions(self.class)),
+        };
+        let refuse = |span: &Span| {
+            Err(RefactorError::Unsupported(format!(
+                "`{}` is also used in `{}`, which would take the special case for a value",
+                slice(self.source, subject.span()),
+                slice(self.source, span)
+            )))
+        };
+        let mentions = |source: &str, span: &Span| {
+            raw_tokens(source, span)
+                .iter()
+                .any(|token| token.text == name)
+        };
+        for func in funcs {
+            let bindings = resolve_function(func);
+            let is_holder =
+                |expr: &Expression| holder_of(self.class, &bindings, expr) == Some(holder);
+            for expr in function_expressions(func) {
+                let null_aware = match expr {
+                    Expression::BinaryOp(op) => {
+                        op.operator == BinaryOperator::Coalesce && is_holder(&op.left)
+                    }
+                    Expression::Invocation(inv) => {
+                        invoked_name(inv) == Some("ReferenceEquals")
+                            && inv.arguments.iter().any(is_holder)
+                    }
+                    Expression::Raw { source, span } => mentions(source, span),
+                    _ => false,
+                };
+                if null_aware {
+                    return refuse(expr.span());
+                }
+            }
+            for stmt in function_blocks(func).flat_map(block_statements) {
+                if let Statement::Unknown { source, span } = stmt {
+                    if mentions(source, span) {
+                        return refuse(span);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// A conditional choosing between a default and a member of the subject.
+    fn default(&self, cond: &'a Conditional) -> Result<Option<NullCheck<'a>>, RefactorError> {
+        let Expression::BinaryOp(op) = cond.condition.as_ref() else {
+            return Ok(None);
+        };
+        let Some((subject, is_null_test, holder)) = self.null_test(op)? else {
+            return Ok(None);
+        };
+        let (default, other) = if is_null_test {
+            (&cond.consequence, &cond.alternative)
+        } else {
+            (&cond.alternative, &cond.consequence)
+        };
+        let (member, invoked) = match other.as_ref() {
+            Expression::MemberAccess(ma) if same_expression(&ma.expression, subject) => {
+                (&ma.member, false)
+            }
+            Expression::Invocation(inv) if inv.arguments.is_empty() => {
+                match inv.function.as_ref() {
+                    Expression::MemberAccess(ma) if same_expression(&ma.expression, subject) => {
+                        (&ma.member, true)
+                    }
+                    _ => return Ok(None),
+                }
+            }
+            _ => return Ok(None),
+        };
+
+        // The default moves into the special case, away from the locals and
+        // members of the class it is written in.
+        let span = default.span();
+        let text = slice(self.source, span);
+        let names = member_names(self.class);
+        for token in raw_tokens(text, span) {
+            let local = self.bindings.declaration_at(token.span.start).is_some();
+            if !token.after_dot
+                && (local || token.text == "this" || names.contains(&token.text.as_str()))
+            {
+                return Err(RefactorError::Unsupported(format!(
+                    "`{}` depends on `{}`, which the special case cannot see",
+                    text, token.text
+                )));
+            }
+        }
+
+        Ok(Some(NullCheck::Default {
+            span: cond.span.clone(),
+            member: member.clone(),
+            invoked,
+            default,
+            access: slice(self.source, other.span()).to_string(),
+            holder,
+        }))
+    }
+}
+
+/// Make `member` of `target` virtual, returning the edit that does so and the
+/// header of the override.
+fn overridable(
+    target: &ClassDef,
+    member: &str,
+    invoked: bool,
+    source: &str,
+) -> Result<(Option<TextEdit>, String), RefactorError> {
+    let declaration = target.body.iter().flatten().find_map(|item| match item {
+        TopLevel::Property(property) if !invoked && property.name == member => Some((
+            &property.span,
+            &property.name_span,
+            &property.modifiers,
+            property.var_type.as_deref(),
+            "",
+        )),
+        TopLevel::Function(func)
+            if invoked
+                && func.name == member
+                && func.parameters.as_ref().is_none_or(Vec::is_empty) =>
+        {
+            Some((
+                &func.span,
+                &func.name_span,
+                &func.modifiers,
+                func.return_type.as_deref(),
+                "()",
+            ))
+        }
+        _ => None,
+    });
+    let Some((span, name_span, modifiers, Some(var_type), parens)) = declaration else {
+        return Err(RefactorError::Unsupported(format!(
+            "`{}` has no {} `{}` to override",
+            target.name,
+            if invoked {
+                "parameterless method"
+            } else {
+                "property"
+            },
+            member
+        )));
+    };
+
+    let modifiers: Vec<&str> = modifiers.iter().flatten().map(String::as_str).collect();
+    if modifiers.contains(&"static") {
+        return Err(RefactorError::Unsupported(format!(
+            "`{}` is static and cannot be overridden",
+            member
+        )));
+    }
+    let access: Vec<&str> = modifiers
+        .iter()
+        .copied()
+        .filter(|modifier| matches!(*modifier, "public" | "protected" | "internal"))
+        .collect();
+    if access.is_empty() {
+        return Err(RefactorError::Unsupported(format!(
+            "`{}` is private and cannot be overridden",
+            member
+        )));
+    }
+    let overriding = format!(
+        "{} override {} {}{}",
+        access.join(" "),
+        var_type,
+        member,
+        parens
+    );
+    if modifiers
+        .iter()
+        .any(|modifier| matches!(*modifier, "virtual" | "abstract" | "override"))
+    {
+        return Ok((None, overriding));
+    }
+    let header = &source[span.start..name_span.start];
+    let offset = header
+        .rfind(var_type)
+        .map_or(name_span.start, |i| span.start + i);
+    Ok((
+        Some(TextEdit {
+            start: offset,
+            end: offset,
+            replacement: "virtual ".to_string(),
+        }),
+        overriding,
+    ))
+}
@@ -0,0 +1,151 @@
+use crate::analysis::locate::{enclosing_function, function_blocks, innermost_if};
+use crate::analysis::variables::function_declarations;
+use crate::syntax::negate;
+use crate::text::{line_indent, reindent, slice, MethodStyle};
+use crate::{RefactorError, Refactoring, TextEdit};
+use uast::*;
+
+/// Negate the condition of an `if` statement and swap its branches.
+///
+/// An `if` without `else` that ends a method returning nothing becomes an
+/// early `return` followed by what used to be its body.
+pub struct InvertIf {
+    pub offset: usize,
+}
+
+impl InvertIf {
+    pub fn new(offset: usize) -> Self {
+        InvertIf { offset }
+    }
+}
+
+impl Refactoring for InvertIf {
+    fn apply(&self, uast: &TopLevel, source: &str) -> Result<Vec<TextEdit>, RefactorError> {
+        let at = Span {
+            start: self.offset,
+            end: self.offset,
+        };
+        let context = enclosing_function(uast, &at).ok_or_else(|| {
+            RefactorError::InvalidSelection("the offset is not inside a method".to_string())
+        })?;
+        let func = context.function;
+        let if_stmt = function_blocks(func)
+            .find_map(|block| innermost_if(block, &at))
+            .ok_or_else(|| {
+                RefactorError::InvalidSelection("the offset is not inside an `if`".to_string())
+            })?;
+
+        let condition = if_stmt.condition.span();
+        let mut edits = vec![TextEdit {
+            start: condition.start,
+            end: condition.end,
+            replacement: negate(&if_stmt.condition, source),
+        }];
+        match &if_stmt.alternative {
+            Some(alternative) => {
+                let style = MethodStyle::of(source, func);
+                let unit = &style.body_indent[style.member_indent.len()..];
+                let indent = line_indent(source, if_stmt.span.start);
+                edits.push(TextEdit {
+                    start: if_stmt.consequence.span.start,
+                    end: if_stmt.consequence.span.end,
+                    replacement: consequence_text(alternative, source, indent, unit),
+                });
+                edits.push(TextEdit {
+                    start: alternative.span.start,
+                    end: alternative.span.end,
+                    replacement: slice(source, &if_stmt.consequence.span).to_string(),
+                });
+            }
+            None => edits.push(early_return(func, if_stmt, source)?),
+        }
+        Ok(edits)
+    }
+}
+
+/// The text of `block` as the body of an `if` with an `else`. An unbraced
+/// `if`, as in `else if`, gets braces so the `else` still pairs with the
+/// outer `if`.
+fn consequence_text(block: &Block, source: &str, indent: &str, unit: &str) -> String {
+    let text = slice(source, &block.span);
+    match block.statements.as_slice() {
+        [Statement::IfStatement(_)] if !text.starts_with('{') => format!(
+            "{{\n{}\n{}}}",
+            reindent(
+                text,
+                line_indent(source, block.span.start),
+                &format!("{}{}", indent, unit)
+            ),
+            indent
+        ),
+        _ => text.to_string(),
+    }
+}
+
+/// Replace the body of an `if` that ends a method with an early `return`
+/// followed by the body's statements.
+fn early_return(
+    func: &FunctionDef,
+    if_stmt: &IfStatement,
+    source: &str,
+) -> Result<TextEdit, RefactorError> {
+    let is_last = function_blocks(func)
+        .next()
+        .and_then(|body| body.statements.last())
+        .is_some_and(|last| last.span() == &if_stmt.span);
+    if !is_last {
+        return Err(RefactorError::Unsupported(
+            "an `if` without `else` can only be inverted at the end of a method".to_string(),
+        ));
+    }
+    if func
+        .return_type
+        .as_deref()
+        .is_some_and(|return_type| return_type != "void")
+    {
+        return Err(RefactorError::Unsupported(format!(
+            "`{}` returns a value, so it cannot return early",
+            func.name
+        )));
+    }
+
+    // The body's locals move out to the method's scope, where C# does not let
+    // them share a name with any other local.
+    let declarations = function_declarations(func);
+    for stmt in &if_stmt.consequence.statements {
+        if let Statement::DeclStmt(decl_stmt) = stmt {
+            for var in &decl_stmt.var_decls {
+                if declarations
+                    .iter()
+                    .filter(|decl| decl.name == var.name)
+                    .count()
+                    > 1
+                {
+                    return Err(RefactorError::Conflict(format!(
+                        "`{}` would be declared twice in `{}`",
+                        var.name, func.name
+                    )));
+                }
+            }
+        }
+    }
+
+    let indent = line_indent(source, if_stmt.span.start);
+    let header = &source[if_stmt.condition.span().end..if_stmt.consequence.span.start];
+    let header = header.rfind(')').map_or(header, |i| &header[..=i]);
+    let mut text = format!("{} return;", header);
+    let statements = &if_stmt.consequence.statements;
+    if let (Some(first), Some(last)) = (statements.first(), statements.last()) {
+        text.push('\n');
+        text.push_str(&reindent(
+            &source[first.span().start..last.span().end],
+            line_indent(source, first.span().start),
+            indent,
+        ));
+    }
+    Ok(TextEdit {
+        start: if_stmt.condition.span().end,
+        end: if_stmt.span.end,
+        replacement: text,
+    })
+}
@@ -0,0 +1,360 @@
+use crate::analysis::calls::{is_instance_of, unlowered_mentions};
+use crate::analysis::members::{
+    classes, constructors, find_class, member_accesses, member_names, members,
+    unlowered_class_mentions, Member,
+};
+use crate::analysis::scope::resolve;
+use crate::analysis::variables::expression_usages;
+use crate::text::{
+    doc_comment_start, line_indent, reindent, removal_span, slice, splice, widen_access, MemberSlot,
+};
+use crate::{FileEdits, RefactorError, SourceFile, TextEdit, WorkspaceRefactoring};
+use uast::*;
+
+/// Move a field to a class reached through the fields and properties of its
+/// own class, such as `customer.Contract`.
+///
+/// The path is the shortest chain of fields and properties whose types lead
+/// to the target class, and must be the only one of its length. Every access
+/// of the field is rewritten to go through the path, so `discount` in a
+/// method of the class becomes `customer.Contract.discount`, and
+/// `order.discount` becomes `order.customer.Contract.discount`. Private
+/// members on the way, and the moved field itself, become internal.
+///
+/// The move is refused when a constructor, or any other code that is not
+/// analyzed, mentions the field.
+pub struct MoveField {
+    pub class: String,
+    pub field: String,
+    pub target: String,
+}
+
+impl MoveField {
+    pub fn new(class: &str, field: &str, target: &str) -> Self {
+        MoveField {
+            class: class.to_string(),
+            field: field.to_string(),
+            target: target.to_string(),
+        }
+    }
+}
+
+/// One field or property on the path to the target, with the class that
+/// declares it and the index of that class's file.
+#[derive(Clone, Copy)]
+struct Hop<'a> {
+    file: usize,
+    owner: &'a ClassDef,
+    member: Member<'a>,
+}
+
+impl WorkspaceRefactoring for MoveField {
+    fn apply_workspace(&self, files: &[SourceFile]) -> Result<Vec<FileEdits>, RefactorError> {
+        let (home, class) = find_class(files, &self.class)?;
+        let (there, target) = find_class(files, &self.target)?;
+        if std::ptr::eq(class, target) {
+            return Err(RefactorError::InvalidSelection(format!(
+                "`{}` is already declared in `{}`",
+                self.field, self.target
+            )));
+        }
+        let (field, var) = self.check_field(class)?;
+        if member_names(target).contains(&self.field.as_str()) {
+            return Err(RefactorError::Conflict(format!(
+                "`{}` already has a member called `{}`",
+                self.target, self.field
+            )));
+        }
+        let path = self.path(files, home, class)?;
+        let names: Vec<&str> = path.iter().map(|hop| hop.member.name()).collect();
+        let route = names.join(".");
+
+        let source = files[home].source;
+        let mut edits: Vec<Vec<TextEdit>> = vec![Vec::new(); files.len()];
+        let region = Span {
+            start: doc_comment_start(source, field.span.start),
+            end: field.span.end,
+        };
+        let slot = MemberSlot::of(files[there].source, target);
+        let access = widen_access(class, None, &[self.field.as_str()], "internal", source);
+        let text = reindent(
+            &splice(source, &region, &access),
+            line_indent(source, field.span.start),
+            &slot.indent,
+        );
+        edits[there].push(slot.insert(&text));
+        let removal = removal_span(source, &region);
+        edits[home].push(TextEdit {
+            start: removal.start,
+            end: removal.end,
+            replacement: String::new(),
+        });
+
+        // The path may not be set up yet while `class` is being constructed.
+        let in_constructor = |span: &Span| {
+            constructors(class)
+                .iter()
+                .any(|constructor| constructor.span.contains(span))
+        };
+        let mut reached_from_outside = false;
+        for (i, file) in files.iter().enumerate() {
+            let bindings = resolve(file.uast);
+            let mut handled = Vec::new();
+            if i == home {
+                // A local named like the first step would capture the path.
+                let hidden = bindings.references.iter().any(|reference| {
+                    reference.decl.name == names[0] && class.span.contains(&reference.span)
+                });
+                let prefix = if hidden {
+                    format!("this.{}.", route)
+                } else {
+                    format!("{}.", route)
+                };
+                for usage in &bindings.unresolved {
+                    if usage.name == self.field && class.span.contains(&usage.span) {
+                        if in_constructor(&usage.span) {
+                            return Err(self.used_in_constructor(names[0]));
+                        }
+                        edits[i].push(TextEdit {
+                            start: usage.span.start,
+                            end: usage.span.start,
+                            replacement: prefix.clone(),
+                        });
+                        handled.push(usage.span.clone());
+                    }
+                }
+                handled.push(var.name_span.clone());
+            }
+
+            for access in member_accesses(file.uast, &self.field) {
+                let inside = i == home && class.span.contains(&access.span);
+                let this = matches!(
+                    access.expression.as_ref(),
+                    Expression::Raw { source, .. } if source == "this"
+                );
+                if this && !inside {
+                    handled.push(access.member_span.clone());
+                    continue;
+                }
+                let ours = is_instance_of(&access.expression, Some(class), &bindings).ok_or_else(
+                    || {
+                        RefactorError::Unsupported(format!(
+                            "cannot tell whether `{}` refers to `{}.{}`",
+                            slice(file.source, &access.span),
+                            self.class,
+                            self.field
+                        ))
+                    },
+                )?;
+                if ours {
+                    if inside && in_constructor(&access.span) {
+                        return Err(self.used_in_constructor(names[0]));
+                    }
+                    reached_from_outside |= !inside;
+                    edits[i].push(TextEdit {
+                        start: access.member_span.start,
+                        end: access.member_span.start,
+                        replacement: format!("{}.", route),
+                    });
+                }
+                handled.push(access.member_span.clone());
+            }
+
+            let unlowered = unlowered_mentions(file.uast, &self.field)
+                .iter()
+                .any(|span| !handled.contains(span))
+                || classes(file.uast).into_iter().any(|other| {
+                    !unlowered_class_mentions(other, file.source, &self.field).is_empty()
+                });
+            if unlowered {
+                return Err(RefactorError::Unsupported(format!(
+                    "`{}` is referenced from code that could not be analyzed",
+                    self.field
+                )));
+            }
+        }
+
+        // Every step after the first is reached from outside its class, and
+        // the first one too once code outside `class` goes through it.
+        let skip = if reached_from_outside { 0 } else { 1 };
+        for hop in path.iter().skip(skip) {
+            edits[hop.file].extend(widen_access(
+                hop.owner,
+                None,
+                &[hop.member.name()],
+                "internal",
+                files[hop.file].source,
+            ));
+        }
+
+        Ok(files
+            .iter()
+            .zip(edits)
+            .filter(|(_, edits)| !edits.is_empty())
+            .map(|(file, edits)| FileEdits {
+                path: file.path.to_string(),
+                edits,
+            })
+            .collect())
+    }
+}
+
+impl MoveField {
+    fn used_in_constructor(&self, first: &str) -> RefactorError {
+        RefactorError::Unsupported(format!(
+            "`{}` is used in a constructor of `{}`, where `{}` may not be set yet",
+            self.field, self.class, first
+        ))
+    }
+
+    fn check_field<'a>(
+        &self,
+        class: &'a ClassDef,
+    ) -> Result<(&'a FieldDef, &'a VarDecl), RefactorError> {
+        let (field, var) = match members(class)
+            .into_iter()
+            .find(|member| member.name() == self.field)
+        {
+            Some(Member::Field(field, var)) => (field, var),
+            Some(Member::Property(_)) => {
+                return Err(RefactorError::Unsupported(format!(
+                    "`{}` is a property",
+                    self.field
+                )))
+            }
+            None => {
+                return Err(RefactorError::NotFound(format!(
+                    "field `{}` in `{}`",
+                    self.field, self.class
+                )))
+            }
+        };
+        if let Some(modifier) = field
+            .modifiers
+            .iter()
+            .flatten()
+            .find(|modifier| matches!(modifier.as_str(), "static" | "const"))
+        {
+            return Err(RefactorError::Unsupported(format!(
+                "`{}` is {} and not reached through an instance",
+                self.field, modifier
+            )));
+        }
+        if field.var_decls.len() > 1 {
+            return Err(RefactorError::Unsupported(format!(
+                "`{}` is declared together with other fields",
+                self.field
+            )));
+        }
+        if let Some(value) = &var.value {
+            let own = member_names(class);
+            if let Some(usage) = expression_usages(value)
+                .into_iter()
+                .find(|usage| own.contains(&usage.name.as_str()))
+            {
+                return Err(RefactorError::Unsupported(format!(
+                    "the initializer of `{}` uses `{}`, which stays in `{}`",
+                    self.field, usage.name, self.class
+                )));
+            }
+        }
+        Ok((field, var))
+    }
+
+    /// The shortest chain of fields and properties leading from `class` to
+    /// the target. The moved field itself is not a way there.
+    fn path<'a>(
+        &self,
+        files: &[SourceFile<'a>],
+        home: usize,
+        class: &'a ClassDef,
+    ) -> Result<Vec<Hop<'a>>, RefactorError> {
+        let all: Vec<(usize, &ClassDef)> = files
+            .iter()
+            .enumerate()
+            .flat_map(|(i, file)| classes(file.uast).into_iter().map(move |c| (i, c)))
+            .collect();
+        let class_of = |var_type: Option<&str>| {
+            let name = var_type?.trim_end_matches('?');
+            match all
+                .iter()
+                .filter(|(_, c)| c.name == name)
+                .collect::<Vec<_>>()
+                .as_slice()
+            {
+                [found] => Some(**found),
+                _ => None,
+            }
+        };
+
+        let mut visited: Vec<&ClassDef> = vec![class];
+        let mut frontier: Vec<Vec<Hop>> = vec![Vec::new()];
+        while !frontier.is_empty() {
+            let mut next: Vec<Vec<Hop>> = Vec::new();
+            for path in frontier {
+                let (file, owner) = path
+                    .last()
+                    .and_then(|hop| class_of(hop.member.var_type()))
+                    .unwrap_or((home, class));
+                for member in members(owner) {
+                    if path.is_empty() && member.name() == self.field {
+                        continue;
+                    }
+                    let Some((_, reached)) = class_of(member.var_type()) else {
+                        continue;
+                    };
+                    if visited.iter().any(|seen| std::ptr::eq(*seen, reached)) {
+                        continue;
+                    }
+                    let mut longer = path.clone();
+                    longer.push(Hop {
+                        file,
+                        owner,
+                        member,
+                    });
+                    next.push(longer);
+                }
+            }
+
+            let arrived: Vec<usize> = (0..next.len())
+                .filter(|&i| {
+                    next[i]
+                        .last()
+                        .and_then(|hop| class_of(hop.member.var_type()))
+                        .is_some_and(|(_, reached)| reached.name == self.target)
+                })
+                .collect();
+            match arrived.as_slice() {
+                [] => {}
+                [i] => return Ok(next.swap_remove(*i)),
+                [first, second, ..] => {
+                    let describe = |path: &Vec<Hop>| {
+                        path.iter()
+                            .map(|hop| hop.member.name())
+                            .collect::<Vec<_>>()
+                            .join(".")
+                    };
+                    return Err(RefactorError::Unsupported(format!(
+                        "`{}` is reached both through `{}` and `{}`",
+                        self.target,
+                        describe(&next[*first]),
+                        describe(&next[*second])
+                    )));
+                }
+            }
+            for path in &next {
+                if let Some((_, reached)) =
+                    path.last().and_then(|hop| class_of(hop.member.var_type()))
+                {
+                    visited.push(reached);
+                }
+            }
+            frontier = next;
+        }
+
+        Err(RefactorError::Unsupported(format!(
+            "`{}` cannot be reached through the fields of `{}`",
+            self.target, self.class
+        )))
+    }
+}
@@ -0,0 +1,668 @@
+use crate::analysis::calls::{calls_of, find_calls, unlowered_mentions, CallSite, Receiver};
+use crate::analysis::locate::function_blocks;
+use crate::analysis::members::{
+    classes, find_class, member_names, members, unlowered_class_mentions,
+};
+use crate::analysis::scope::{resolve, resolve_function};
+use crate::analysis::variables::{function_declarations, function_usages};
+use crate::syntax::{has_side_effects, precedence, wrap, PRIMARY};
+use crate::text::{
+    argument_span, doc_comment_start, line_indent, lower_first, member_after, reindent,
+    removal_span, replace_list, slice, splice, widen_access, MemberSlot, MethodStyle,
+};
+use crate::{FileEdits, RefactorError, SourceFile, TextEdit, WorkspaceRefactoring};
+use uast::*;
+
+/// Move a method from one class to another, which may be declared in another
+/// file.
+///
+/// The method must reach an instance of the target class through exactly one
+/// of its parameters, which the moved method drops in favour of `this`, or
+/// else through exactly one field or property of its class. When the body
+/// uses other members of its old class, the moved method takes the old
+/// instance as an extra parameter named after the class, and any of those
+/// members that are private become internal.
+///
+/// With `delegate`, the old method stays behind and forwards to the moved
+/// one. Otherwise it is deleted and every call is rewritten to call the
+/// target instead.
+pub struct MoveMethod {
+    pub class: String,
+    pub method: String,
+    pub target: String,
+    pub delegate: bool,
+}
+
+impl MoveMethod {
+    pub fn new(class: &str, method: &str, target: &str, delegate: bool) -> Self {
+        MoveMethod {
+            class: class.to_string(),
+            method: method.to_string(),
+            target: target.to_string(),
+            delegate,
+        }
+    }
+}
+
+/// How the method reaches the instance of the target class it works on.
+enum Via<'a> {
+    /// The parameter at this position.
+    Parameter(usize, &'a VarDecl),
+    /// A field or property of the method's class.
+    Member(&'a str),
+}
+
+/// The method as it reads once moved.
+struct Moved {
+    text: String,
+    /// The name of the added parameter for the old instance, if the body
+    /// needs one.
+    source: Option<String>,
+    /// Members of the old class the moved method uses.
+    used: Vec<String>,
+}
+
+impl WorkspaceRefactoring for MoveMethod {
+    fn apply_workspace(&self, files: &[SourceFile]) -> Result<Vec<FileEdits>, RefactorError> {
+        let (home, class) = find_class(files, &self.class)?;
+        let (there, target) = find_class(files, &self.target)?;
+        if std::ptr::eq(class, target) {
+            return Err(RefactorError::InvalidSelection(format!(
+                "`{}` is already declared in `{}`",
+                self.method, self.target
+            )));
+        }
+        let func = self.check_method(class)?;
+        let source = files[home].source;
+
+        if find_calls(files[home].uast, &self.method)
+            .iter()
+            .any(|site| std::ptr::eq(site.caller, func))
+        {
+            return Err(RefactorError::Unsupported(format!(
+                "`{}` is recursive",
+                self.method
+            )));
+        }
+        if member_names(target).contains(&self.method.as_str()) {
+            return Err(RefactorError::Conflict(format!(
+                "`{}` already has a member called `{}`",
+                self.target, self.method
+            )));
+        }
+
+        let via = self.via(class, func)?;
+        let slot = MemberSlot::of(files[there].source, target);
+        let moved = self.moved(&files[home], class, func, target, &via, &slot.indent)?;
+
+        let mut edits: Vec<Vec<TextEdit>> = vec![Vec::new(); files.len()];
+        edits[there].push(slot.insert(&moved.text));
+
+        let mut widened: Vec<&str> = moved.used.iter().map(String::as_str).collect();
+        if self.delegate {
+            edits[home].push(self.delegation(func, &via, moved.source.is_some(), source)?);
+        } else {
+            let removal = removal_span(
+                source,
+                &Span {
+                    start: doc_comment_start(source, func.span.start),
+                    end: func.span.end,
+                },
+            );
+            edits[home].push(TextEdit {
+                start: removal.start,
+                end: removal.end,
+                replacement: String::new(),
+            });
+            let arity = func.parameters.iter().flatten().count();
+            let mut reached_from_outside = false;
+            for (i, file) in files.iter().enumerate() {
+                let calls = self.calls(file, i == home, class, func)?;
+                for site in &calls {
+                    let inside = site.class.is_some_and(|other| std::ptr::eq(other, class));
+                    reached_from_outside |= !inside;
+                    edits[i].extend(self.rewrite_call(
+                        site,
+                        &via,
+                        arity,
+                        moved.source.is_some(),
+                        file.source,
+                    )?);
+                }
+            }
+            if let Via::Member(name) = via {
+                if reached_from_outside {
+                    widened.push(name);
+                }
+            }
+        }
+        edits[home].extend(widen_access(
+            class,
+            Some(func),
+            &widened,
+            "internal",
+            source,
+        ));
+
+        Ok(files
+            .iter()
+            .zip(edits)
+            .filter(|(_, edits)| !edits.is_empty())
+            .map(|(file, edits)| FileEdits {
+                path: file.path.to_string(),
+                edits,
+            })
+            .collect())
+    }
+}
+
+impl MoveMethod {
+    fn check_method<'a>(&self, class: &'a ClassDef) -> Result<&'a FunctionDef, RefactorError> {
+        let candidates: Vec<&FunctionDef> = class
+            .body
+            .iter()
+            .flatten()
+            .filter_map(|item| match item {
+                TopLevel::Function(func) if func.name == self.method => Some(func),
+                _ => None,
+            })
+            .collect();
+        let func = match candidates.as_slice() {
+            [] => {
+                return Err(RefactorError::NotFound(format!(
+                    "method `{}` in `{}`",
+                    self.method, self.class
+                )))
+            }
+            [func] => *func,
+            _ => {
+                return Err(RefactorError::Unsupported(format!(
+                    "`{}` is overloaded",
+                    self.method
+                )))
+            }
+        };
+
+        if let Some(modifier) = func.modifiers.iter().flatten().find(|modifier| {
+            matches!(
+                modifier.as_str(),
+                "static" | "virtual" | "override" | "abstract" | "protected"
+            )
+        }) {
+            return Err(RefactorError::Unsupported(format!(
+                "`{}` is {} and belongs to `{}`",
+                self.method, modifier, self.class
+            )));
+        }
+        if func.body.is_none() {
+            return Err(RefactorError::Unsupported(format!(
+                "`{}` has no body",
+                self.method
+            )));
+        }
+        Ok(func)
+    }
+
+    fn via<'a>(
+        &self,
+        class: &'a ClassDef,
+        func: &'a FunctionDef,
+    ) -> Result<Via<'a>, RefactorError> {
+        let is_target = |var_type: Option<&str>| var_type == Some(self.target.as_str());
+
+        let parameters: Vec<(usize, &VarDecl)> = func
+            .parameters
+            .iter()
+            .flatten()
+            .enumerate()
+            .filter(|(_, param)| is_target(param.var_type.as_deref()))
+            .collect();
+        match parameters.as_slice() {
+            [(i, param)] if param.modifiers.as_ref().is_none_or(Vec::is_empty) => {
+                return Ok(Via::Parameter(*i, param))
+            }
+            [(_, param)] => {
+                return Err(RefactorError::Unsupported(format!(
+                    "parameter `{}` is passed by reference",
+                    param.name
+                )))
+            }
+            [] => {}
+            _ => {
+                return Err(RefactorError::Unsupported(format!(
+                    "`{}` takes more than one `{}`",
+                    self.method, self.target
+                )))
+            }
+        }
+
+        let fields: Vec<&str> = members(class)
+            .into_iter()
+            .filter(|member| is_target(member.var_type()))
+            .map(|member| member.name())
+            .collect();
+        match fields.as_slice() {
+            [name] => Ok(Via::Member(name)),
+            [] => Err(RefactorError::Unsupported(format!(
+                "`{}` has no parameter and `{}` no field of type `{}`",
+                self.method, self.class, self.target
+            ))),
+            _ => Err(RefactorError::Unsupported(format!(
+                "`{}` has more than one field of type `{}`",
+                self.class, self.target
+            ))),
+        }
+    }
+
+    /// The text of the method rewritten to run on the target, indented by
+    /// `indent`.
+    fn moved(
+        &self,
+        file: &SourceFile,
+        class: &ClassDef,
+        func: &FunctionDef,
+        target: &ClassDef,
+        via: &Via,
+        indent: &str,
+    ) -> Result<Moved, RefactorError> {
+        let source = file.source;
+        let bindings = resolve_function(func);
+        let navigation = match via {
+            Via::Parameter(_, param) => param.name.as_str(),
+            Via::Member(name) => name,
+        };
+        let source_name = lower_first(&self.class);
+        let mut shadowed: Vec<&str> = function_declarations(func)
+            .into_iter()
+            .map(|decl| decl.name.as_str())
+            .filter(|name| *name != navigation)
+            .collect();
+        shadowed.push(&source_name);
+
+        // `target.X` becomes `X`, unless a local hides `X`, and a bare `target`
+        // becomes `this`.
+        let navigate = |span: Span| -> TextEdit {
+            match member_after(source, span.end) {
+                Some(member) if !shadowed.contains(&member) => TextEdit {
+                    start: span.start,
+                    end: span.end + 1,
+                    replacement: String::new(),
+                },
+                _ => TextEdit {
+                    start: span.start,
+                    end: span.end,
+                    replacement: "this".to_string(),
+                },
+            }
+        };
+
+        let mut edits = Vec::new();
+        let mut navigations = Vec::new();
+        if let Via::Parameter(_, param) = via {
+            for reference in bindings.references_to(param) {
+                match reference.access {
+                    None => {}
+                    Some(access) if access.writes() => {
+                        return Err(RefactorError::Unsupported(format!(
+                            "`{}` assigns parameter `{}`",
+                            self.method, param.name
+                        )))
+                    }
+                    Some(_) => navigations.push(reference.span.clone()),
+                }
+            }
+        }
+
+        let statics: Vec<&str> = class
+            .body
+            .iter()
+            .flatten()
+            .filter_map(|item| match item {
+                TopLevel::Function(other) => Some((other.name.as_str(), &other.modifiers)),
+                TopLevel::Field(field) => field
+                    .var_decls
+                    .first()
+                    .map(|var| (var.name.as_str(), &field.modifiers)),
+                TopLevel::Property(property) => Some((property.name.as_str(), &property.modifiers)),
+                _ => None,
+            })
+            .filter(|(_, modifiers)| {
+                modifiers
+                    .iter()
+                    .flatten()
+                    .any(|modifier| modifier == "static" || modifier == "const")
+            })
+            .map(|(name, _)| name)
+            .collect();
+        let own = member_names(class);
+        let theirs = member_names(target);
+        // Members of the old class, with the span to replace by their owner:
+        // empty in front of a bare name, or covering `this.`.
+        let mut qualified: Vec<(&str, Span)> = Vec::new();
+        let mut uses_this = false;
+        for usage in &bindings.unresolved {
+            let name = usage.name.as_str();
+            let at = Span {
+                start: usage.span.start,
+                end: usage.span.start,
+            };
+            if name == "this" {
+                match member_after(source, usage.span.end) {
+                    Some(member) if matches!(via, Via::Member(_)) && member == navigation => {
+                        navigations.push(Span {
+                            start: usage.span.start,
+                            end: usage.span.end + 1 + member.len(),
+                        })
+                    }
+                    Some(member) => qualified.push((
+                        member,
+                        Span {
+                            start: usage.span.start,
+                            end: usage.span.end + 1,
+                        },
+                    )),
+                    None => {
+                        uses_this = true;
+                        edits.push(TextEdit {
+                            start: usage.span.start,
+                            end: usage.span.end,
+                            replacement: source_name.clone(),
+                        });
+                    }
+                }
+            } else if name == "base" {
+                return Err(RefactorError::Unsupported(format!(
+                    "`{}` uses the base class of `{}`",
+                    self.method, self.class
+                )));
+            } else if matches!(via, Via::Member(_)) && name == navigation {
+                if usage.access.writes() {
+                    return Err(RefactorError::Unsupported(format!(
+                        "`{}` assigns `{}`",
+                        self.method, navigation
+                    )));
+                }
+                navigations.push(usage.span.clone());
+            } else if own.contains(&name) {
+                qualified.push((name, at));
+            } else if theirs.contains(&name) {
+                return Err(RefactorError::Conflict(format!(
+                    "`{}` would refer to the member of `{}` with the same name",
+                    name, self.target
+                )));
+            }
+        }
+        for method in class.body.iter().flatten().filter_map(|item| match item {
+            TopLevel::Function(other) if other.name != self.method => Some(other),
+            _ => None,
+        }) {
+            for site in find_calls(file.uast, &method.name) {
+                if std::ptr::eq(site.caller, func) && site.receiver == Receiver::Implicit {
+                    let start = site.invocation.span.start;
+                    qualified.push((&method.name, Span { start, end: start }));
+                }
+            }
+        }
+
+        let mut used: Vec<String> = Vec::new();
+        for (name, span) in qualified {
+            let owner = if statics.contains(&name) {
+                &self.class
+            } else {
+                uses_this = true;
+                &source_name
+            };
+            if !used.iter().any(|other| other == name) {
+                used.push(name.to_string());
+            }
+            edits.push(TextEdit {
+                start: span.start,
+                end: span.end,
+                replacement: format!("{}.", owner),
+            });
+        }
+        edits.extend(navigations.into_iter().map(navigate));
+
+        let source_name = uses_this.then_some(source_name);
+        if let Some(name) = &source_name {
+            let taken = function_declarations(func)
+                .into_iter()
+                .map(|decl| decl.name.clone())
+                .chain(function_usages(func).into_iter().map(|usage| usage.name))
+                .any(|existing| existing == *name)
+                || theirs.contains(&name.as_str());
+            if taken {
+                return Err(RefactorError::Conflict(format!(
+                    "`{}` is already used in `{}`",
+                    name, self.method
+                )));
+            }
+        }
+
+        // The signature loses the target parameter and gains the old instance.
+        let parameters: Vec<&VarDecl> = func.parameters.iter().flatten().collect();
+        let mut declaration: Vec<String> = parameters
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !matches!(via, Via::Parameter(j, _) if j == i))
+            .map(|(_, param)| slice(source, &param.span).to_string())
+            .collect();
+        if let Some(name) = &source_name {
+            declaration.push(format!("{} {}", self.class, name));
+        }
+        if matches!(via, Via::Parameter(..)) || source_name.is_some() {
+            edits.push(replace_list(
+                source,
+                parameters.iter().map(|param| param.span.clone()).collect(),
+                func.name_span.end,
+                declaration.join(", "),
+            ));
+        }
+        edits.extend(widen_access(
+            class,
+            None,
+            &[self.method.as_str()],
+            "internal",
+            source,
+        ));
+
+        let region = Span {
+            start: doc_comment_start(source, func.span.start),
+            end: func.span.end,
+        };
+        let text = splice(source, &region, &edits);
+        Ok(Moved {
+            text: reindent(&text, line_indent(source, func.span.start), indent),
+            source: source_name,
+            used,
+        })
+    }
+
+    /// The edit turning the body of `func` into a call of the moved method.
+    fn delegation(
+        &self,
+        func: &FunctionDef,
+        via: &Via,
+        pass_this: bool,
+        source: &str,
+    ) -> Result<TextEdit, RefactorError> {
+        let parameters: Vec<&VarDecl> = func.parameters.iter().flatten().collect();
+        let receiver = match via {
+            Via::Parameter(_, param) => param.name.clone(),
+            Via::Member(name) if parameters.iter().any(|param| param.name == *name) => {
+                format!("this.{}", name)
+            }
+            Via::Member(name) => name.to_string(),
+        };
+        let mut arguments: Vec<&str> = parameters
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !matches!(via, Via::Parameter(j, _) if j == i))
+            .map(|(_, param)| param.name.as_str())
+            .collect();
+        if pass_this {
+            arguments.push("this");
+        }
+        let call = format!("{}.{}({})", receiver, self.method, arguments.join(", "));
+
+        if let Some(block) = function_blocks(func).next() {
+            let style = MethodStyle::of(source, func);
+            let returns = func.return_type.as_deref().is_some_and(|ty| ty != "void");
+            let statement = if returns {
+                format!("return {};", call)
+            } else {
+                format!("{};", call)
+            };
+            return Ok(TextEdit {
+                start: block.span.start,
+                end: block.span.end,
+                replacement: format!(
+                    "{{\n{}{}\n{}}}",
+                    style.body_indent, statement, style.member_indent
+                ),
+            });
+        }
+        match func.body.iter().flatten().next() {
+            Some(FunctionBodyItems::Expression(expr)) => Ok(TextEdit {
+                start: expr.span().start,
+                end: expr.span().end,
+                replacement: call,
+            }),
+            _ => Err(RefactorError::Unsupported(format!(
+                "`{}` has no body",
+                self.method
+            ))),
+        }
+    }
+
+    /// The calls of `func` in `file`, which is the file declaring it when
+    /// `home` is set.
+    fn calls<'a>(
+        &self,
+        file: &SourceFile<'a>,
+        home: bool,
+        class: &ClassDef,
+        func: &FunctionDef,
+    ) -> Result<Vec<CallSite<'a>>, RefactorError> {
+        let unlowered = unlowered_mentions(file.uast, &self.method)
+            .iter()
+            .any(|span| !(home && func.span.contains(span)))
+            || classes(file.uast).into_iter().any(|other| {
+                !unlowered_class_mentions(other, file.source, &self.method).is_empty()
+            });
+        if unlowered {
+            return Err(RefactorError::Unsupported(format!(
+                "`{}` is referenced from code that could not be analyzed",
+                self.method
+            )));
+        }
+        let bindings = resolve(file.uast);
+        if bindings
+            .unresolved
+            .iter()
+            .any(|usage| usage.name == self.method)
+        {
+            return Err(RefactorError::Unsupported(format!(
+                "`{}` is used as a delegate",
+                self.method
+            )));
+        }
+        calls_of(file.uast, Some(class), &self.method, &bindings, file.source)
+    }
+
+    fn rewrite_call(
+        &self,
+        site: &CallSite,
+        via: &Via,
+        arity: usize,
+        pass_this: bool,
+        source: &str,
+    ) -> Result<Vec<TextEdit>, RefactorError> {
+        let invocation = site.invocation;
+        let arguments: Vec<Span> = invocation
+            .arguments
+            .iter()
+            .map(|arg| argument_span(source, arg.span()))
+            .collect();
+        if invocation.arguments.len() != arity {
+            return Err(RefactorError::Unsupported(format!(
+                "a call of `{}` does not pass every parameter",
+                self.method
+            )));
+        }
+        let named = invocation
+            .arguments
+            .iter()
+            .zip(&arguments)
+            .any(|(arg, span)| source[span.start..arg.span().start].contains(':'));
+        if named {
+            return Err(RefactorError::Unsupported(format!(
+                "a call of `{}` uses named arguments",
+                self.method
+            )));
+        }
+
+        let instance = match site.receiver {
+            Receiver::Other(receiver) => {
+                if has_side_effects(receiver) {
+                    return Err(RefactorError::Unsupported(format!(
+                        "a call of `{}` is made on an expression with side effects",
+                        self.method
+                    )));
+                }
+                Some(wrap(
+                    slice(source, receiver.span()),
+                    precedence(receiver),
+                    PRIMARY,
+                ))
+            }
+            _ => None,
+        };
+        let this = instance.clone().unwrap_or_else(|| "this".to_string());
+
+        let (callee, mut values) = match via {
+            Via::Parameter(i, _) => {
+                let target = &invocation.arguments[*i];
+                if has_side_effects(target) {
+                    return Err(RefactorError::Unsupported(format!(
+                        "a call of `{}` passes an argument with side effects for the `{}`",
+                        self.method, self.target
+                    )));
+                }
+                let callee = wrap(slice(source, target.span()), precedence(target), PRIMARY);
+                let values: Vec<&str> = arguments
+                    .iter()
+                    .enumerate()
+                    .filter(|(j, _)| j != i)
+                    .map(|(_, span)| slice(source, span))
+                    .collect();
+                (callee, values)
+            }
+            Via::Member(name) => {
+                let hidden = function_declarations(site.caller)
+                    .iter()
+                    .any(|decl| decl.name == *name);
+                let callee = match &instance {
+                    Some(instance) => format!("{}.{}", instance, name),
+                    None if hidden => format!("this.{}", name),
+                    None => name.to_string(),
+                };
+                let values = arguments.iter().map(|span| slice(source, span)).collect();
+                (callee, values)
+            }
+        };
+        if pass_this {
+            values.push(&this);
+        }
+
+        let function = invocation.function.span();
+        Ok(vec![
+            TextEdit {
+                start: function.start,
+                end: function.end,
+                replacement: format!("{}.{}", callee, self.method),
+            },
+            replace_list(source, arguments, function.end, values.join(", ")),
+        ])
+    }
+}
@@ -0,0 +1,115 @@
+use crate::analysis::calls::{is_instance_of, unlowered_mentions};
+use crate::analysis::members::{find_member, member_accesses, member_names, members, Member};
+use crate::analysis::scope::resolve;
+use crate::text::slice;
+use crate::{RefactorError, Refactoring, TextEdit};
+use uast::*;
+
+/// Rename a field or property and every reference to it.
+///
+/// References are `this.name`, `x.name` on a variable of the declaring class,
+/// and bare uses of the name inside the class that are not hidden by a local
+/// or parameter of the same name.
+pub struct RenameField {
+    pub name: String,
+    pub new_name: String,
+}
+
+impl RenameField {
+    pub fn new(name: &str, new_name: &str) -> Self {
+        RenameField {
+            name: name.to_string(),
+            new_name: new_name.to_string(),
+        }
+    }
+}
+
+impl Refactoring for RenameField {
+    fn apply(&self, uast: &TopLevel, source: &str) -> Result<Vec<TextEdit>, RefactorError> {
+        let (class, member) = find_member(uast, &self.name)
+            .ok_or_else(|| RefactorError::NotFound(format!("field or property `{}`", self.name)))?;
+        if self.new_name == self.name {
+            return Ok(Vec::new());
+        }
+        if class.name == self.new_name || member_names(class).contains(&self.new_name.as_str()) {
+            return Err(RefactorError::Conflict(format!(
+                "`{}` already has a member named `{}`",
+                class.name, self.new_name
+            )));
+        }
+
+        let rename = |span: &Span| TextEdit {
+            start: span.start,
+            end: span.end,
+            replacement: self.new_name.clone(),
+        };
+        let mut edits = vec![rename(member.name_span())];
+        let mut handled = Vec::new();
+
+        let bindings = resolve(uast);
+        let bodies = bodies(class);
+        for usage in &bindings.unresolved {
+            if usage.name != self.name || !class.span.contains(&usage.span) {
+                continue;
+            }
+            // A local of the new name would capture the renamed reference.
+            let body = bodies.iter().find(|body| body.contains(&usage.span));
+            let captured = bindings.references.iter().any(|reference| {
+                reference.decl.name == self.new_name
+                    && body.is_some_and(|body| body.contains(&reference.decl.name_span))
+            });
+            if captured {
+                return Err(RefactorError::Conflict(format!(
+                    "a local named `{}` would hide the renamed `{}`",
+                    self.new_name, self.name
+                )));
+            }
+            edits.push(rename(&usage.span));
+            handled.push(usage.span.clone());
+        }
+
+        for access in member_accesses(uast, &self.name) {
+            let ours =
+                is_instance_of(&access.expression, Some(class), &bindings).ok_or_else(|| {
+                    RefactorError::Unsupported(format!(
+                        "cannot tell whether `{}` refers to `{}.{}`",
+                        slice(source, &access.span),
+                        class.name,
+                        self.name
+                    ))
+                })?;
+            if ours {
+                edits.push(rename(&access.member_span));
+            }
+            handled.push(access.member_span.clone());
+        }
+
+        if unlowered_mentions(uast, &self.name)
+            .iter()
+            .any(|span| !handled.contains(span))
+        {
+            return Err(RefactorError::Unsupported(format!(
+                "`{}` is referenced from code that could not be analyzed",
+                self.name
+            )));
+        }
+
+        Ok(edits)
+    }
+}
+
+/// The spans of the methods and property accessors of `class`.
+fn bodies(class: &ClassDef) -> Vec<&Span> {
+    let methods = class.body.iter().flatten().filter_map(|item| match item {
+        TopLevel::Function(func) => Some(&func.span),
+        _ => None,
+    });
+    let accessors = members(class)
+        .into_iter()
+        .filter_map(|member| match member {
+            Member::Property(property) => Some(property),
+            Member::Field(..) => None,
+        })
+        .flat_map(|property| property.accessors.iter().map(|accessor| &accessor.span));
+    methods.chain(accessors).collect()
+}
@@ -0,0 +1,135 @@
+use crate::analysis::calls::{calls_of, unlowered_mentions};
+use crate::analysis::locate::{find_function, functions};
+use crate::analysis::members;
+use crate::analysis::scope::resolve;
+use crate::{RefactorError, Refactoring, TextEdit};
+use uast::*;
+
+/// Rename a method, together with its overloads, and every call of it.
+///
+/// Calls are updated whether they are bare, made on `this`, or made on a
+/// variable whose declared type is the method's class. A call on any other
+/// receiver whose type cannot be determined stops the refactoring.
+pub struct RenameMethod {
+    pub name: String,
+    pub new_name: String,
+}
+
+impl RenameMethod {
+    pub fn new(name: &str, new_name: &str) -> Self {
+        RenameMethod {
+            name: name.to_string(),
+            new_name: new_name.to_string(),
+        }
+    }
+}
+
+impl Refactoring for RenameMethod {
+    fn apply(&self, uast: &TopLevel, source: &str) -> Result<Vec<TextEdit>, RefactorError> {
+        let context = find_function(uast, None, &|func| func.name == self.name)
+            .ok_or_else(|| RefactorError::NotFound(format!("method `{}`", self.name)))?;
+        if self.new_name == self.name {
+            return Ok(Vec::new());
+        }
+
+        let members = match context.class {
+            Some(class) => class.body.iter().flatten().flat_map(functions).collect(),
+            None => functions(uast),
+        };
+        let targets: Vec<&FunctionDef> = members
+            .iter()
+            .copied()
+            .filter(|func| func.name == self.name)
+            .collect();
+
+        for target in &targets {
+            if let Some(modifier) =
+                target.modifiers.iter().flatten().find(|modifier| {
+                    matches!(modifier.as_str(), "virtual" | "override" | "abstract")
+                })
+            {
+                return Err(RefactorError::Unsupported(format!(
+                    "`{}` is {} and shares its name with other classes",
+                    self.name, modifier
+                )));
+            }
+        }
+
+        if let Some(class) = context.class {
+            if class.name == self.new_name {
+                return Err(RefactorError::Conflict(format!(
+                    "a member of `{}` cannot have the same name as the class",
+                    class.name
+                )));
+            }
+            // Only methods can share a name, as overloads.
+            if members::members(class)
+                .iter()
+                .any(|member| member.name() == self.new_name)
+            {
+                return Err(RefactorError::Conflict(format!(
+                    "`{}` already declares a member named `{}`",
+                    class.name, self.new_name
+                )));
+            }
+        }
+        for target in &targets {
+            let arity = arity(target);
+            if members
+                .iter()
+                .any(|func| func.name == self.new_name && self::arity(func) == arity)
+            {
+                return Err(RefactorError::Conflict(format!(
+                    "a method `{}` with {} parameter(s) already exists",
+                    self.new_name, arity
+                )));
+            }
+        }
+
+        let rename = |span: &Span| TextEdit {
+            start: span.start,
+            end: span.end,
+            replacement: self.new_name.clone(),
+        };
+        let mut edits: Vec<TextEdit> = targets.iter().map(|func| rename(&func.name_span)).collect();
+
+        let bindings = resolve(uast);
+        for site in calls_of(uast, context.class, &self.name, &bindings, source)? {
+            match site.invocation.function.as_ref() {
+                Expression::MemberAccess(ma) => edits.push(rename(&ma.member_span)),
+                other => edits.push(rename(other.span())),
+            }
+        }
+
+        // A bare name that is not a local can only be this class's method
+        // group, as in `Action run = Foo;`.
+        let groups: Vec<&Span> = bindings
+            .unresolved
+            .iter()
+            .filter(|usage| usage.name == self.name)
+            .filter(|usage| {
+                context
+                    .class
+                    .is_none_or(|class| class.span.contains(&usage.span))
+            })
+            .map(|usage| &usage.span)
+            .collect();
+        edits.extend(groups.iter().map(|span| rename(span)));
+
+        if unlowered_mentions(uast, &self.name)
+            .iter()
+            .any(|span| !groups.contains(&span))
+        {
+            return Err(RefactorError::Unsupported(format!(
+                "`{}` is referenced from code that could not be analyzed",
+                self.name
+            )));
+        }
+
+        Ok(edits)
+    }
+}
+
+fn arity(func: &FunctionDef) -> usize {
+    func.parameters.iter().flatten().count()
+}
@@ -1,244 +1,154 @@
-use crate::{Refactoring, TextEdit};
+use crate::analysis::locate::enclosing_function;
+use crate::analysis::scope::{resolve, Bindings};
+use crate::{RefactorError, Refactoring, TextEdit};
 use uast::*;
 
+/// Rename a local variable or parameter and every reference to it.
+///
+/// The variable is picked by a byte offset inside its declaration or any of
+/// its references. Identifiers are resolved through their scopes, so locals of
+/// the same name elsewhere, fields and type names are left alone.
 pub struct RenameVariable {
-    pub old_name: String,
+    pub offset: usize,
     pub new_name: String,
 }
 
 impl RenameVariable {
-    pub fn new(old_name: &str, new_name: &str) -> Self {
+    pub fn new(offset: usize, new_name: &str) -> Self {
         RenameVariable {
-            old_name: old_name.to_string(),
+            offset,
             new_name: new_name.to_string(),
         }
     }
 }
 
 impl Refactoring for RenameVariable {
-    fn apply(&self, uast: &TopLevel) -> Vec<TextEdit> {
-        let mut edits = Vec::new();
-        visit_top_level(uast, &self.old_name, &self.new_name, &mut edits);
-        edits
+    fn apply(&self, uast: &TopLevel, _source: &str) -> Result<Vec<TextEdit>, RefactorError> {
+        let bindings = resolve(uast);
+        let decl = bindings.declaration_at(self.offset).ok_or_else(|| {
+            RefactorError::InvalidSelection(
+                "the offset is not on a local variable or parameter".to_string(),
+            )
+        })?;
+        if decl.name == self.new_name {
+            return Ok(Vec::new());
+        }
+
+        if name_taken(uast, &bindings, decl, &self.new_name) {
+            return Err(RefactorError::Conflict(format!(
+                "`{}` is already used where `{}` is visible",
+                self.new_name, decl.name
+            )));
+        }
+
+        Ok(bindings
+            .references_to(decl)
+            .into_iter()
+            .map(|reference| TextEdit {
+                start: reference.span.start,
+                end: reference.span.end,
+                replacement: self.new_name.clone(),
+            })
+            .collect())
     }
 }
 
-/// Visit a top level node and parse the approprate variables.
+/// Whether `name` is declared or referenced in the code that can see `decl`.
 ///
-/// # Arguments
-/// * `node`  - The entry point to the top level node.
-/// * `old`   - The old variable name.
-/// * `new`   - The new variable name.
-/// * `edits` - Contains the edits made to the original source code.
-fn visit_top_level(node: &TopLevel, old: &str, new: &str, edits: &mut Vec<TextEdit>) {
-    match node {
-        TopLevel::Class(class) => {
-            if let Some(body) = &class.body {
-                for item in body {
-                    visit_top_level(item, old, new, edits);
-                }
-            }
-        }
-        TopLevel::Function(func) => {
-            if let Some(params) = &func.parameters {
-                for param in params {
-                    if param.name == old {
-                        edits.push(TextEdit {
-                            start: param.name_span.start,
-                            end: param.name_span.end,
-                            replacement: new.to_string(),
-                        });
-                    }
-                }
-            }
-            if let Some(body) = &func.body {
-                for item in body {
-                    match item {
-                        FunctionBodyItems::Block(block) => visit_block(block, old, new, edits),
-                        FunctionBodyItems::TopLevel(tl) => visit_top_level(tl, old, new, edits),
-                        FunctionBodyItems::Expression(expr) => {
-                            visit_expression(expr, old, new, edits)
-                        }
-                    }
-                }
-            }
-        }
-        TopLevel::Statement(stmt) => visit_statement(stmt, old, new, edits),
-        TopLevel::Module(mod_def) => {
-            for item in &mod_def.body {
-                visit_top_level(item, old, new, edits);
-            }
-        }
-        _ => {}
-    }
-}
-
-fn visit_statement(stmt: &Statement, old: &str, new: &str, edits: &mut Vec<TextEdit>) {
-    match stmt {
-        Statement::DeclStmt(decl_stmt) => {
-            for var in &decl_stmt.var_decls {
-                if var.name == old {
-                    edits.push(TextEdit {
-                        start: var.name_span.start,
-                        end: var.name_span.end,
-                        replacement: new.to_string(),
-                    });
-                }
-                if let Some(val) = &var.value {
-                    visit_expression(val, old, new, edits);
-                }
-            }
-        }
-        Statement::IfStatement(if_stmt) => {
-            visit_expression(&if_stmt.condition, old, new, edits);
-            visit_block(&if_stmt.consequence, old, new, edits);
-            if let Some(alt) = &if_stmt.alternative {
-                visit_block(alt, old, new, edits);
-            }
-        }
-        Statement::ReturnStatement(ret) => {
-            if let Some(val) = &ret.value {
-                visit_expression(val, old, new, edits);
-            }
-        }
-        Statement::ExpressionStatement(expr) => {
-            visit_expression(&expr.expression, old, new, edits);
-        }
-        Statement::WhileLoop(w) => {
-            visit_expression(&w.condition, old, new, edits);
-            visit_block(&w.body, old, new, edits);
-        }
-        Statement::ForLoop(f) => {
-            if let Some(init) = &f.initializer {
-                visit_statement(init, old, new, edits);
-            }
-            if let Some(cond) = &f.condition {
-                visit_expression(cond, old, new, edits);
-            }
-            if let Some(update) = &f.update {
-                visit_expression(update, old, new, edits);
-            }
-            visit_block(&f.body, old, new, edits);
-        }
-        _ => {}
-    }
-}
-
-fn visit_block(block: &Block, old: &str, new: &str, edits: &mut Vec<TextEdit>) {
-    for stmt in &block.statements {
-        visit_statement(stmt, old, new, edits);
-    }
-}
-
-fn visit_expression(expr: &Expression, old: &str, new: &str, edits: &mut Vec<TextEdit>) {
-    match expr {
-        Expression::Identifier(id, span) => {
-            if id == old {
-                edits.push(TextEdit {
-                    start: span.start,
-                    end: span.end,
-                    replacement: new.to_string(),
-                });
-            }
-        }
-        Expression::BinaryOp(op) => {
-            visit_expression(&op.left, old, new, edits);
-            visit_expression(&op.right, old, new, edits);
-        }
-        Expression::UnaryOp(op) => {
-            visit_expression(&op.operand, old, new, edits);
-        }
-        Expression::Assignment(assign) => {
-            visit_expression(&assign.left, old, new, edits);
-            visit_expression(&assign.right, old, new, edits);
-        }
-        Expression::Invocation(inv) => {
-            visit_expression(&inv.function, old, new, edits);
-            for arg in &inv.arguments {
-                visit_expression(arg, old, new, edits);
-            }
-        }
-        Expression::MemberAccess(ma) => {
-            visit_expression(&ma.expression, old, new, edits);
-        }
-        _ => {}
-    }
+/// Any such use would either clash with the renamed variable or be captured by
+/// it, so the check is kept to the enclosing method, or to the top-level
+/// statements when the variable is not inside one.
+fn name_taken(uast: &TopLevel, bindings: &Bindings, decl: &VarDecl, name: &str) -> bool {
+    let region = enclosing_function(uast, &decl.name_span).map(|context| &context.function.span);
+    let in_region = |span: &Span| region.is_none_or(|region| region.contains(span));
+
+    bindings
+        .references
+        .iter()
+        .any(|reference| reference.decl.name == name && in_region(&reference.span))
+        || bindings
+            .unresolved
+            .iter()
+            .any(|usage| usage.name == name && in_region(&usage.span))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_rename_variable_in_invocation() {
-        let old_name = "oldVar";
-        let new_name = "newVar";
-        let rename = RenameVariable::new(old_name, new_name);
-
-        // x = Console.WriteLine(oldVar)
-        let uast = TopLevel::Statement(Statement::ExpressionStatement(ExpressionStatement {
+    /// `void Run(int oldVar) { x = Console.WriteLine(oldVar); }`
+    fn method() -> TopLevel {
+        let param = VarDecl {
+            span: Span { start: 9, end: 19 },
+            modifiers: None,
+            var_type: Some("int".to_string()),
+            name: "oldVar".to_string(),
+            name_span: Span { start: 13, end: 19 },
+            value: None,
+        };
+        let call = Expression::Invocation(Invocation {
+            function: Box::new(Expression::MemberAccess(MemberAccess {
+                expression: Box::new(Expression::Identifier(
+                    "Console".to_string(),
+                    Span { start: 27, end: 34 },
+                )),
+                member: "WriteLine".to_string(),
+                member_span: Span { start: 35, end: 44 },
+                span: Span { start: 27, end: 44 },
+            })),
+            arguments: vec![Expression::Identifier(
+                "oldVar".to_string(),
+                Span { start: 45, end: 51 },
+            )],
+            span: Span { start: 27, end: 52 },
+        });
+        let stmt = Statement::ExpressionStatement(ExpressionStatement {
             expression: Box::new(Expression::Assignment(Assignment {
                 left: Box::new(Expression::Identifier(
                     "x".to_string(),
-                    Span { start: 0, end: 1 },
+                    Span { start: 23, end: 24 },
                 )),
                 operator: AssignmentOperator::Assign,
-                right: Box::new(Expression::Invocation(Invocation {
-                    function: Box::new(Expression::MemberAccess(MemberAccess {
-                        expression: Box::new(Expression::Identifier(
-                            "Console".to_string(),
-                            Span { start: 4, end: 11 },
-                        )),
-                        member: "WriteLine".to_string(),
-                        member_span: Span { start: 12, end: 21 },
-                    })),
-                    arguments: vec![Expression::Identifier(
-                        old_name.to_string(),
-                        Span { start: 22, end: 28 },
-                    )],
-                })),
+                right: Box::new(call),
+                span: Span { start: 23, end: 52 },
             })),
-            span: Span { start: 0, end: 29 },
-        }));
+            span: Span { start: 23, end: 53 },
+        });
+
+        TopLevel::Function(FunctionDef {
+            name: "Run".to_string(),
+            name_span: Span { start: 5, end: 8 },
+            span: Span { start: 0, end: 55 },
+            body: Some(vec![FunctionBodyItems::Block(Block {
+                statements: vec![stmt],
+                span: Span { start: 21, end: 55 },
+            })]),
+            modifiers: None,
+            parameters: Some(vec![param]),
+            return_type: Some("void".to_string()),
+            annotations: None,
+            metadata: None,
+        })
+    }
 
-        let edits = rename.apply(&uast);
+    #[test]
+    fn test_rename_variable_in_invocation() {
+        let rename = RenameVariable::new(47, "newVar");
 
-        assert_eq!(edits.len(), 1);
-        assert_eq!(edits[0].start, 22);
-        assert_eq!(edits[0].end, 28);
-        assert_eq!(edits[0].replacement, new_name);
+        let edits = rename.apply(&method(), "").unwrap();
+
+        assert_eq!(edits.len(), 2);
+        assert_eq!((edits[0].start, edits[0].end), (13, 19));
+        assert_eq!((edits[1].start, edits[1].end), (45, 51));
+        assert!(edits.iter().all(|edit| edit.replacement == "newVar"));
     }
 
     #[test]
     fn test_rename_object_in_member_access() {
-        let old_name = "Console";
-        let new_name = "MyConsole";
-        let rename = RenameVariable::new(old_name, new_name);
-
-        // Console.WriteLine(arg)
-        let uast = TopLevel::Statement(Statement::ExpressionStatement(ExpressionStatement {
-            expression: Box::new(Expression::Invocation(Invocation {
-                function: Box::new(Expression::MemberAccess(MemberAccess {
-                    expression: Box::new(Expression::Identifier(
-                        old_name.to_string(),
-                        Span { start: 0, end: 7 },
-                    )),
-                    member: "WriteLine".to_string(),
-                    member_span: Span { start: 8, end: 17 },
-                })),
-                arguments: vec![Expression::Identifier(
-                    "arg".to_string(),
-                    Span { start: 18, end: 21 },
-                )],
-            })),
-            span: Span { start: 0, end: 22 },
-        }));
+        let rename = RenameVariable::new(30, "MyConsole");
 
-        let edits = rename.apply(&uast);
+        let result = rename.apply(&method(), "");
 
-        assert_eq!(edits.len(), 1);
-        assert_eq!(edits[0].start, 0);
-        assert_eq!(edits[0].end, 7);
-        assert_eq!(edits[0].replacement, new_name);
+        assert!(matches!(result, Err(RefactorError::InvalidSelection(_))));
     }
 }
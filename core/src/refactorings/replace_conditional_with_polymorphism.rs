@@ -0,0 +1,418 @@
+use crate::analysis::locate::{find_function, function_blocks, functions};
+use crate::analysis::members::members;
+use crate::analysis::variables::{function_declarations, raw_tokens};
+use crate::text::{
+    declares_constructor, declares_type, line_indent, reindent, widen_access, MethodStyle,
+};
+use crate::{RefactorError, Refactoring, TextEdit};
+use uast::*;
+
+/// Replace a method that dispatches on a type code with one subclass per
+/// type code value, each overriding the method with its own branch.
+///
+/// The method must consist of a single `switch` on a field of its class, or
+/// an `if`/`else if` chain comparing that field to constants. The method
+/// becomes abstract, and so does the class, unless the `default` branch does
+/// more than throw. In that case it stays in the class as a virtual method.
+/// Subclasses are named after the case value followed by the class name, so
+/// `case BirdType.African:` in `Bird` becomes `AfricanBird`.
+pub struct ReplaceConditionalWithPolymorphism {
+    pub method: String,
+}
+
+impl ReplaceConditionalWithPolymorphism {
+    pub fn new(method: &str) -> Self {
+        ReplaceConditionalWithPolymorphism {
+            method: method.to_string(),
+        }
+    }
+}
+
+/// One branch of the dispatch and the type code values that select it.
+struct Branch<'a> {
+    values: Vec<&'a Expression>,
+    statements: &'a [Statement],
+}
+
+impl Refactoring for ReplaceConditionalWithPolymorphism {
+    fn apply(&self, uast: &TopLevel, source: &str) -> Result<Vec<TextEdit>, RefactorError> {
+        let context = find_function(uast, None, &|func| func.name == self.method)
+            .ok_or_else(|| RefactorError::NotFound(format!("method `{}`", self.method)))?;
+        let func = context.function;
+        let class = context.class.ok_or_else(|| {
+            RefactorError::Unsupported("the method is not declared in a class".to_string())
+        })?;
+        self.check_method(uast, func)?;
+        check_class(class, source)?;
+
+        let body = function_blocks(func)
+            .next()
+            .ok_or_else(|| RefactorError::Unsupported(format!("`{}` has no body", self.method)))?;
+        let (field, branches, default) = match body.statements.as_slice() {
+            [Statement::SwitchStatement(switch)] => switch_branches(switch)?,
+            [Statement::IfStatement(if_stmt)] => if_chain_branches(if_stmt)?,
+            _ => {
+                return Err(RefactorError::Unsupported(format!(
+                    "`{}` does more than a single `switch` or `if` chain",
+                    self.method
+                )))
+            }
+        };
+        let is_field = members(class).iter().any(|member| member.name() == field)
+            && !function_declarations(func)
+                .iter()
+                .any(|decl| decl.name == field);
+        if !is_field {
+            return Err(RefactorError::Unsupported(format!(
+                "`{}` is not a field of `{}`",
+                field, class.name
+            )));
+        }
+
+        let mut subclasses: Vec<(String, &[Statement])> = Vec::new();
+        for branch in &branches {
+            for value in &branch.values {
+                let name = format!("{}{}", type_name(value, source)?, class.name);
+                let taken = subclasses.iter().any(|(other, _)| *other == name)
+                    || declares_type(source, &name);
+                if taken {
+                    return Err(RefactorError::Conflict(format!(
+                        "a class named `{}` already exists",
+                        name
+                    )));
+                }
+                subclasses.push((name, branch.statements));
+            }
+        }
+
+        // A default branch that only throws cannot be reached once every type
+        // code has its own subclass.
+        let default = default.filter(|statements| {
+            !matches!(statements, [Statement::Unknown { source, .. }] if source.starts_with("throw"))
+        });
+
+        let style = MethodStyle::of(source, func);
+        let access: Vec<&str> = func
+            .modifiers
+            .iter()
+            .flatten()
+            .map(String::as_str)
+            .filter(|modifier| matches!(*modifier, "public" | "protected" | "internal"))
+            .collect();
+        let return_type = func.return_type.as_deref().unwrap_or("void");
+        let signature = format!(
+            "{} {}{}",
+            return_type,
+            func.name,
+            source[func.name_span.end..body.span.start].trim_end()
+        );
+
+        let mut edits = Vec::new();
+        match default {
+            None => {
+                let keyword = format!("class {}", class.name);
+                let is_abstract = class
+                    .modifiers
+                    .iter()
+                    .flatten()
+                    .any(|modifier| modifier == "abstract");
+                if !is_abstract {
+                    let offset = source[class.span.start..class.span.end]
+                        .find(&keyword)
+                        .map(|i| class.span.start + i)
+                        .unwrap_or(class.span.start);
+                    edits.push(TextEdit {
+                        start: offset,
+                        end: offset,
+                        replacement: "abstract ".to_string(),
+                    });
+                }
+                edits.push(TextEdit {
+                    start: func.span.start,
+                    end: func.span.end,
+                    replacement: format!("{} abstract {};", access.join(" "), signature),
+                });
+            }
+            Some(statements) => {
+                let header = format!("{} virtual {}", access.join(" "), signature);
+                let method = style.render(&header, &moved(statements, source, &style.body_indent));
+                edits.push(TextEdit {
+                    start: func.span.start,
+                    end: func.span.end,
+                    replacement: method[style.member_indent.len()..].to_string(),
+                });
+            }
+        }
+
+        let moved_spans: Vec<Span> = subclasses
+            .iter()
+            .filter_map(|(_, statements)| statements_span(statements))
+            .collect();
+        edits.extend(protect_members(class, func, &moved_spans, source));
+
+        let class_indent = line_indent(source, class.span.start);
+        let class_style = MethodStyle {
+            member_indent: class_indent.to_string(),
+            body_indent: style.member_indent.clone(),
+            allman: style.allman,
+        };
+        let class_access = class
+            .modifiers
+            .iter()
+            .flatten()
+            .find(|modifier| matches!(modifier.as_str(), "public" | "internal"))
+            .map_or(String::new(), |modifier| format!("{} ", modifier));
+        let override_header = format!("{} override {}", access.join(" "), signature);
+        let text: String = subclasses
+            .iter()
+            .map(|(name, statements)| {
+                let method = style.render(
+                    &override_header,
+                    &moved(statements, source, &style.body_indent),
+                );
+                let header = format!("{}class {} : {}", class_access, name, class.name);
+                format!("\n\n{}", class_style.render(&header, &method))
+            })
+            .collect();
+        edits.push(TextEdit {
+            start: class.span.end,
+            end: class.span.end,
+            replacement: text,
+        });
+
+        Ok(edits)
+    }
+}
+
+impl ReplaceConditionalWithPolymorphism {
+    fn check_method(&self, uast: &TopLevel, func: &FunctionDef) -> Result<(), RefactorError> {
+        let overloads = functions(uast)
+            .into_iter()
+            .filter(|other| other.name == self.method)
+            .count();
+        if overloads > 1 {
+            return Err(RefactorError::Unsupported(format!(
+                "`{}` is overloaded",
+                self.method
+            )));
+        }
+        let modifiers: Vec<&str> = func
+            .modifiers
+            .iter()
+            .flatten()
+            .map(String::as_str)
+            .collect();
+        if let Some(modifier) = modifiers
+            .iter()
+            .find(|modifier| matches!(**modifier, "static" | "virtual" | "override" | "abstract"))
+        {
+            return Err(RefactorError::Unsupported(format!(
+                "`{}` is {} and cannot be overridden by new subclasses",
+                self.method, modifier
+            )));
+        }
+        if !modifiers
+            .iter()
+            .any(|modifier| matches!(*modifier, "public" | "protected" | "internal"))
+        {
+            return Err(RefactorError::Unsupported(format!(
+                "`{}` is private and cannot be overridden",
+                self.method
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Subclasses get only the implicit parameterless constructor, and existing
+/// `new` expressions would keep creating the base class.
+fn check_class(class: &ClassDef, source: &str) -> Result<(), RefactorError> {
+    if let Some(modifier) = class
+        .modifiers
+        .iter()
+        .flatten()
+        .find(|modifier| matches!(modifier.as_str(), "sealed" | "static"))
+    {
+        return Err(RefactorError::Unsupported(format!(
+            "`{}` is {} and cannot have subclasses",
+            class.name, modifier
+        )));
+    }
+    if declares_constructor(source, class) {
+        return Err(RefactorError::Unsupported(format!(
+            "`{}` declares constructors, which its subclasses would have to repeat",
+            class.name
+        )));
+    }
+    let instantiated = source.match_indices("new ").any(|(i, _)| {
+        source[i + 4..]
+            .trim_start()
+            .strip_prefix(class.name.as_str())
+            .is_some_and(|after| after.trim_start().starts_with(['(', '{']))
+    });
+    if instantiated {
+        return Err(RefactorError::Unsupported(format!(
+            "`{}` is created with `new`, which would not pick a subclass",
+            class.name
+        )));
+    }
+    Ok(())
+}
+
+type Dispatch<'a> = (String, Vec<Branch<'a>>, Option<&'a [Statement]>);
+
+fn switch_branches(switch: &SwitchStatement) -> Result<Dispatch<'_>, RefactorError> {
+    let field = type_code(&switch.value)
+        .ok_or_else(|| RefactorError::Unsupported("the `switch` is not on a field".to_string()))?;
+    let mut branches = Vec::new();
+    let mut default = None;
+    for section in &switch.sections {
+        let mut statements = section.body.statements.as_slice();
+        if let [rest @ .., Statement::Unknown { source, .. }] = statements {
+            if source.trim() == "break;" {
+                statements = rest;
+            }
+        }
+        let jumps = statements.iter().any(|stmt| {
+            matches!(stmt, Statement::Unknown { source, .. }
+                if source.starts_with("break") || source.starts_with("goto"))
+        });
+        if jumps {
+            return Err(RefactorError::Unsupported(
+                "a `switch` section leaves with `break` or `goto` before its end".to_string(),
+            ));
+        }
+
+        let mut values = Vec::new();
+        for label in &section.labels {
+            match label {
+                SwitchLabel::Case(value) => values.push(value),
+                SwitchLabel::Default(_) => default = Some(statements),
+            }
+        }
+        if default.is_some_and(|d| std::ptr::eq(d, statements)) && !values.is_empty() {
+            return Err(RefactorError::Unsupported(
+                "a `switch` section is both a `case` and the `default`".to_string(),
+            ));
+        }
+        if !values.is_empty() {
+            branches.push(Branch { values, statements });
+        }
+    }
+    Ok((field, branches, default))
+}
+
+fn if_chain_branches(if_stmt: &IfStatement) -> Result<Dispatch<'_>, RefactorError> {
+    let mut field: Option<String> = None;
+    let mut branches = Vec::new();
+    let mut current = if_stmt;
+    loop {
+        let comparison = match current.condition.as_ref() {
+            Expression::BinaryOp(op) if op.operator == BinaryOperator::Equal => {
+                match (type_code(&op.left), type_code(&op.right)) {
+                    (Some(name), None) => Some((name, op.right.as_ref())),
+                    (None, Some(name)) => Some((name, op.left.as_ref())),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        let (name, value) = comparison
+            .filter(|(name, _)| field.as_ref().is_none_or(|field| field == name))
+            .ok_or_else(|| {
+                RefactorError::Unsupported(
+                    "every condition must compare the same field with a value".to_string(),
+                )
+            })?;
+        field = Some(name);
+        branches.push(Branch {
+            values: vec![value],
+            statements: &current.consequence.statements,
+        });
+
+        match current.alternative.as_deref() {
+            Some(alt) => match alt.statements.as_slice() {
+                [Statement::IfStatement(next)] => current = next,
+                statements => return Ok((field.unwrap(), branches, Some(statements))),
+            },
+            None => return Ok((field.unwrap(), branches, None)),
+        }
+    }
+}
+
+/// The name of the field `expr` reads, if it is `name` or `this.name`.
+fn type_code(expr: &Expression) -> Option<String> {
+    match expr {
+        Expression::Identifier(name, _) => Some(name.clone()),
+        Expression::MemberAccess(ma) => match ma.expression.as_ref() {
+            Expression::Raw { source, .. } if source == "this" => Some(ma.member.clone()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The subclass name prefix for a type code value: the constant's own name,
+/// or the words of a string.
+fn type_name(value: &Expression, source: &str) -> Result<String, RefactorError> {
+    let name = match value {
+        Expression::MemberAccess(ma) => ma.member.clone(),
+        Expression::Literal(Literal::String(text), _) => text
+            .split(|c: char| !c.is_alphanumeric())
+            .map(|word| {
+                let mut chars = word.chars();
+                chars.next().map_or_else(String::new, |first| {
+                    first.to_uppercase().chain(chars).collect()
+                })
+            })
+            .collect(),
+        _ => String::new(),
+    };
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        let span = value.span();
+        return Err(RefactorError::Unsupported(format!(
+            "cannot name a subclass after `{}`",
+            &source[span.start..span.end]
+        )));
+    }
+    Ok(name)
+}
+
+fn statements_span(statements: &[Statement]) -> Option<Span> {
+    match (statements.first(), statements.last()) {
+        (Some(first), Some(last)) => Some(Span {
+            start: first.span().start,
+            end: last.span().end,
+        }),
+        _ => None,
+    }
+}
+
+/// The text of `statements` indented as the body of a method.
+fn moved(statements: &[Statement], source: &str, indent: &str) -> String {
+    match statements_span(statements) {
+        Some(span) => reindent(
+            &source[span.start..span.end],
+            line_indent(source, span.start),
+            indent,
+        ),
+        None => String::new(),
+    }
+}
+
+/// Make the private members of `class` that the moved branches use protected,
+/// so the subclasses can still reach them.
+fn protect_members(
+    class: &ClassDef,
+    func: &FunctionDef,
+    moved: &[Span],
+    source: &str,
+) -> Vec<TextEdit> {
+    let used: Vec<String> = moved
+        .iter()
+        .flat_map(|span| raw_tokens(&source[span.start..span.end], span))
+        .map(|token| token.text)
+        .collect();
+    let used: Vec<&str> = used.iter().map(String::as_str).collect();
+    widen_access(class, Some(func), &used, "protected", source)
+}
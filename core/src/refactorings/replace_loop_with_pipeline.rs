@@ -0,0 +1,655 @@
+use crate::analysis::locate::{
+    enclosing_function, function_blocks, innermost_loop, subexpressions,
+};
+use crate::analysis::members::members;
+use crate::analysis::scope::{resolve_function, Bindings};
+use crate::analysis::variables::{expression_usages, function_declarations};
+use crate::syntax::{literal_type, negate, precedence, wrap, PRIMARY};
+use crate::text::{add_using, removal_span, slice};
+use crate::{RefactorError, Refactoring, TextEdit};
+use uast::*;
+
+/// Replace a loop that filters, maps and accumulates the items of a
+/// collection with a LINQ pipeline.
+///
+/// The body of the loop at `offset` may filter with an `if` around the rest
+/// of the body or with `if (...) continue;`, map by declaring a local from
+/// the current item, and must end by adding to a list with `list.Add(...)` or
+/// to a total with `total += ...`. These become `.Where(...)`, `.Select(...)`
+/// and `.ToList()` or `.Sum(...)`. When the list or total is declared right
+/// before the loop, the pipeline becomes its initializer; otherwise the loop
+/// becomes `list.AddRange(...)` or `total += ...`.
+///
+/// Besides `foreach`, a `for` loop counting from zero up to the `Length` or
+/// `Count` of a collection is accepted as long as its index is only used to
+/// read the current item.
+///
+/// `.Sum()` adds in the type of the values it is given, so a total is only
+/// accepted when its declared numeric type is the type of the values added to
+/// it, as far as that can be told from their declarations.
+pub struct ReplaceLoopWithPipeline {
+    pub offset: usize,
+}
+
+impl ReplaceLoopWithPipeline {
+    pub fn new(offset: usize) -> Self {
+        ReplaceLoopWithPipeline { offset }
+    }
+}
+
+impl Refactoring for ReplaceLoopWithPipeline {
+    fn apply(&self, uast: &TopLevel, source: &str) -> Result<Vec<TextEdit>, RefactorError> {
+        let at = Span {
+            start: self.offset,
+            end: self.offset,
+        };
+        let context = enclosing_function(uast, &at).ok_or_else(|| {
+            RefactorError::InvalidSelection("the offset is not inside a method".to_string())
+        })?;
+        let func = context.function;
+        let (block, index) = function_blocks(func)
+            .find_map(|block| innermost_loop(block, &at))
+            .ok_or_else(|| {
+                RefactorError::InvalidSelection("the offset is not inside a loop".to_string())
+            })?;
+        let stmt = &block.statements[index];
+        let items = Items::of(stmt, source)?;
+
+        let (stages, sink) = parse_body(&items.body.statements).ok_or_else(|| {
+            RefactorError::Unsupported(
+                "the loop body does not only filter, map and add to a list or a total".to_string(),
+            )
+        })?;
+
+        // Each step of the pipeline only sees the item handed to it, so every
+        // expression may use the current item but not the ones before it.
+        let mut steps: Vec<&str> = vec![&items.key];
+        steps.extend(stages.iter().filter_map(|stage| match stage {
+            Stage::Select { name, .. } => Some(*name),
+            Stage::Where { .. } => None,
+        }));
+        let mut current = items.key.as_str();
+        let mut checked: Vec<(&Expression, &str)> = Vec::new();
+        for stage in &stages {
+            match stage {
+                Stage::Where { condition, .. } => checked.push((condition, current)),
+                Stage::Select { value, name, .. } => {
+                    checked.push((value, current));
+                    current = name;
+                }
+            }
+        }
+        checked.push((sink.value(), current));
+        for (expr, current) in &checked {
+            for usage in expression_usages(expr) {
+                if usage.access.writes() {
+                    return Err(RefactorError::Unsupported(format!(
+                        "the loop body assigns `{}` outside of its accumulation",
+                        usage.name
+                    )));
+                }
+                if usage.name == sink.target() {
+                    return Err(RefactorError::Unsupported(format!(
+                        "the loop reads `{}` while accumulating it",
+                        usage.name
+                    )));
+                }
+                if steps.contains(&usage.name.as_str()) && usage.name != *current {
+                    return Err(RefactorError::Unsupported(format!(
+                        "`{}` is used after the pipeline has moved on from it",
+                        usage.name
+                    )));
+                }
+            }
+        }
+
+        // Name the item of a counting loop, whose index goes away.
+        let bindings = resolve_function(func);
+        let element = match &items.access {
+            Some(access) => {
+                let index_decl = bindings.declaration_at(items.index_span.start);
+                let mut accesses = Vec::new();
+                for (expr, _) in &checked {
+                    collect_accesses(expr, access, &mut accesses);
+                }
+                let stray = checked
+                    .iter()
+                    .flat_map(|(expr, _)| expression_usages(expr))
+                    .filter(|usage| {
+                        index_decl.is_some_and(|decl| {
+                            bindings
+                                .declaration_at(usage.span.start)
+                                .is_some_and(|found| std::ptr::eq(found, decl))
+                        })
+                    })
+                    .any(|usage| !accesses.iter().any(|span| span.contains(&usage.span)));
+                if stray {
+                    return Err(RefactorError::Unsupported(format!(
+                        "`{}` is used for more than reading the current item",
+                        items.key
+                    )));
+                }
+                fresh_name(func, &bindings)
+            }
+            None => items.key.clone(),
+        };
+        let render = |text: String| match &items.access {
+            Some(access) => text.replace(access.as_str(), &element),
+            None => text,
+        };
+
+        let mut pipeline = wrap(
+            slice(source, items.collection.span()),
+            precedence(items.collection),
+            PRIMARY,
+        );
+        let mut current = element.clone();
+        for stage in &stages {
+            match stage {
+                Stage::Where { condition, negated } => {
+                    let text = if *negated {
+                        negate(condition, source)
+                    } else {
+                        slice(source, condition.span()).to_string()
+                    };
+                    pipeline += &format!(".Where({} => {})", current, render(text));
+                }
+                Stage::Select { value, name, .. } => {
+                    let text = render(slice(source, value.span()).to_string());
+                    pipeline += &format!(".Select({} => {})", current, text);
+                    current = name.to_string();
+                }
+            }
+        }
+        let value = sink.value();
+        let is_item = matches!(value, Expression::Identifier(name, _) if *name == current)
+            || items.access.as_deref() == Some(slice(source, value.span()));
+        let mapped = render(slice(source, value.span()).to_string());
+
+        let fresh = index
+            .checked_sub(1)
+            .and_then(|before| fresh_declaration(&block.statements[before], &sink));
+        if let Sink::Sum { total, at, value } = &sink {
+            let typing = Typing {
+                bindings: &bindings,
+                class: context.class,
+                source,
+                access: items.access.as_deref(),
+                item: None,
+                known: Vec::new(),
+            };
+            check_sum(total, at, value, &stages, &items, fresh, typing)?;
+        }
+        let mut edits = Vec::new();
+        match (&sink, fresh) {
+            (Sink::Collect { .. }, Some(initializer)) => {
+                if !is_item {
+                    pipeline += &format!(".Select({} => {})", current, mapped);
+                }
+                edits.push(replace(
+                    initializer.span(),
+                    format!("{}.ToList()", pipeline),
+                ));
+                edits.push(replace(&removal_span(source, stmt.span()), String::new()));
+            }
+            (Sink::Sum { .. }, Some(initializer)) => {
+                pipeline += &sum(is_item, &current, &mapped);
+                edits.push(replace(initializer.span(), pipeline));
+                edits.push(replace(&removal_span(source, stmt.span()), String::new()));
+            }
+            (Sink::Collect { list, .. }, None) => {
+                if !is_item {
+                    pipeline += &format!(".Select({} => {})", current, mapped);
+                }
+                edits.push(replace(
+                    stmt.span(),
+                    format!("{}.AddRange({});", list, pipeline),
+                ));
+            }
+            (Sink::Sum { total, .. }, None) => {
+                pipeline += &sum(is_item, &current, &mapped);
+                edits.push(replace(stmt.span(), format!("{} += {};", total, pipeline)));
+            }
+        }
+        edits.extend(add_using(source, "System.Linq"));
+        Ok(edits)
+    }
+}
+
+/// What the loop iterates over.
+struct Items<'a> {
+    collection: &'a Expression,
+    /// The `foreach` variable, or the index of a counting `for` loop.
+    key: String,
+    /// The declared type of the `foreach` variable, unless it is `var`.
+    item_type: Option<&'a str>,
+    index_span: Span,
+    /// How a counting loop reads the current item, as in `items[i]`.
+    access: Option<String>,
+    body: &'a Block,
+}
+
+impl<'a> Items<'a> {
+    fn of(stmt: &'a Statement, source: &str) -> Result<Items<'a>, RefactorError> {
+        match stmt {
+            Statement::ForEachLoop(f) => Ok(Items {
+                collection: &f.collection,
+                key: f.variable.name.clone(),
+                item_type: f.variable.var_type.as_deref().filter(|ty| *ty != "var"),
+                index_span: f.variable.name_span.clone(),
+                access: None,
+                body: &f.body,
+            }),
+            Statement::ForLoop(f) => {
+                let counting = counting_loop(f).ok_or_else(|| {
+                    RefactorError::Unsupported(
+                        "the loop does not count from zero up to the size of a collection"
+                            .to_string(),
+                    )
+                })?;
+                let (index, collection) = counting;
+                Ok(Items {
+                    collection,
+                    key: index.name.clone(),
+                    item_type: None,
+                    index_span: index.name_span.clone(),
+                    access: Some(format!(
+                        "{}[{}]",
+                        slice(source, collection.span()),
+                        index.name
+                    )),
+                    body: &f.body,
+                })
+            }
+            _ => Err(RefactorError::Unsupported(
+                "only `foreach` loops and `for` loops over a collection can become a pipeline"
+                    .to_string(),
+            )),
+        }
+    }
+}
+
+/// The index and collection of `for (int i = 0; i < items.Length; i++)`,
+/// where `Count` may stand for `Length`.
+fn counting_loop(f: &ForLoop) -> Option<(&VarDecl, &Expression)> {
+    let Some(Statement::DeclStmt(init)) = f.initializer.as_deref() else {
+        return None;
+    };
+    let [index] = init.var_decls.as_slice() else {
+        return None;
+    };
+    if !matches!(
+        index.value.as_deref(),
+        Some(Expression::Literal(Literal::Integer(0), _))
+    ) {
+        return None;
+    }
+    let is_index =
+        |expr: &Expression| matches!(expr, Expression::Identifier(name, _) if *name == index.name);
+
+    let Some(Expression::BinaryOp(condition)) = f.condition.as_deref() else {
+        return None;
+    };
+    let Expression::MemberAccess(size) = condition.right.as_ref() else {
+        return None;
+    };
+    let bounded = condition.operator == BinaryOperator::LessThan
+        && is_index(&condition.left)
+        && (size.member == "Length" || size.member == "Count");
+
+    let steps = match f.update.as_deref() {
+        Some(Expression::UnaryOp(op)) => {
+            matches!(
+                op.operator,
+                UnaryOperator::PostIncrement | UnaryOperator::PreIncrement
+            ) && is_index(&op.operand)
+        }
+        Some(Expression::Assignment(assign)) => {
+            assign.operator == AssignmentOperator::AddAssign
+                && is_index(&assign.left)
+                && matches!(*assign.right, Expression::Literal(Literal::Integer(1), _))
+        }
+        _ => false,
+    };
+    (bounded && steps).then_some((index, &size.expression))
+}
+
+enum Stage<'a> {
+    /// `if (condition) { ... }`, or `if (condition) continue;` when negated.
+    Where {
+        condition: &'a Expression,
+        negated: bool,
+    },
+    /// `var name = value;`
+    Select {
+        value: &'a Expression,
+        name: &'a str,
+        var_type: Option<&'a str>,
+    },
+}
+
+enum Sink<'a> {
+    /// `list.Add(item);`
+    Collect { list: &'a str, item: &'a Expression },
+    /// `total += value;`
+    Sum {
+        total: &'a str,
+        at: &'a Span,
+        value: &'a Expression,
+    },
+}
+
+impl Sink<'_> {
+    fn target(&self) -> &str {
+        match self {
+            Sink::Collect { list, .. } => list,
+            Sink::Sum { total, .. } => total,
+        }
+    }
+
+    fn value(&self) -> &Expression {
+        match self {
+            Sink::Collect { item, .. } => item,
+            Sink::Sum { value, .. } => value,
+        }
+    }
+}
+
+fn parse_body(mut statements: &[Statement]) -> Option<(Vec<Stage<'_>>, Sink<'_>)> {
+    let mut stages = Vec::new();
+    loop {
+        match statements {
+            [Statement::IfStatement(if_stmt), rest @ ..]
+                if !rest.is_empty()
+                    && if_stmt.alternative.is_none()
+                    && is_continue(&if_stmt.consequence) =>
+            {
+                stages.push(Stage::Where {
+                    condition: &if_stmt.condition,
+                    negated: true,
+                });
+                statements = rest;
+            }
+            [Statement::DeclStmt(decl), rest @ ..] if !rest.is_empty() => {
+                let [var] = decl.var_decls.as_slice() else {
+                    return None;
+                };
+                stages.push(Stage::Select {
+                    value: var.value.as_deref()?,
+                    name: &var.name,
+                    var_type: var.var_type.as_deref(),
+                });
+                statements = rest;
+            }
+            [Statement::IfStatement(if_stmt)] if if_stmt.alternative.is_none() => {
+                stages.push(Stage::Where {
+                    condition: &if_stmt.condition,
+                    negated: false,
+                });
+                statements = &if_stmt.consequence.statements;
+            }
+            [Statement::ExpressionStatement(last)] => {
+                return Some((stages, sink(&last.expression)?));
+            }
+            _ => return None,
+        }
+    }
+}
+
+fn is_continue(block: &Block) -> bool {
+    matches!(
+        block.statements.as_slice(),
+        [Statement::Unknown { source, .. }] if source.trim() == "continue;"
+    )
+}
+
+fn sink(expr: &Expression) -> Option<Sink<'_>> {
+    match expr {
+        Expression::Invocation(inv) => {
+            let Expression::MemberAccess(ma) = inv.function.as_ref() else {
+                return None;
+            };
+            let (Expression::Identifier(list, _), [item]) =
+                (ma.expression.as_ref(), inv.arguments.as_slice())
+            else {
+                return None;
+            };
+            (ma.member == "Add").then_some(Sink::Collect { list, item })
+        }
+        Expression::Assignment(assign) if assign.operator == AssignmentOperator::AddAssign => {
+            match assign.left.as_ref() {
+                Expression::Identifier(total, at) => Some(Sink::Sum {
+                    total,
+                    at,
+                    value: &assign.right,
+                }),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// The initializer of `var list = new List<T>();` or `int total = 0;` when
+/// `stmt` declares the target of `sink` that way.
+fn fresh_declaration<'a>(stmt: &'a Statement, sink: &Sink) -> Option<&'a Expression> {
+    let Statement::DeclStmt(decl) = stmt else {
+        return None;
+    };
+    let [var] = decl.var_decls.as_slice() else {
+        return None;
+    };
+    if var.name != sink.target() {
+        return None;
+    }
+    let value = var.value.as_deref()?;
+    let empty = match (sink, value) {
+        (Sink::Collect { .. }, Expression::Raw { source, .. }) => {
+            let compact: String = source.split_whitespace().collect();
+            compact.starts_with("newList<") && compact.ends_with(">()")
+        }
+        (Sink::Sum { .. }, Expression::Literal(Literal::Integer(0), _)) => true,
+        (Sink::Sum { .. }, Expression::Literal(Literal::Float(zero), _)) => *zero == 0.0,
+        _ => false,
+    };
+    empty.then_some(value)
+}
+
+/// Refuse to turn `total += value` into `.Sum()` unless `total` has a numeric
+/// type and the values added to it have that very type.
+fn check_sum<'a>(
+    total: &str,
+    at: &Span,
+    value: &Expression,
+    stages: &[Stage<'a>],
+    items: &Items<'a>,
+    fresh: Option<&Expression>,
+    mut typing: Typing<'a, '_>,
+) -> Result<(), RefactorError> {
+    let total_type = match typing.declared(total, at) {
+        Some(var_type) if var_type == "var" => {
+            fresh.and_then(|initializer| literal_type(initializer, typing.source))
+        }
+        var_type => var_type,
+    };
+    let Some(total_type) = total_type.filter(|var_type| {
+        matches!(
+            var_type.as_str(),
+            "int" | "long" | "float" | "double" | "decimal"
+        )
+    }) else {
+        return Err(RefactorError::Unsupported(format!(
+            "`{}` does not have a numeric type that `Sum` can add in",
+            total
+        )));
+    };
+
+    typing.item = items
+        .item_type
+        .map(str::to_string)
+        .or_else(|| match items.collection {
+            Expression::Identifier(name, span) => typing
+                .declared(name, span)
+                .and_then(|var_type| element_type(&var_type)),
+            _ => None,
+        });
+    if items.access.is_none() {
+        typing.known.push((items.key.clone(), typing.item.clone()));
+    }
+    for stage in stages {
+        if let Stage::Select {
+            value,
+            name,
+            var_type,
+        } = stage
+        {
+            let var_type = match var_type {
+                Some(var_type) if *var_type != "var" => Some(var_type.to_string()),
+                _ => typing.of(value),
+            };
+            typing.known.push((name.to_string(), var_type));
+        }
+    }
+    match typing.of(value) {
+        Some(added) if added == total_type => Ok(()),
+        Some(added) => Err(RefactorError::Unsupported(format!(
+            "`{}` is a `{}` but the loop adds `{}` values, which `Sum` would add as `{}`",
+            total, total_type, added, added
+        ))),
+        None => Err(RefactorError::Unsupported(format!(
+            "cannot tell that the values added to `{}` are `{}`s",
+            total, total_type
+        ))),
+    }
+}
+
+/// Types as far as the declarations around a loop tell them.
+struct Typing<'a, 'b> {
+    bindings: &'b Bindings<'a>,
+    class: Option<&'a ClassDef>,
+    source: &'b str,
+    /// How a counting loop reads the current item, as in `items[i]`.
+    access: Option<&'b str>,
+    /// The type of the current item.
+    item: Option<String>,
+    /// The types of the `foreach` variable and of the locals mapped from it.
+    known: Vec<(String, Option<String>)>,
+}
+
+impl Typing<'_, '_> {
+    /// The declared type of the local, parameter or field `name` at `at`.
+    fn declared(&self, name: &str, at: &Span) -> Option<String> {
+        match self.bindings.declaration_at(at.start) {
+            Some(decl) => decl.var_type.clone(),
+            None => self
+                .class
+                .and_then(|class| {
+                    members(class)
+                        .into_iter()
+                        .find(|member| member.name() == name)
+                })
+                .and_then(|member| member.var_type())
+                .map(str::to_string),
+        }
+    }
+
+    fn of(&self, expr: &Expression) -> Option<String> {
+        match expr {
+            Expression::Identifier(name, at) => {
+                match self.known.iter().find(|(known, _)| known == name) {
+                    Some((_, var_type)) => var_type.clone(),
+                    None => self.declared(name, at),
+                }
+            }
+            Expression::Raw { source, .. } if Some(source.as_str()) == self.access => {
+                self.item.clone()
+            }
+            Expression::Literal(..) => literal_type(expr, self.source),
+            Expression::BinaryOp(op)
+                if matches!(
+                    op.operator,
+                    BinaryOperator::Add
+                        | BinaryOperator::Sub
+                        | BinaryOperator::Mul
+                        | BinaryOperator::Div
+                        | BinaryOperator::Mod
+                ) =>
+            {
+                let (left, right) = (self.of(&op.left)?, self.of(&op.right)?);
+                // An `int` literal takes the type of the other operand.
+                let is_int = |expr: &Expression| {
+                    matches!(expr, Expression::Literal(Literal::Integer(_), _))
+                        && literal_type(expr, self.source).as_deref() == Some("int")
+                };
+                if left == right || is_int(&op.right) {
+                    Some(left)
+                } else if is_int(&op.left) {
+                    Some(right)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The element type of an array or of a collection such as `List<int>`.
+fn element_type(collection: &str) -> Option<String> {
+    let element = match collection.strip_suffix("[]") {
+        Some(element) => element,
+        None => {
+            let (_, arguments) = collection.strip_suffix('>')?.split_once('<')?;
+            arguments
+        }
+    };
+    (!element.contains(',')).then(|| element.trim().to_string())
+}
+
+/// The spans of the unlowered `items[i]` reads in `expr`.
+fn collect_accesses(expr: &Expression, access: &str, spans: &mut Vec<Span>) {
+    match expr {
+        Expression::Raw { source, span } => {
+            if source == access {
+                spans.push(span.clone());
+            }
+        }
+        _ => {
+            for nested in subexpressions(expr) {
+                collect_accesses(nested, access, spans);
+            }
+        }
+    }
+}
+
+/// A name for the current item that nothing in `func` uses yet.
+fn fresh_name(func: &FunctionDef, bindings: &Bindings) -> String {
+    let declared = function_declarations(func);
+    let taken = |name: &str| {
+        declared.iter().any(|decl| decl.name == name)
+            || bindings.unresolved.iter().any(|usage| usage.name == name)
+    };
+    let mut name = "item".to_string();
+    let mut n = 2;
+    while taken(&name) {
+        name = format!("item{}", n);
+        n += 1;
+    }
+    name
+}
+
+fn sum(is_item: bool, current: &str, mapped: &str) -> String {
+    if is_item {
+        ".Sum()".to_string()
+    } else {
+        format!(".Sum({} => {})", current, mapped)
+    }
+}
+
+fn replace(span: &Span, replacement: String) -> TextEdit {
+    TextEdit {
+        start: span.start,
+        end: span.end,
+        replacement,
+    }
+}
@@ -0,0 +1,184 @@
+use crate::analysis::locate::{find_function, function_blocks, functions};
+use crate::analysis::variables::function_declarations;
+use crate::syntax::negate;
+use crate::text::{line_indent, reindent, slice};
+use crate::{RefactorError, Refactoring, TextEdit};
+use uast::*;
+
+/// Flatten an `if`/`else` that ends a method into a sequence of guard
+/// clauses that return early.
+///
+/// Every branch of the conditional must end in a `return`, so removing an
+/// `else` cannot let control fall through into code it used to skip. When
+/// the nesting continues in the `if` branch, the condition is inverted so
+/// that the short `else` branch becomes the guard.
+pub struct ReplaceNestedConditionalWithGuardClauses {
+    pub name: String,
+}
+
+impl ReplaceNestedConditionalWithGuardClauses {
+    pub fn new(name: &str) -> Self {
+        ReplaceNestedConditionalWithGuardClauses {
+            name: name.to_string(),
+        }
+    }
+}
+
+impl Refactoring for ReplaceNestedConditionalWithGuardClauses {
+    fn apply(&self, uast: &TopLevel, source: &str) -> Result<Vec<TextEdit>, RefactorError> {
+        let target = find_function(uast, None, &|func| func.name == self.name)
+            .ok_or_else(|| RefactorError::NotFound(format!("method `{}`", self.name)))?
+            .function;
+        let overloads = functions(uast)
+            .into_iter()
+            .filter(|func| func.name == self.name)
+            .count();
+        if overloads > 1 {
+            return Err(RefactorError::Unsupported(format!(
+                "`{}` is overloaded",
+                self.name
+            )));
+        }
+
+        let conditional = function_blocks(target)
+            .next()
+            .and_then(|body| match body.statements.last() {
+                Some(Statement::IfStatement(if_stmt)) if returns(&if_stmt.consequence) => {
+                    if_stmt.alternative.as_ref().filter(|alt| returns(alt))?;
+                    Some(if_stmt)
+                }
+                _ => None,
+            })
+            .ok_or_else(|| {
+                RefactorError::Unsupported(format!(
+                    "`{}` does not end in an `if`/`else` whose branches all return",
+                    self.name
+                ))
+            })?;
+
+        let indent = line_indent(source, conditional.span.start);
+        let mut flattened = Vec::new();
+        let mut clauses = Vec::new();
+        guard_clauses(conditional, indent, source, &mut flattened, &mut clauses);
+
+        // Locals of the flattened blocks move out to the method's scope, where
+        // C# does not let them share a name with any other local.
+        let declarations = function_declarations(target);
+        for block in flattened {
+            for stmt in &block.statements {
+                if let Statement::DeclStmt(decl_stmt) = stmt {
+                    for var in &decl_stmt.var_decls {
+                        let clashes = declarations
+                            .iter()
+                            .filter(|decl| decl.name == var.name)
+                            .count()
+                            > 1;
+                        if clashes {
+                            return Err(RefactorError::Conflict(format!(
+                                "`{}` would be declared twice in `{}`",
+                                var.name, self.name
+                            )));
+                        }
+                    }
+                }
+            }
+        }
+
+        let text = clauses.join("\n");
+        Ok(vec![TextEdit {
+            start: conditional.span.start,
+            end: conditional.span.end,
+            replacement: text[indent.len()..].to_string(),
+        }])
+    }
+}
+
+/// Whether control cannot leave `block` except through a `return`.
+fn returns(block: &Block) -> bool {
+    match block.statements.last() {
+        Some(Statement::ReturnStatement(_)) => true,
+        Some(Statement::IfStatement(if_stmt)) => {
+            returns(&if_stmt.consequence) && if_stmt.alternative.as_deref().is_some_and(returns)
+        }
+        _ => false,
+    }
+}
+
+/// Whether `block` ends in an `if` with an `else`, which can be flattened
+/// further.
+fn ends_in_conditional(block: &Block) -> bool {
+    matches!(
+        block.statements.last(),
+        Some(Statement::IfStatement(if_stmt)) if if_stmt.alternative.is_some()
+    )
+}
+
+/// Append the guard clauses for `if_stmt` to `clauses`, each at `indent`, and
+/// the blocks whose statements were moved out of the conditional to
+/// `flattened`.
+fn guard_clauses<'a>(
+    if_stmt: &'a IfStatement,
+    indent: &str,
+    source: &str,
+    flattened: &mut Vec<&'a Block>,
+    clauses: &mut Vec<String>,
+) {
+    let Some(alternative) = if_stmt.alternative.as_deref() else {
+        return;
+    };
+    let consequence = &if_stmt.consequence;
+    let from = line_indent(source, if_stmt.span.start);
+
+    if ends_in_conditional(consequence) && !ends_in_conditional(alternative) {
+        // Keep whatever separates the original header from its block, so
+        // braces stay on the line they were on.
+        let header_end = source[..consequence.span.start]
+            .rfind(')')
+            .map_or(consequence.span.start, |i| i + 1);
+        let guard = format!(
+            "if ({}){}{}",
+            negate(&if_stmt.condition, source),
+            &source[header_end..consequence.span.start],
+            slice(source, &alternative.span)
+        );
+        clauses.push(reindent(&guard, from, indent));
+        flatten(consequence, indent, source, flattened, clauses);
+    } else {
+        let guard = &source[if_stmt.span.start..consequence.span.end];
+        clauses.push(reindent(guard, from, indent));
+        flatten(alternative, indent, source, flattened, clauses);
+    }
+}
+
+/// Append the statements of `block` at `indent`, turning a conditional at its
+/// end into further guard clauses.
+fn flatten<'a>(
+    block: &'a Block,
+    indent: &str,
+    source: &str,
+    flattened: &mut Vec<&'a Block>,
+    clauses: &mut Vec<String>,
+) {
+    flattened.push(block);
+    let Some((last, rest)) = block.statements.split_last() else {
+        return;
+    };
+    if let Some(first) = rest.first() {
+        let leading = source[first.span().start..last.span().start].trim_end();
+        clauses.push(reindent(
+            leading,
+            line_indent(source, first.span().start),
+            indent,
+        ));
+    }
+    match last {
+        Statement::IfStatement(if_stmt) if if_stmt.alternative.is_some() => {
+            guard_clauses(if_stmt, indent, source, flattened, clauses)
+        }
+        _ => clauses.push(reindent(
+            slice(source, last.span()),
+            line_indent(source, last.span().start),
+            indent,
+        )),
+    }
+}
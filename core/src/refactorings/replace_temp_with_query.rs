@@ -0,0 +1,211 @@
+use crate::analysis::locate::{
+    enclosing_function, locate_declaration, nested_blocks, statement_expressions,
+};
+use crate::analysis::members::member_names;
+use crate::analysis::scope::resolve_function;
+use crate::analysis::variables::{expression_usages, statement_usages};
+use crate::refactorings::extract_function::{call, method_header};
+use crate::syntax::{has_nonlocal_effects, has_side_effects};
+use crate::text::{removal_span, slice, MethodStyle};
+use crate::{RefactorError, Refactoring, TextEdit};
+use uast::*;
+
+/// Replace a local holding the result of an expression with a private method
+/// computing it, so that other methods can ask for the same value.
+///
+/// The initializer may only read fields and parameters; the parameters it
+/// reads become parameters of the query. When it reads fields, no method may
+/// be called between the declaration and a use, as it could change them.
+/// With `property` set, the query is a read-only property instead, which
+/// requires the initializer not to read any parameters.
+pub struct ReplaceTempWithQuery {
+    pub offset: usize,
+    pub name: String,
+    pub property: bool,
+}
+
+impl ReplaceTempWithQuery {
+    pub fn new(offset: usize, name: &str, property: bool) -> Self {
+        ReplaceTempWithQuery {
+            offset,
+            name: name.to_string(),
+            property,
+        }
+    }
+}
+
+impl Refactoring for ReplaceTempWithQuery {
+    fn apply(&self, uast: &TopLevel, source: &str) -> Result<Vec<TextEdit>, RefactorError> {
+        let position = Span {
+            start: self.offset,
+            end: self.offset,
+        };
+        let context = enclosing_function(uast, &position).ok_or_else(|| {
+            RefactorError::InvalidSelection("the offset is not inside a method".to_string())
+        })?;
+        let func = context.function;
+        let class = context.class.ok_or_else(|| {
+            RefactorError::Unsupported("the method is not declared in a class".to_string())
+        })?;
+        let location = locate_declaration(func, self.offset).ok_or_else(|| {
+            RefactorError::InvalidSelection(
+                "the offset is not on the name of a local declaration".to_string(),
+            )
+        })?;
+        let var = location.var;
+
+        let value = var.value.as_deref().ok_or_else(|| {
+            RefactorError::Unsupported(format!("`{}` has no initializer", var.name))
+        })?;
+        if location.statement.var_decls.len() > 1 {
+            return Err(RefactorError::Unsupported(format!(
+                "`{}` is declared together with other variables",
+                var.name
+            )));
+        }
+        let var_type = var
+            .var_type
+            .as_deref()
+            .filter(|var_type| *var_type != "var")
+            .ok_or_else(|| {
+                RefactorError::Unsupported(format!("the type of `{}` is not written out", var.name))
+            })?;
+        if has_side_effects(value) {
+            return Err(RefactorError::Unsupported(format!(
+                "the initializer of `{}` may have side effects",
+                var.name
+            )));
+        }
+        if member_names(class).contains(&self.name.as_str()) {
+            return Err(RefactorError::Conflict(format!(
+                "`{}` already declares a member named `{}`",
+                class.name, self.name
+            )));
+        }
+
+        // Parameters become parameters of the query. Locals are out of reach.
+        let bindings = resolve_function(func);
+        let mut inputs: Vec<&VarDecl> = Vec::new();
+        for usage in expression_usages(value) {
+            let Some(decl) = bindings.declaration_at(usage.span.start) else {
+                continue;
+            };
+            let is_parameter = func
+                .parameters
+                .iter()
+                .flatten()
+                .any(|param| std::ptr::eq(param, decl));
+            if !is_parameter {
+                return Err(RefactorError::Unsupported(format!(
+                    "the initializer of `{}` reads the local `{}`, which the query cannot see",
+                    var.name, decl.name
+                )));
+            }
+            if !inputs.iter().any(|input| std::ptr::eq(*input, decl)) {
+                inputs.push(decl);
+            }
+        }
+        if self.property && !inputs.is_empty() {
+            return Err(RefactorError::Unsupported(format!(
+                "the initializer of `{}` reads parameters, which a property cannot take",
+                var.name
+            )));
+        }
+
+        // Every read must see the value the declaration computed, so neither
+        // the local nor anything its initializer reads may change after it.
+        // Fields may also change in a method called in between.
+        let names: Vec<String> = expression_usages(value)
+            .into_iter()
+            .map(|usage| usage.name)
+            .collect();
+        let reads_fields = expression_usages(value)
+            .iter()
+            .any(|usage| bindings.declaration_at(usage.span.start).is_none());
+        let mut references = Vec::new();
+        let mut inputs_changed = false;
+        for stmt in &location.block.statements[location.index + 1..] {
+            let usages = statement_usages(stmt);
+            inputs_changed |= usages
+                .iter()
+                .any(|usage| usage.access.writes() && names.contains(&usage.name));
+            for usage in usages.iter().filter(|usage| usage.name == var.name) {
+                if usage.access.writes() {
+                    return Err(RefactorError::Unsupported(format!(
+                        "`{}` is assigned after its declaration",
+                        var.name
+                    )));
+                }
+                if inputs_changed {
+                    return Err(RefactorError::Unsupported(format!(
+                        "a variable read by the initializer of `{}` changes before it is used",
+                        var.name
+                    )));
+                }
+                references.push(usage.span.clone());
+            }
+            inputs_changed |= reads_fields && may_change_fields(stmt);
+        }
+
+        let query = if self.property {
+            self.name.clone()
+        } else {
+            call(&self.name, &inputs)
+        };
+        let mut edits: Vec<TextEdit> = references
+            .iter()
+            .map(|span| TextEdit {
+                start: span.start,
+                end: span.end,
+                replacement: query.clone(),
+            })
+            .collect();
+        let removal = removal_span(source, &location.statement.span);
+        edits.push(TextEdit {
+            start: removal.start,
+            end: removal.end,
+            replacement: String::new(),
+        });
+
+        let style = MethodStyle::of(source, func);
+        let text = slice(source, value.span());
+        let member = if self.property {
+            let header = method_header(func, var_type, &self.name, &[]);
+            format!(
+                "{}{} => {};",
+                style.member_indent,
+                header.trim_end_matches("()"),
+                text
+            )
+        } else {
+            style.render(
+                &method_header(func, var_type, &self.name, &inputs),
+                &format!("{}return {};", style.body_indent, text),
+            )
+        };
+        edits.push(TextEdit {
+            start: func.span.end,
+            end: func.span.end,
+            replacement: format!("\n\n{}", member),
+        });
+        Ok(edits)
+    }
+}
+
+/// Like `has_unknown_effects`, except that a `return` only counts for what
+/// its value does, since no read after it is reached.
+fn may_change_fields(stmt: &Statement) -> bool {
+    match stmt {
+        Statement::ReturnStatement(ret) => ret.value.as_deref().is_some_and(has_nonlocal_effects),
+        Statement::Unknown { .. } => true,
+        _ => {
+            statement_expressions(stmt)
+                .into_iter()
+                .any(|(expr, _)| has_nonlocal_effects(expr))
+                || nested_blocks(stmt)
+                    .into_iter()
+                    .flat_map(|block| &block.statements)
+                    .any(may_change_fields)
+        }
+    }
+}
@@ -0,0 +1,187 @@
+use crate::analysis::locate::{enclosing_function, function_blocks, select_statements};
+use crate::analysis::scope::resolve_function;
+use crate::analysis::variables::{statement_declarations, statement_usages, Usage};
+use crate::syntax::has_unknown_effects;
+use crate::text::{line_end, line_start};
+use crate::{RefactorError, Refactoring, TextEdit};
+use uast::*;
+
+/// Move a run of statements up or down within their block, next to the
+/// statement at `target`.
+///
+/// The statements end up directly before `target` when it is above them and
+/// directly after it when it is below. The move is refused when a statement
+/// it crosses writes a variable the moved statements use, or the other way
+/// around. Statements with effects that cannot be analyzed, such as a method
+/// call, a `return` or a statement that was not lowered, are never crossed,
+/// and when the moved statements have them the move is refused past any
+/// statement using a field or a variable they mention.
+pub struct SlideStatements {
+    pub selection: Span,
+    pub target: usize,
+}
+
+impl SlideStatements {
+    pub fn new(start: usize, end: usize, target: usize) -> Self {
+        SlideStatements {
+            selection: Span { start, end },
+            target,
+        }
+    }
+}
+
+impl Refactoring for SlideStatements {
+    fn apply(&self, uast: &TopLevel, source: &str) -> Result<Vec<TextEdit>, RefactorError> {
+        let func = enclosing_function(uast, &self.selection)
+            .ok_or_else(|| {
+                RefactorError::InvalidSelection("the selection is not inside a method".to_string())
+            })?
+            .function;
+        let mut selected = None;
+        for block in function_blocks(func) {
+            if let Some(found) = select_statements(block, &self.selection)? {
+                selected = Some(found);
+                break;
+            }
+        }
+        let selected = selected.ok_or_else(|| {
+            RefactorError::InvalidSelection("the selection contains no statements".to_string())
+        })?;
+        let statements = &selected.block.statements;
+        let target = statements
+            .iter()
+            .position(|stmt| stmt.span().start <= self.target && self.target <= stmt.span().end)
+            .ok_or_else(|| {
+                RefactorError::InvalidSelection(
+                    "the target is not a statement of the same block".to_string(),
+                )
+            })?;
+        let range = selected.range.clone();
+        let crossed = if target < range.start {
+            target..range.start
+        } else if target >= range.end {
+            range.end..target + 1
+        } else {
+            return Err(RefactorError::InvalidSelection(
+                "the target is one of the selected statements".to_string(),
+            ));
+        };
+
+        let bindings = resolve_function(func);
+        let moved = Effects::of(&statements[range.clone()]);
+        for stmt in &statements[crossed] {
+            let other = Effects::of(std::slice::from_ref(stmt));
+            if other.unknown {
+                return Err(RefactorError::Unsupported(format!(
+                    "the statements would move past `{}`, whose effects cannot be analyzed",
+                    summary(source, stmt)
+                )));
+            }
+            // A call may change whatever its arguments and receiver refer
+            // to, as well as any field.
+            let touched = other.usages.iter().any(|usage| {
+                bindings.declaration_at(usage.span.start).is_none()
+                    || moved.usages.iter().any(|own| own.name == usage.name)
+            });
+            if moved.unknown && touched {
+                return Err(RefactorError::Unsupported(format!(
+                    "the statements have effects that cannot be analyzed and would move past `{}`",
+                    summary(source, stmt)
+                )));
+            }
+            if let Some(name) = moved.conflict(&other) {
+                return Err(RefactorError::Unsupported(format!(
+                    "the statements would move past `{}`, which also uses `{}`",
+                    summary(source, stmt),
+                    name
+                )));
+            }
+        }
+
+        let first = statements[range.start].span();
+        let last = statements[range.end - 1].span();
+        let text_span = Span {
+            start: line_start(source, first.start),
+            end: line_end(source, last.end),
+        };
+        let anchor = statements[target].span();
+        let on_own_lines = source[text_span.start..first.start].trim().is_empty()
+            && source[last.end..text_span.end].trim().is_empty()
+            && source[line_start(source, anchor.start)..anchor.start]
+                .trim()
+                .is_empty()
+            && source[anchor.end..line_end(source, anchor.end)]
+                .trim()
+                .is_empty();
+        if !on_own_lines {
+            return Err(RefactorError::Unsupported(
+                "the statements share their lines with other code".to_string(),
+            ));
+        }
+
+        let at = if target < range.start {
+            line_start(source, anchor.start)
+        } else {
+            line_end(source, anchor.end)
+        };
+        Ok(vec![
+            TextEdit {
+                start: text_span.start,
+                end: text_span.end,
+                replacement: String::new(),
+            },
+            TextEdit {
+                start: at,
+                end: at,
+                replacement: source[text_span.start..text_span.end].to_string(),
+            },
+        ])
+    }
+}
+
+/// The first line of a statement, for messages.
+fn summary<'a>(source: &'a str, stmt: &Statement) -> &'a str {
+    let span = stmt.span();
+    source[span.start..span.end]
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .trim()
+}
+
+/// What a run of statements reads, writes and declares.
+struct Effects {
+    usages: Vec<Usage>,
+    declared: Vec<String>,
+    /// Whether the statements call methods, jump, or contain code that was
+    /// not lowered, any of which may touch state the usages do not show.
+    unknown: bool,
+}
+
+impl Effects {
+    fn of(statements: &[Statement]) -> Effects {
+        Effects {
+            usages: statements.iter().flat_map(statement_usages).collect(),
+            declared: statements
+                .iter()
+                .flat_map(statement_declarations)
+                .map(|decl| decl.name.clone())
+                .collect(),
+            unknown: statements.iter().any(has_unknown_effects),
+        }
+    }
+
+    /// A variable that one side writes or declares and the other uses.
+    fn conflict(&self, other: &Effects) -> Option<String> {
+        let one_way = |a: &Effects, b: &Effects| {
+            a.usages
+                .iter()
+                .filter(|usage| usage.access.writes())
+                .map(|usage| &usage.name)
+                .chain(&a.declared)
+                .find(|name| b.usages.iter().any(|usage| usage.name == **name))
+                .cloned()
+        };
+        one_way(self, other).or_else(|| one_way(other, self))
+    }
+}
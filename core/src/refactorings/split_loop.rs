@@ -0,0 +1,276 @@
+use crate::analysis::locate::{enclosing_function, function_blocks, innermost_loop, jump_out};
+use crate::analysis::scope::{resolve_function, Bindings};
+use crate::analysis::variables::{
+    expression_usages, statement_declarations, statement_usages, Usage,
+};
+use crate::syntax::{has_nonlocal_effects, has_unknown_effects};
+use crate::text::{line_end, line_indent, line_start};
+use crate::{RefactorError, Refactoring, TextEdit};
+use uast::*;
+
+/// Split a loop that computes several independent results into one loop per
+/// result.
+///
+/// The statements of the body are grouped by the variables they share: two
+/// statements belong together when one writes or declares a variable the
+/// other uses. A statement with effects that cannot be analyzed, such as
+/// `list.Add(x)`, may change anything it mentions, so it counts as writing
+/// every variable it uses. Each group gets a copy of the loop holding only its own
+/// statements, in their original order. The loop at `offset` is the
+/// innermost `for`, `foreach` or `while` loop containing it.
+///
+/// The split is refused when the body leaves the loop with `break` or
+/// `continue`, when it changes state the loop header depends on, and when
+/// more than one group has effects that cannot be analyzed.
+pub struct SplitLoop {
+    pub offset: usize,
+}
+
+impl SplitLoop {
+    pub fn new(offset: usize) -> Self {
+        SplitLoop { offset }
+    }
+}
+
+impl Refactoring for SplitLoop {
+    fn apply(&self, uast: &TopLevel, source: &str) -> Result<Vec<TextEdit>, RefactorError> {
+        let at = Span {
+            start: self.offset,
+            end: self.offset,
+        };
+        let func = enclosing_function(uast, &at)
+            .ok_or_else(|| {
+                RefactorError::InvalidSelection("the offset is not inside a method".to_string())
+            })?
+            .function;
+        let stmt = function_blocks(func)
+            .find_map(|block| innermost_loop(block, &at))
+            .map(|(block, index)| &block.statements[index])
+            .ok_or_else(|| {
+                RefactorError::InvalidSelection("the offset is not inside a loop".to_string())
+            })?;
+        let header = Header::of(stmt);
+
+        if let Some(keyword) = header.body.statements.iter().find_map(jump_out) {
+            return Err(RefactorError::Unsupported(format!(
+                "the loop body uses `{}`",
+                keyword
+            )));
+        }
+        // Every copy evaluates the header again, so it must start from the
+        // same state each time.
+        if header
+            .expressions
+            .iter()
+            .any(|expr| has_nonlocal_effects(expr))
+            || header.initializer.is_some_and(has_unknown_effects)
+        {
+            return Err(RefactorError::Unsupported(
+                "the loop header has effects that cannot be analyzed".to_string(),
+            ));
+        }
+        let header_usages: Vec<Usage> = header
+            .expressions
+            .iter()
+            .flat_map(|expr| expression_usages(expr))
+            .collect();
+        let reset: Vec<&str> = header
+            .initializer
+            .iter()
+            .flat_map(|init| statement_declarations(init))
+            .map(|decl| decl.name.as_str())
+            .collect();
+        if let Some(usage) = header_usages
+            .iter()
+            .find(|usage| usage.access.writes() && !reset.contains(&usage.name.as_str()))
+        {
+            return Err(RefactorError::Unsupported(format!(
+                "the loop header changes `{}`, which would not start over for each copy",
+                usage.name
+            )));
+        }
+
+        let bindings = resolve_function(func);
+        let statements = &header.body.statements;
+        let effects: Vec<Effects> = statements
+            .iter()
+            .map(|stmt| Effects::of(stmt, &bindings))
+            .collect();
+        for effect in &effects {
+            if let Some(name) = effect
+                .written
+                .iter()
+                .find(|name| header_usages.iter().any(|usage| usage.name == **name))
+            {
+                return Err(RefactorError::Unsupported(format!(
+                    "the loop body changes `{}`, which controls the loop",
+                    name
+                )));
+            }
+        }
+
+        let groups = partition(&effects);
+        if groups.len() < 2 {
+            return Err(RefactorError::Unsupported(
+                "the statements of the loop body all depend on each other".to_string(),
+            ));
+        }
+        let opaque: Vec<&Vec<usize>> = groups
+            .iter()
+            .filter(|group| group.iter().any(|&i| effects[i].unknown))
+            .collect();
+        let reads_nonlocal = |group: &Vec<usize>| group.iter().any(|&i| effects[i].nonlocal);
+        let shares_state = match opaque.as_slice() {
+            [] => false,
+            [group] => groups
+                .iter()
+                .any(|other| other != *group && reads_nonlocal(other)),
+            _ => true,
+        };
+        if shares_state {
+            return Err(RefactorError::Unsupported(
+                "the loop body has effects that cannot be analyzed, which other statements may depend on"
+                    .to_string(),
+            ));
+        }
+
+        let body = &header.body.span;
+        for stmt in statements {
+            let span = stmt.span();
+            let own_lines = source[line_start(source, span.start)..span.start]
+                .trim()
+                .is_empty()
+                && source[span.end..line_end(source, span.end)]
+                    .trim()
+                    .is_empty();
+            if !own_lines {
+                return Err(RefactorError::Unsupported(
+                    "the statements of the loop body share their lines".to_string(),
+                ));
+            }
+        }
+
+        // Each statement carries the comments and blank lines above it.
+        let inner = line_end(source, body.start);
+        let mut chunks = Vec::new();
+        let mut from = inner;
+        for stmt in statements {
+            let to = line_end(source, stmt.span().end);
+            chunks.push(&source[from..to]);
+            from = to;
+        }
+        let span = stmt.span();
+        let opening = &source[span.start..inner];
+        let closing = &source[from..span.end];
+        let copies: Vec<String> = groups
+            .iter()
+            .map(|group| {
+                let kept: String = group.iter().map(|&i| chunks[i]).collect();
+                format!("{}{}{}", opening, kept, closing)
+            })
+            .collect();
+        let separator = format!("\n{}", line_indent(source, span.start));
+        Ok(vec![TextEdit {
+            start: span.start,
+            end: span.end,
+            replacement: copies.join(&separator),
+        }])
+    }
+}
+
+/// The parts of a loop that every copy repeats.
+struct Header<'a> {
+    initializer: Option<&'a Statement>,
+    /// The condition, update or collection.
+    expressions: Vec<&'a Expression>,
+    body: &'a Block,
+}
+
+impl<'a> Header<'a> {
+    fn of(stmt: &'a Statement) -> Header<'a> {
+        match stmt {
+            Statement::WhileLoop(w) => Header {
+                initializer: None,
+                expressions: vec![&w.condition],
+                body: &w.body,
+            },
+            Statement::ForLoop(f) => Header {
+                initializer: f.initializer.as_deref(),
+                expressions: f.condition.iter().chain(&f.update).map(|e| &**e).collect(),
+                body: &f.body,
+            },
+            Statement::ForEachLoop(f) => Header {
+                initializer: None,
+                expressions: vec![&f.collection],
+                body: &f.body,
+            },
+            _ => unreachable!("not a loop"),
+        }
+    }
+}
+
+/// What one statement of the body uses and changes.
+struct Effects {
+    used: Vec<String>,
+    /// The variables the statement writes or declares.
+    written: Vec<String>,
+    unknown: bool,
+    /// Whether the statement uses a field or anything else that is not a
+    /// local, which unknown effects elsewhere may change.
+    nonlocal: bool,
+}
+
+impl Effects {
+    fn of(stmt: &Statement, bindings: &Bindings) -> Effects {
+        let usages = statement_usages(stmt);
+        let written: Vec<String> = usages
+            .iter()
+            .filter(|usage| usage.access.writes())
+            .map(|usage| usage.name.clone())
+            .chain(
+                statement_declarations(stmt)
+                    .into_iter()
+                    .map(|decl| decl.name.clone()),
+            )
+            .collect();
+        Effects {
+            nonlocal: usages
+                .iter()
+                .any(|usage| bindings.declaration_at(usage.span.start).is_none()),
+            used: usages.into_iter().map(|usage| usage.name).collect(),
+            written,
+            unknown: has_unknown_effects(stmt),
+        }
+    }
+
+    fn depends(&self, other: &Effects) -> bool {
+        let shared = |name: &String| other.used.contains(name);
+        self.written.iter().any(shared) || (self.unknown && self.used.iter().any(shared))
+    }
+}
+
+/// Group the statements so that no two groups share a variable that either
+/// of them writes. Groups are ordered by their first statement.
+fn partition(effects: &[Effects]) -> Vec<Vec<usize>> {
+    let mut group_of: Vec<usize> = (0..effects.len()).collect();
+    for i in 0..effects.len() {
+        for j in 0..i {
+            if effects[i].depends(&effects[j]) || effects[j].depends(&effects[i]) {
+                let (from, to) = (group_of[i], group_of[j]);
+                for group in group_of.iter_mut() {
+                    if *group == from {
+                        *group = to;
+                    }
+                }
+            }
+        }
+    }
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    for (i, &group) in group_of.iter().enumerate() {
+        match groups.iter_mut().find(|g| group_of[g[0]] == group) {
+            Some(g) => g.push(i),
+            None => groups.push(vec![i]),
+        }
+    }
+    groups
+}
@@ -0,0 +1,217 @@
+use crate::analysis::def_use::def_use;
+use crate::analysis::locate::{enclosing_function, function_blocks, nested_blocks};
+use crate::analysis::scope::resolve_function;
+use crate::analysis::variables::{function_declarations, Access};
+use crate::syntax::literal_type;
+use crate::text::slice;
+use crate::{RefactorError, Refactoring, TextEdit};
+use uast::*;
+
+/// Give each assignment of a local variable that is reused for unrelated
+/// values a declaration of its own.
+///
+/// The variable at `offset` keeps its name for its first value. Every later
+/// assignment declares a new variable, named by `names` in order, and the
+/// reads that see the assigned value are renamed with it. A variable that is
+/// updated from its old value, as in `total += x`, holds a single value built
+/// up over time and is not split.
+///
+/// A variable declared with `var` has the type of its first value, so the new
+/// declarations write that type out when it can be read off a literal, and
+/// otherwise keep `var` only for a value created with the same `new` type.
+pub struct SplitVariable {
+    pub offset: usize,
+    pub names: Vec<String>,
+}
+
+impl SplitVariable {
+    pub fn new(offset: usize, names: &[&str]) -> Self {
+        SplitVariable {
+            offset,
+            names: names.iter().map(|name| name.to_string()).collect(),
+        }
+    }
+}
+
+impl Refactoring for SplitVariable {
+    fn apply(&self, uast: &TopLevel, source: &str) -> Result<Vec<TextEdit>, RefactorError> {
+        let at = Span {
+            start: self.offset,
+            end: self.offset,
+        };
+        let func = enclosing_function(uast, &at)
+            .ok_or_else(|| {
+                RefactorError::InvalidSelection("the offset is not inside a method".to_string())
+            })?
+            .function;
+        let bindings = resolve_function(func);
+        let decl = bindings.declaration_at(self.offset).ok_or_else(|| {
+            RefactorError::InvalidSelection("no local variable at the offset".to_string())
+        })?;
+        if func
+            .parameters
+            .iter()
+            .flatten()
+            .any(|param| std::ptr::eq(param, decl))
+        {
+            return Err(RefactorError::Unsupported(format!(
+                "`{}` is a parameter",
+                decl.name
+            )));
+        }
+
+        let chains = def_use(func, decl);
+        if chains.opaque {
+            return Err(RefactorError::Unsupported(format!(
+                "`{}` is used by code that could not be analyzed",
+                decl.name
+            )));
+        }
+        let definitions = &chains.definitions;
+        if definitions.len() < 2 {
+            return Err(RefactorError::Unsupported(format!(
+                "`{}` is assigned only once",
+                decl.name
+            )));
+        }
+        if definitions
+            .iter()
+            .any(|definition| definition.access == Access::ReadWrite)
+        {
+            return Err(RefactorError::Unsupported(format!(
+                "`{}` is updated from its previous value, so it holds a single result",
+                decl.name
+            )));
+        }
+        for definition in definitions {
+            for read in &definition.reads {
+                if chains.reaching(read).len() > 1 {
+                    return Err(RefactorError::Unsupported(format!(
+                        "a read of `{}` may see more than one of its assignments",
+                        decl.name
+                    )));
+                }
+            }
+        }
+        if self.names.len() != definitions.len() - 1 {
+            return Err(RefactorError::InvalidSelection(format!(
+                "`{}` is assigned {} more times, but {} names were given",
+                decl.name,
+                definitions.len() - 1,
+                self.names.len()
+            )));
+        }
+
+        let declared = function_declarations(func);
+        for (i, name) in self.names.iter().enumerate() {
+            let used = declared.iter().any(|other| other.name == *name)
+                || bindings.unresolved.iter().any(|usage| usage.name == *name)
+                || self.names[..i].contains(name)
+                || *name == decl.name;
+            if used {
+                return Err(RefactorError::Conflict(format!(
+                    "`{}` is already used in `{}`",
+                    name, func.name
+                )));
+            }
+        }
+
+        let implicit = decl
+            .var_type
+            .as_deref()
+            .is_none_or(|var_type| var_type == "var");
+        let first = decl.value.as_deref();
+        let var_type = match first {
+            Some(value) if implicit => literal_type(value, source),
+            _ => decl.var_type.clone(),
+        };
+        let mut edits = Vec::new();
+        for (definition, name) in definitions[1..].iter().zip(&self.names) {
+            let (block, assignment) = function_blocks(func)
+                .find_map(|block| assignment_statement(block, &definition.span))
+                .ok_or_else(|| {
+                    RefactorError::Unsupported(format!(
+                        "an assignment of `{}` is not a statement of its own",
+                        decl.name
+                    ))
+                })?;
+            if assignment.operator != AssignmentOperator::Assign {
+                return Err(RefactorError::Unsupported(format!(
+                    "`{}` is updated from its previous value, so it holds a single result",
+                    decl.name
+                )));
+            }
+            let var_type = match &var_type {
+                Some(var_type) => var_type.as_str(),
+                None if first
+                    .and_then(created_type)
+                    .is_some_and(|created| Some(created) == created_type(&assignment.right)) =>
+                {
+                    "var"
+                }
+                None => {
+                    return Err(RefactorError::Unsupported(format!(
+                        "the type of `{}` is implicit, and `{}` may not have it",
+                        decl.name,
+                        slice(source, assignment.right.span())
+                    )))
+                }
+            };
+            // The new variable is only in scope until the end of the block
+            // holding its declaration.
+            if definition
+                .reads
+                .iter()
+                .any(|read| !block.span.contains(read))
+            {
+                return Err(RefactorError::Unsupported(format!(
+                    "`{}` would be read outside the block that declares it",
+                    name
+                )));
+            }
+
+            edits.push(TextEdit {
+                start: definition.span.start,
+                end: definition.span.end,
+                replacement: format!("{} {}", var_type, name),
+            });
+            edits.extend(definition.reads.iter().map(|read| TextEdit {
+                start: read.start,
+                end: read.end,
+                replacement: name.clone(),
+            }));
+        }
+        Ok(edits)
+    }
+}
+
+/// The block holding the statement `x = value;`, or a compound assignment of
+/// `x`, whose `x` is at `span`, and the assignment itself.
+fn assignment_statement<'a>(block: &'a Block, span: &Span) -> Option<(&'a Block, &'a Assignment)> {
+    for stmt in &block.statements {
+        if !stmt.span().contains(span) {
+            continue;
+        }
+        if let Statement::ExpressionStatement(expr) = stmt {
+            if let Expression::Assignment(assign) = expr.expression.as_ref() {
+                if assign.left.span() == span {
+                    return Some((block, assign));
+                }
+            }
+        }
+        return nested_blocks(stmt)
+            .into_iter()
+            .find_map(|nested| assignment_statement(nested, span));
+    }
+    None
+}
+
+/// The type named by `new T(...)`.
+fn created_type(expr: &Expression) -> Option<&str> {
+    let Expression::Raw { source, .. } = expr else {
+        return None;
+    };
+    let created = source.strip_prefix("new ")?;
+    let end = created.find(['(', '{']).unwrap_or(created.len());
+    Some(created[..end].trim())
+}
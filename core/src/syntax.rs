@@ -0,0 +1,449 @@
+//! Operator precedence, used to decide when substituted expressions need
+//! parentheses, and other questions about the shape of expressions.
+
+use crate::analysis::locate::{
+    expression_tree, nested_blocks, statement_expressions, subexpressions,
+};
+use uast::*;
+
+const ASSIGNMENT: u8 = 1;
+const CONDITIONAL: u8 = 2;
+const UNARY: u8 = 14;
+pub(crate) const PRIMARY: u8 = 15;
+
+/// The C# precedence level of an expression; higher binds tighter.
+pub(crate) fn precedence(expr: &Expression) -> u8 {
+    match expr {
+        Expression::Identifier(..)
+        | Expression::Literal(..)
+        | Expression::Invocation(_)
+        | Expression::MemberAccess(_) => PRIMARY,
+        Expression::UnaryOp(op) => match op.operator {
+            UnaryOperator::PostIncrement | UnaryOperator::PostDecrement => PRIMARY,
+            _ => UNARY,
+        },
+        Expression::BinaryOp(op) => binary_precedence(&op.operator),
+        Expression::Assignment(_) => ASSIGNMENT,
+        Expression::Conditional(_) => CONDITIONAL,
+        Expression::Lambda(_) => ASSIGNMENT,
+        // Operators the lowering does not know stay raw, so anything but a
+        // plain name gets the lowest precedence and is always parenthesized.
+        Expression::Raw { source, .. } => {
+            let atomic = source
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_' || c == '.');
+            if atomic {
+                PRIMARY
+            } else {
+                0
+            }
+        }
+    }
+}
+
+pub(crate) fn binary_precedence(operator: &BinaryOperator) -> u8 {
+    match operator {
+        BinaryOperator::Mul | BinaryOperator::Div | BinaryOperator::Mod => 13,
+        BinaryOperator::Add | BinaryOperator::Sub => 12,
+        BinaryOperator::LeftShift | BinaryOperator::RightShift => 11,
+        BinaryOperator::GreaterThan
+        | BinaryOperator::LessThan
+        | BinaryOperator::GreaterThanEqual
+        | BinaryOperator::LessThanEqual => 10,
+        BinaryOperator::Equal | BinaryOperator::NotEqual => 9,
+        BinaryOperator::Logical(LogicalOperator::And) => 8,
+        BinaryOperator::Logical(LogicalOperator::Xor) => 7,
+        BinaryOperator::Logical(LogicalOperator::Or) => 6,
+        BinaryOperator::Logical(LogicalOperator::CondAnd) => 5,
+        BinaryOperator::Logical(LogicalOperator::CondOr) => 4,
+        BinaryOperator::Coalesce => 3,
+    }
+}
+
+/// The precedence an expression must have to replace the sub-expression at
+/// `span` inside `stmt` without parentheses.
+pub(crate) fn required_precedence(stmt: &Statement, span: &Span) -> u8 {
+    let mut found = None;
+    match stmt {
+        Statement::DeclStmt(decl_stmt) => {
+            for var in &decl_stmt.var_decls {
+                if let Some(val) = &var.value {
+                    search(val, span, ASSIGNMENT, &mut found);
+                }
+            }
+        }
+        Statement::IfStatement(if_stmt) => search(&if_stmt.condition, span, 0, &mut found),
+        Statement::ReturnStatement(ret) => {
+            if let Some(val) = &ret.value {
+                search(val, span, 0, &mut found);
+            }
+        }
+        Statement::ExpressionStatement(expr) => search(&expr.expression, span, 0, &mut found),
+        Statement::WhileLoop(w) => search(&w.condition, span, 0, &mut found),
+        Statement::ForEachLoop(f) => search(&f.collection, span, 0, &mut found),
+        Statement::ForLoop(f) => {
+            if let Some(init) = &f.initializer {
+                found = Some(required_precedence(init, span));
+            }
+            if let Some(cond) = &f.condition {
+                search(cond, span, 0, &mut found);
+            }
+            if let Some(update) = &f.update {
+                search(update, span, 0, &mut found);
+            }
+        }
+        Statement::SwitchStatement(switch) => {
+            search(&switch.value, span, 0, &mut found);
+            for section in &switch.sections {
+                for label in &section.labels {
+                    if let SwitchLabel::Case(value) = label {
+                        search(value, span, 0, &mut found);
+                    }
+                }
+            }
+        }
+        Statement::Unknown { .. } => {}
+    }
+    found.unwrap_or(PRIMARY)
+}
+
+/// Like [`required_precedence`], for a sub-expression of a standalone
+/// expression such as a returned value.
+pub(crate) fn required_precedence_in(expr: &Expression, span: &Span) -> u8 {
+    let mut found = None;
+    search(expr, span, 0, &mut found);
+    found.unwrap_or(PRIMARY)
+}
+
+/// Parenthesize `text` unless an expression of precedence `own` can stand
+/// where `required` is needed.
+pub(crate) fn wrap(text: &str, own: u8, required: u8) -> String {
+    if own < required {
+        format!("({})", text)
+    } else {
+        text.to_string()
+    }
+}
+
+fn search(expr: &Expression, span: &Span, required: u8, found: &mut Option<u8>) {
+    if found.is_some() || !expr.span().contains(span) {
+        return;
+    }
+    if expr.span() == span {
+        *found = Some(required);
+        return;
+    }
+    match expr {
+        Expression::BinaryOp(op) => {
+            let own = binary_precedence(&op.operator);
+            search(&op.left, span, own, found);
+            search(&op.right, span, own + 1, found);
+        }
+        Expression::UnaryOp(op) => search(&op.operand, span, UNARY, found),
+        Expression::Assignment(assign) => {
+            search(&assign.left, span, PRIMARY, found);
+            search(&assign.right, span, ASSIGNMENT, found);
+        }
+        Expression::Invocation(inv) => {
+            search(&inv.function, span, PRIMARY, found);
+            for arg in &inv.arguments {
+                search(arg, span, ASSIGNMENT, found);
+            }
+        }
+        Expression::MemberAccess(ma) => search(&ma.expression, span, PRIMARY, found),
+        Expression::Conditional(cond) => {
+            search(&cond.condition, span, CONDITIONAL + 1, found);
+            search(&cond.consequence, span, ASSIGNMENT, found);
+            search(&cond.alternative, span, CONDITIONAL, found);
+        }
+        Expression::Lambda(Lambda {
+            body: LambdaBody::Expression(body),
+            ..
+        }) => search(body, span, ASSIGNMENT, found),
+        _ => {}
+    }
+}
+
+/// Whether two expressions have the same shape, ignoring their positions and
+/// any whitespace inside unlowered source.
+pub(crate) fn same_expression(a: &Expression, b: &Expression) -> bool {
+    match (a, b) {
+        (Expression::Identifier(a, _), Expression::Identifier(b, _)) => a == b,
+        (Expression::Literal(a, _), Expression::Literal(b, _)) => a == b,
+        (Expression::BinaryOp(a), Expression::BinaryOp(b)) => {
+            a.operator == b.operator
+                && same_expression(&a.left, &b.left)
+                && same_expression(&a.right, &b.right)
+        }
+        (Expression::UnaryOp(a), Expression::UnaryOp(b)) => {
+            a.operator == b.operator && same_expression(&a.operand, &b.operand)
+        }
+        (Expression::Assignment(a), Expression::Assignment(b)) => {
+            a.operator == b.operator
+                && same_expression(&a.left, &b.left)
+                && same_expression(&a.right, &b.right)
+        }
+        (Expression::Invocation(a), Expression::Invocation(b)) => {
+            same_expression(&a.function, &b.function)
+                && a.arguments.len() == b.arguments.len()
+                && a.arguments
+                    .iter()
+                    .zip(&b.arguments)
+                    .all(|(a, b)| same_expression(a, b))
+        }
+        (Expression::MemberAccess(a), Expression::MemberAccess(b)) => {
+            a.member == b.member && same_expression(&a.expression, &b.expression)
+        }
+        (Expression::Conditional(a), Expression::Conditional(b)) => {
+            same_expression(&a.condition, &b.condition)
+                && same_expression(&a.consequence, &b.consequence)
+                && same_expression(&a.alternative, &b.alternative)
+        }
+        (Expression::Raw { source: a, .. }, Expression::Raw { source: b, .. }) => {
+            a.split_whitespace().eq(b.split_whitespace())
+        }
+        _ => false,
+    }
+}
+
+/// Whether the sub-expression at `span` inside the expressions of `stmt` may
+/// be skipped or run more than once while the statement runs once: the right
+/// operand of `&&`, `||` and `??`, a branch of `?:`, or the body of a lambda.
+pub(crate) fn evaluated_conditionally(stmt: &Statement, span: &Span) -> bool {
+    statement_expressions(stmt)
+        .into_iter()
+        .any(|(expr, _)| evaluated_conditionally_in(expr, span))
+}
+
+/// Whether the sub-expression at `span` inside `expr` may be skipped or run
+/// more than once while `expr` is evaluated once, as with
+/// [`evaluated_conditionally`].
+pub(crate) fn evaluated_conditionally_in(expr: &Expression, span: &Span) -> bool {
+    if !expr.span().contains(span) {
+        return false;
+    }
+    let conditional: Vec<&Expression> = match expr {
+        Expression::BinaryOp(op)
+            if matches!(
+                op.operator,
+                BinaryOperator::Logical(LogicalOperator::CondAnd | LogicalOperator::CondOr)
+                    | BinaryOperator::Coalesce
+            ) =>
+        {
+            vec![&op.right]
+        }
+        Expression::Conditional(cond) => vec![&cond.consequence, &cond.alternative],
+        Expression::Lambda(_) => return expr.span() != span,
+        _ => vec![],
+    };
+    conditional
+        .iter()
+        .any(|branch| branch.span().contains(span))
+        || subexpressions(expr)
+            .into_iter()
+            .any(|inner| evaluated_conditionally_in(inner, span))
+}
+
+/// The type of a literal, with numeric ones read from their suffix.
+pub(crate) fn literal_type(expr: &Expression, source: &str) -> Option<String> {
+    let Expression::Literal(literal, span) = expr else {
+        return None;
+    };
+    let text = &source[span.start..span.end];
+    let suffix = text
+        .trim_start_matches(|c: char| !c.is_ascii_alphabetic())
+        .to_ascii_lowercase();
+    let var_type = match literal {
+        Literal::Integer(_) if text.len() > 1 && text[..2].eq_ignore_ascii_case("0x") => {
+            return None
+        }
+        Literal::Integer(_) => match suffix.as_str() {
+            "" => "int",
+            "l" => "long",
+            "u" => "uint",
+            "ul" | "lu" => "ulong",
+            _ => return None,
+        },
+        Literal::Float(_) => match suffix.as_str() {
+            "" | "d" => "double",
+            "f" => "float",
+            "m" => "decimal",
+            _ => return None,
+        },
+        Literal::String(_) => "string",
+        Literal::Boolean(_) => "bool",
+        Literal::Char(_) => "char",
+    };
+    Some(var_type.to_string())
+}
+
+/// Whether the code at `span` inside `statements` runs exactly once, before
+/// anything else in them that may have effects: outside loops, branches and
+/// short-circuited operands, with nothing that has side effects evaluated
+/// ahead of it.
+pub(crate) fn evaluated_first(statements: &[Statement], span: &Span) -> bool {
+    for stmt in statements {
+        if !stmt.span().contains(span) {
+            if has_unknown_effects(stmt) {
+                return false;
+            }
+            continue;
+        }
+        let looping = matches!(
+            stmt,
+            Statement::WhileLoop(_) | Statement::ForLoop(_) | Statement::ForEachLoop(_)
+        );
+        let exprs = statement_expressions(stmt);
+        return !looping
+            && exprs.iter().any(|(expr, _)| expr.span().contains(span))
+            && exprs.iter().all(|(expr, _)| evaluated_first_in(expr, span));
+    }
+    false
+}
+
+/// Whether the code at `span` inside `expr` runs exactly once and before
+/// anything else in it that has side effects, as with [`evaluated_first`].
+pub(crate) fn evaluated_first_in(expr: &Expression, span: &Span) -> bool {
+    !evaluated_conditionally_in(expr, span)
+        && expression_tree(expr)
+            .into_iter()
+            .all(|inner| inner.span().end > span.start || !has_side_effects(inner))
+}
+
+/// Whether evaluating `expr` may do more than produce a value, so it must not
+/// be evaluated a different number of times or in a different order.
+pub(crate) fn has_side_effects(expr: &Expression) -> bool {
+    match expr {
+        Expression::Invocation(_) | Expression::Assignment(_) => true,
+        Expression::Raw { source, .. } => !source
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '.'),
+        Expression::UnaryOp(op) => {
+            !matches!(op.operator, UnaryOperator::Negate | UnaryOperator::Not)
+                || has_side_effects(&op.operand)
+        }
+        Expression::BinaryOp(op) => has_side_effects(&op.left) || has_side_effects(&op.right),
+        Expression::MemberAccess(ma) => has_side_effects(&ma.expression),
+        Expression::Conditional(cond) => {
+            has_side_effects(&cond.condition)
+                || has_side_effects(&cond.consequence)
+                || has_side_effects(&cond.alternative)
+        }
+        // Creating a delegate runs none of its body.
+        Expression::Identifier(..) | Expression::Literal(..) | Expression::Lambda(_) => false,
+    }
+}
+
+/// Whether a statement calls methods, assigns anything but a local, returns,
+/// or contains code that was not lowered, any of which may touch state its
+/// variable usages do not show.
+pub(crate) fn has_unknown_effects(stmt: &Statement) -> bool {
+    match stmt {
+        Statement::Unknown { .. } | Statement::ReturnStatement(_) => true,
+        _ => {
+            statement_expressions(stmt)
+                .into_iter()
+                .any(|(expr, _)| has_nonlocal_effects(expr))
+                || nested_blocks(stmt)
+                    .into_iter()
+                    .flat_map(|block| &block.statements)
+                    .any(has_unknown_effects)
+        }
+    }
+}
+
+/// Whether `expr` does more than read variables and assign locals.
+pub(crate) fn has_nonlocal_effects(expr: &Expression) -> bool {
+    let local = |target: &Expression| matches!(target, Expression::Identifier(..));
+    let own = match expr {
+        Expression::Invocation(_) => true,
+        Expression::Raw { .. } => has_side_effects(expr),
+        Expression::Assignment(assign) => !local(&assign.left),
+        Expression::UnaryOp(op) => {
+            !matches!(op.operator, UnaryOperator::Negate | UnaryOperator::Not)
+                && !local(&op.operand)
+        }
+        _ => false,
+    };
+    own || subexpressions(expr).into_iter().any(has_nonlocal_effects)
+}
+
+/// The text of a condition that holds exactly when `condition` does not, to
+/// be used on its own, for example in an `if` header.
+///
+/// Comparisons flip their operator, `!x` loses its `!`, `&&` and `||` are
+/// negated by De Morgan's laws and anything else is negated with `!`, adding
+/// parentheses where precedence requires them.
+pub(crate) fn negate(condition: &Expression, source: &str) -> String {
+    negation(condition, source).0
+}
+
+/// The negation of `condition` together with its precedence.
+fn negation(condition: &Expression, source: &str) -> (String, u8) {
+    let span = condition.span();
+    let text = &source[span.start..span.end];
+    let fallback = || {
+        (
+            format!("!{}", wrap(text, precedence(condition), UNARY)),
+            UNARY,
+        )
+    };
+    match condition {
+        Expression::BinaryOp(op) => {
+            let flipped = match op.operator {
+                BinaryOperator::Equal => ("==", "!="),
+                BinaryOperator::NotEqual => ("!=", "=="),
+                BinaryOperator::GreaterThan => (">", "<="),
+                BinaryOperator::LessThan => ("<", ">="),
+                BinaryOperator::GreaterThanEqual => (">=", "<"),
+                BinaryOperator::LessThanEqual => ("<=", ">"),
+                BinaryOperator::Logical(LogicalOperator::CondAnd) => {
+                    return de_morgan(op, LogicalOperator::CondOr, source)
+                }
+                BinaryOperator::Logical(LogicalOperator::CondOr) => {
+                    return de_morgan(op, LogicalOperator::CondAnd, source)
+                }
+                _ => return fallback(),
+            };
+            let between = &source[op.left.span().end..op.right.span().start];
+            if !between.contains(flipped.0) {
+                return fallback();
+            }
+            let text = format!(
+                "{}{}{}",
+                &source[span.start..op.left.span().end],
+                between.replacen(flipped.0, flipped.1, 1),
+                &source[op.right.span().start..span.end]
+            );
+            (text, binary_precedence(&op.operator))
+        }
+        Expression::UnaryOp(op) if op.operator == UnaryOperator::Not => {
+            let operand = op.operand.span();
+            (
+                source[operand.start..operand.end].to_string(),
+                precedence(&op.operand),
+            )
+        }
+        Expression::Literal(Literal::Boolean(value), _) => ((!value).to_string(), PRIMARY),
+        _ => fallback(),
+    }
+}
+
+/// `!(a && b)` as `!a || !b`, and `!(a || b)` as `!a && !b`.
+fn de_morgan(op: &BinaryOp, operator: LogicalOperator, source: &str) -> (String, u8) {
+    let (symbol, own) = match operator {
+        LogicalOperator::CondAnd => ("&&", binary_precedence(&BinaryOperator::Logical(operator))),
+        _ => ("||", binary_precedence(&BinaryOperator::Logical(operator))),
+    };
+    let (left, left_precedence) = negation(&op.left, source);
+    let (right, right_precedence) = negation(&op.right, source);
+    // Both operators are left-associative, so only the right operand needs
+    // parentheses at the same precedence.
+    let text = format!(
+        "{} {} {}",
+        wrap(&left, left_precedence, own),
+        symbol,
+        wrap(&right, right_precedence, own + 1)
+    );
+    (text, own)
+}
@@ -0,0 +1,451 @@
+//! Helpers for generating source text that blends in with the surrounding code.
+
+use crate::TextEdit;
+use uast::{ClassDef, FunctionDef, Span, TopLevel};
+
+/// The byte offset of the start of the line containing `offset`.
+pub(crate) fn line_start(source: &str, offset: usize) -> usize {
+    source[..offset].rfind('\n').map_or(0, |i| i + 1)
+}
+
+/// The offset just past the newline ending the line containing `offset`.
+pub(crate) fn line_end(source: &str, offset: usize) -> usize {
+    source[offset..]
+        .find('\n')
+        .map_or(source.len(), |i| offset + i + 1)
+}
+
+/// The leading whitespace of the line containing `offset`.
+pub(crate) fn line_indent(source: &str, offset: usize) -> &str {
+    let start = line_start(source, offset);
+    let line = &source[start..];
+    let len = line.len() - line.trim_start_matches([' ', '\t']).len();
+    &line[..len]
+}
+
+/// The source text covered by `span`.
+pub(crate) fn slice<'a>(source: &'a str, span: &Span) -> &'a str {
+    &source[span.start..span.end]
+}
+
+/// Move a snippet from one indentation level to another.
+///
+/// The first line of `text` is assumed to start right after its indentation,
+/// as is the case for any span that begins at a statement.
+pub(crate) fn reindent(text: &str, from: &str, to: &str) -> String {
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| {
+            if i == 0 {
+                format!("{}{}", to, line)
+            } else if line.trim().is_empty() {
+                String::new()
+            } else {
+                format!(
+                    "{}{}",
+                    to,
+                    line.strip_prefix(from).unwrap_or(line.trim_start())
+                )
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Whether `source` already declares a type called `name`.
+pub(crate) fn declares_type(source: &str, name: &str) -> bool {
+    ["class", "struct", "interface", "record", "enum"]
+        .iter()
+        .any(|keyword| {
+            source.match_indices(keyword).any(|(i, _)| {
+                source[i + keyword.len()..]
+                    .strip_prefix(' ')
+                    .and_then(|rest| rest.trim_start().strip_prefix(name))
+                    .is_some_and(|rest| {
+                        !rest.starts_with(|c: char| c.is_alphanumeric() || c == '_')
+                    })
+            })
+        })
+}
+
+/// Whether `class` declares a constructor. Constructors chaining to another
+/// one are not lowered, so this looks for a line starting with the class
+/// name and a `(`.
+pub(crate) fn declares_constructor(source: &str, class: &ClassDef) -> bool {
+    slice(source, &class.span).lines().any(|line| {
+        let mut rest = line.trim_start();
+        while let Some((word, after)) = rest.split_once(' ') {
+            if !matches!(
+                word,
+                "public" | "protected" | "internal" | "private" | "static"
+            ) {
+                break;
+            }
+            rest = after.trim_start();
+        }
+        rest.strip_prefix(class.name.as_str())
+            .is_some_and(|after| after.trim_start().starts_with('('))
+    })
+}
+
+/// Layout of the methods in a class, taken from an existing method.
+pub(crate) struct MethodStyle {
+    /// Indentation of the method declaration.
+    pub member_indent: String,
+    /// Indentation of the statements in the method body.
+    pub body_indent: String,
+    /// Whether the opening brace goes on its own line.
+    pub allman: bool,
+}
+
+impl MethodStyle {
+    pub fn of(source: &str, func: &FunctionDef) -> MethodStyle {
+        let member_indent = line_indent(source, func.span.start).to_string();
+        let block = crate::analysis::locate::function_blocks(func).next();
+
+        let body_indent = block
+            .and_then(|block| block.statements.first())
+            .map(|stmt| line_indent(source, stmt.span().start).to_string())
+            .filter(|indent| indent.len() > member_indent.len())
+            .unwrap_or_else(|| format!("{}    ", member_indent));
+
+        let allman = block.is_some_and(|block| {
+            source[line_start(source, block.span.start)..block.span.start]
+                .trim()
+                .is_empty()
+        });
+
+        MethodStyle {
+            member_indent,
+            body_indent,
+            allman,
+        }
+    }
+
+    /// The layout for a new member placed next to the one at `span`, with the
+    /// brace style and body indentation of the first method of `class`.
+    pub fn beside(source: &str, class: &ClassDef, span: &Span) -> MethodStyle {
+        let member_indent = line_indent(source, span.start).to_string();
+        let method = class.body.iter().flatten().find_map(|item| match item {
+            TopLevel::Function(func) => Some(func),
+            _ => None,
+        });
+        let (unit, allman) = method.map_or(("    ".to_string(), false), |func| {
+            let style = MethodStyle::of(source, func);
+            let unit = style.body_indent[style.member_indent.len()..].to_string();
+            (unit, style.allman)
+        });
+
+        MethodStyle {
+            body_indent: format!("{}{}", member_indent, unit),
+            member_indent,
+            allman,
+        }
+    }
+
+    /// Render a method with the given header and body. `body` is expected to be
+    /// indented at `body_indent` already.
+    pub fn render(&self, header: &str, body: &str) -> String {
+        let open = if self.allman {
+            format!("\n{}{{", self.member_indent)
+        } else {
+            " {".to_string()
+        };
+        format!(
+            "{indent}{header}{open}\n{body}\n{indent}}}",
+            indent = self.member_indent,
+        )
+    }
+}
+
+/// The text of `region` with `edits` applied. Edits must lie inside `region`.
+pub(crate) fn splice(source: &str, region: &Span, edits: &[TextEdit]) -> String {
+    let mut edits: Vec<&TextEdit> = edits.iter().collect();
+    edits.sort_by_key(|edit| edit.start);
+
+    let mut result = String::new();
+    let mut cursor = region.start;
+    for edit in edits {
+        result.push_str(&source[cursor..edit.start]);
+        result.push_str(&edit.replacement);
+        cursor = edit.end;
+    }
+    result.push_str(&source[cursor..region.end]);
+    result
+}
+
+/// The span to delete in order to remove the construct at `span` along with
+/// the rest of its line, when nothing else shares that line. A blank line left
+/// in front of the construct is removed too, so members do not end up
+/// separated by two empty lines.
+pub(crate) fn removal_span(source: &str, span: &Span) -> Span {
+    let start = line_start(source, span.start);
+    if !source[start..span.start].trim().is_empty() {
+        return span.clone();
+    }
+
+    let rest = &source[span.end..];
+    let end = match rest.find('\n') {
+        Some(i) if rest[..i].trim().is_empty() => span.end + i + 1,
+        None if rest.trim().is_empty() => source.len(),
+        _ => return span.clone(),
+    };
+
+    let mut start = start;
+    if start > 0 {
+        let previous = line_start(source, start - 1);
+        if source[previous..start].trim().is_empty() {
+            start = previous;
+        }
+    }
+
+    Span { start, end }
+}
+
+/// Widen `span` over any parentheses that only group the expression it
+/// covers. Parentheses belonging to a call or to an `if`/`while`/`for` header
+/// are left alone.
+pub(crate) fn grouping_parens(source: &str, span: &Span) -> Span {
+    let mut span = span.clone();
+    loop {
+        let before = source[..span.start].trim_end();
+        let after = source[span.end..].trim_start();
+        if !before.ends_with('(') || !after.starts_with(')') {
+            return span;
+        }
+
+        let open = before.len() - 1;
+        let preceding = source[..open].trim_end();
+        let token_start = preceding
+            .rfind(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .map_or(0, |i| i + 1);
+        let token = &preceding[token_start..];
+        let is_call =
+            preceding.ends_with([')', ']', '>']) || (!token.is_empty() && token != "return");
+        if is_call {
+            return span;
+        }
+
+        span = Span {
+            start: open,
+            end: source.len() - after.len() + 1,
+        };
+    }
+}
+
+/// An edit replacing the items of a parenthesized list with `text`. An empty
+/// list is located by the first `(` after `after`.
+pub(crate) fn replace_list(source: &str, items: Vec<Span>, after: usize, text: String) -> TextEdit {
+    match (items.first(), items.last()) {
+        (Some(first), Some(last)) => TextEdit {
+            start: first.start,
+            end: last.end,
+            replacement: text,
+        },
+        _ => {
+            let open = after + source[after..].find('(').map_or(0, |i| i + 1);
+            TextEdit {
+                start: open,
+                end: open,
+                replacement: text,
+            }
+        }
+    }
+}
+
+/// Widen the span of an argument's value over parentheses around it and a
+/// `ref`, `out` or `name:` written before it.
+pub(crate) fn argument_span(source: &str, value: &Span) -> Span {
+    let value = grouping_parens(source, value);
+    let separator = source[..value.start]
+        .rfind(['(', ','])
+        .map_or(value.start, |i| i + 1);
+    let prefix = &source[separator..value.start];
+    Span {
+        start: value.start - prefix.trim_start().len(),
+        end: value.end,
+    }
+}
+
+/// The edit adding `using namespace;` to a file that does not have it yet,
+/// after the last `using` directive at the top of the file or else at the
+/// very start.
+pub(crate) fn add_using(source: &str, namespace: &str) -> Option<TextEdit> {
+    let directive = format!("using {};", namespace);
+    let mut at = None;
+    let mut offset = 0;
+    for line in source.split_inclusive('\n') {
+        let trimmed = line.trim();
+        if trimmed == directive {
+            return None;
+        }
+        if trimmed.starts_with("using ") && trimmed.ends_with(';') {
+            at = Some(offset + line.len());
+        } else if !trimmed.is_empty() && !trimmed.starts_with("//") {
+            break;
+        }
+        offset += line.len();
+    }
+    Some(match at {
+        Some(at) => TextEdit {
+            start: at,
+            end: at,
+            replacement: format!("{}\n", directive),
+        },
+        None => TextEdit {
+            start: 0,
+            end: 0,
+            replacement: format!("{}\n\n", directive),
+        },
+    })
+}
+
+/// Give the private members of `class` called one of `names` the `access`
+/// modifier instead, so that code outside the class can reach them. The
+/// method `skip` is left alone.
+pub(crate) fn widen_access(
+    class: &ClassDef,
+    skip: Option<&FunctionDef>,
+    names: &[&str],
+    access: &str,
+    source: &str,
+) -> Vec<TextEdit> {
+    let mut declarations: Vec<(&str, &Option<Vec<String>>, &Span, &Span)> = Vec::new();
+    for item in class.body.iter().flatten() {
+        match item {
+            TopLevel::Function(other) if !skip.is_some_and(|func| std::ptr::eq(other, func)) => {
+                declarations.push((&other.name, &other.modifiers, &other.span, &other.name_span))
+            }
+            TopLevel::Field(field) => {
+                if let Some(var) = field.var_decls.first() {
+                    declarations.push((&var.name, &field.modifiers, &field.span, &var.name_span));
+                }
+            }
+            TopLevel::Property(property) => declarations.push((
+                &property.name,
+                &property.modifiers,
+                &property.span,
+                &property.name_span,
+            )),
+            _ => {}
+        }
+    }
+
+    let mut edits = Vec::new();
+    for (name, modifiers, span, name_span) in declarations {
+        let modifiers = modifiers.as_deref().unwrap_or_default();
+        let private = !modifiers
+            .iter()
+            .any(|modifier| matches!(modifier.as_str(), "public" | "protected" | "internal"));
+        if !private || !names.contains(&name) {
+            continue;
+        }
+        let edit = match source[span.start..name_span.start].find("private") {
+            Some(i) => TextEdit {
+                start: span.start + i,
+                end: span.start + i + "private".len(),
+                replacement: access.to_string(),
+            },
+            None => TextEdit {
+                start: span.start,
+                end: span.start,
+                replacement: format!("{} ", access),
+            },
+        };
+        edits.push(edit);
+    }
+    edits
+}
+
+/// The start of the `///` documentation lines directly above the member
+/// starting at `offset`, or `offset` itself when there are none.
+pub(crate) fn doc_comment_start(source: &str, offset: usize) -> usize {
+    let mut start = offset;
+    loop {
+        let line = line_start(source, start);
+        if line == 0 {
+            return start;
+        }
+        let previous = line_start(source, line - 1);
+        let text = &source[previous..line];
+        if !text.trim_start().starts_with("///") {
+            return start;
+        }
+        start = previous + text.len() - text.trim_start().len();
+    }
+}
+
+/// Where a new member goes at the end of a class, after its last member or
+/// in place of the whitespace of an empty body.
+pub(crate) struct MemberSlot {
+    span: Span,
+    separator: &'static str,
+    closing: String,
+    /// Indentation of the members of the class.
+    pub indent: String,
+}
+
+impl MemberSlot {
+    pub fn of(source: &str, class: &ClassDef) -> MemberSlot {
+        let class_indent = line_indent(source, class.span.start);
+        let last = class.body.iter().flatten().last().map(|item| match item {
+            TopLevel::Function(func) => func.span.clone(),
+            TopLevel::Field(field) => field.span.clone(),
+            TopLevel::Property(property) => property.span.clone(),
+            TopLevel::Unknown { span, .. } => span.clone(),
+            _ => class.span.clone(),
+        });
+        match last {
+            Some(span) if span != class.span => MemberSlot {
+                indent: line_indent(source, span.start).to_string(),
+                span: Span {
+                    start: span.end,
+                    end: span.end,
+                },
+                separator: "\n\n",
+                closing: String::new(),
+            },
+            _ => {
+                let open = source[class.span.start..class.span.end]
+                    .find('{')
+                    .map_or(class.span.start, |i| class.span.start + i + 1);
+                MemberSlot {
+                    indent: format!("{}    ", class_indent),
+                    span: Span {
+                        start: open,
+                        end: class.span.end - 1,
+                    },
+                    separator: "\n",
+                    closing: format!("\n{}", class_indent),
+                }
+            }
+        }
+    }
+
+    /// The edit adding `members`, which must already be indented.
+    pub fn insert(&self, members: &str) -> TextEdit {
+        TextEdit {
+            start: self.span.start,
+            end: self.span.end,
+            replacement: format!("{}{}{}", self.separator, members, self.closing),
+        }
+    }
+}
+
+/// The member name written right after `offset`, as in `.Name`.
+pub(crate) fn member_after(source: &str, offset: usize) -> Option<&str> {
+    let rest = source[offset..].strip_prefix('.')?;
+    let len = rest
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(rest.len());
+    (len > 0).then(|| &rest[..len])
+}
+
+/// `name` with its first letter in lower case, as for a field holding an
+/// instance of the class `name`.
+pub(crate) fn lower_first(name: &str) -> String {
+    let mut chars = name.chars();
+    chars.next().map_or_else(String::new, |first| {
+        first.to_lowercase().chain(chars).collect()
+    })
+}
@@ -52,6 +52,106 @@ fn extract_parameters(node: Node, source: &[u8]) -> Option<Vec<VarDecl>> {
     Some(parameters)
 }
 
+/// Lower the declarators of a `variable_declaration` node.
+///
+/// # Arguments
+/// * `node`   - The `variable_declaration` node.
+/// * `span`   - The span recorded on every declarator, usually the enclosing statement.
+/// * `source` - The original source code.
+fn lower_variable_declaration(node: Node, span: &Span, source: &[u8]) -> Vec<VarDecl> {
+    let var_type = node
+        .named_child(0)
+        .unwrap()
+        .utf8_text(source)
+        .unwrap()
+        .to_string();
+
+    let mut cursor = node.walk();
+    node.children(&mut cursor)
+        .filter(|child| child.kind() == "variable_declarator")
+        .map(|var| {
+            let name_node = var.named_child(0).unwrap();
+            let name = name_node.utf8_text(source).unwrap().to_string();
+
+            let value: Option<Box<Expression>> = var
+                .named_child(1)
+                .filter(|child| child.kind() == "equals_value_clause")
+                .and_then(|clause| {
+                    clause
+                        .named_child(0)
+                        .map(|literal_node| Box::new(lower_expressions(literal_node, source)))
+                });
+
+            VarDecl {
+                span: span.clone(),
+                modifiers: None,
+                var_type: Some(var_type.clone()),
+                name,
+                name_span: Span {
+                    start: name_node.start_byte(),
+                    end: name_node.end_byte(),
+                },
+                value,
+            }
+        })
+        .collect()
+}
+
+/// Lower a property accessor, or the arrow clause of an expression-bodied
+/// property, to a function named after its keyword.
+fn lower_accessor(node: Node, source: &[u8]) -> FunctionDef {
+    let (keyword, arrow) = if node.kind() == "arrow_expression_clause" {
+        (node.child(0).unwrap(), Some(node))
+    } else {
+        let mut cursor = node.walk();
+        let children: Vec<Node> = node.children(&mut cursor).collect();
+        let keyword = *children
+            .iter()
+            .find(|child| matches!(child.kind(), "get" | "set" | "init" | "add" | "remove"))
+            .expect("unable to find accessor keyword");
+        let arrow = children
+            .into_iter()
+            .find(|child| child.kind() == "arrow_expression_clause");
+        (keyword, arrow)
+    };
+
+    let body = if let Some(arrow) = arrow {
+        arrow.named_child(0).map(|expr| {
+            vec![FunctionBodyItems::Expression(lower_expressions(
+                expr, source,
+            ))]
+        })
+    } else {
+        let mut cursor = node.walk();
+        let block = node
+            .children(&mut cursor)
+            .find(|child| child.kind() == "block");
+        block.map(|block| vec![FunctionBodyItems::Block(lower_block(block, source))])
+    };
+
+    FunctionDef {
+        name: if arrow == Some(node) {
+            "get".to_string()
+        } else {
+            keyword.utf8_text(source).unwrap().to_string()
+        },
+        name_span: Span {
+            start: keyword.start_byte(),
+            end: keyword.end_byte(),
+        },
+        span: Span {
+            start: node.start_byte(),
+            end: node.end_byte(),
+        },
+        body,
+        modifiers: extract_modifiers(node, source),
+        parameters: None,
+        return_type: None,
+        annotations: None,
+        metadata: None,
+    }
+}
+
 pub fn lower_statement(node: Node, source: &[u8]) -> Statement {
     match node.kind() {
         "global_statement" => {
@@ -77,49 +177,15 @@ pub fn lower_statement(node: Node, source: &[u8]) -> Statement {
                 .find(|child| child.kind() == "variable_declaration")
                 .expect("Expected to find variable declaration inside local declaration");
 
-            let var_type = variable_declaration_node
-                .named_child(0)
-                .unwrap()
-                .utf8_text(source)
-                .unwrap()
-                .to_string();
-
-            let variable_declarators: Vec<VarDecl> = variable_declaration_node
-                .children(&mut cursor)
-                .filter(|child| child.kind() == "variable_declarator")
-                .map(|var| {
-                    let name_node = var.named_child(0).unwrap();
-                    let name = name_node.utf8_text(source).unwrap().to_string();
-
-                    let value: Option<Box<Expression>> = var
-                        .named_child(1)
-                        .filter(|child| child.kind() == "equals_value_clause")
-                        .and_then(|clause| {
-                            clause.named_child(0).map(|literal_node| {
-                                Box::new(lower_expressions(literal_node, source))
-                            })
-                        });
-
-                    VarDecl {
-                        span: Span {
-                            start: node.start_byte(),
-                            end: node.end_byte(),
-                        },
-                        modifiers: None,
-                        var_type: Some(var_type.clone()),
-                        name,
-                        name_span: Span {
-                            start: name_node.start_byte(),
-                            end: name_node.end_byte(),
-                        },
-                        value,
-                    }
-                })
-                .collect();
+            let span = Span {
+                start: node.start_byte(),
+                end: node.end_byte(),
+            };
 
             Statement::DeclStmt(DeclStmt {
                 modifiers,
-                var_decls: variable_declarators,
+                var_decls: lower_variable_declaration(variable_declaration_node, &span, source),
+                span,
             })
         }
         "if_statement" => {
@@ -155,7 +221,148 @@ pub fn lower_statement(node: Node, source: &[u8]) -> Statement {
             if let Some(child) = node.named_child(0) {
                 value = Some(Box::new(lower_expressions(child, source)));
             }
-            Statement::ReturnStatement(ReturnStatement { value })
+            Statement::ReturnStatement(ReturnStatement {
+                value,
+                span: Span {
+                    start: node.start_byte(),
+                    end: node.end_byte(),
+                },
+            })
+        }
+        "while_statement" => {
+            let condition_node = node
+                .named_child(0)
+                .expect("Expected a condition for while_statement");
+            let body_node = node
+                .named_child(1)
+                .expect("Expected a body for while_statement");
+
+            Statement::WhileLoop(WhileLoop {
+                condition: Box::new(lower_expressions(condition_node, source)),
+                body: Box::new(lower_block(body_node, source)),
+                span: Span {
+                    start: node.start_byte(),
+                    end: node.end_byte(),
+                },
+            })
+        }
+        "for_each_statement" => {
+            let span = Span {
+                start: node.start_byte(),
+                end: node.end_byte(),
+            };
+            let (Some(type_node), Some(name_node), Some(collection), Some(body)) = (
+                node.child_by_field_name("type"),
+                node.child_by_field_name("left"),
+                node.child_by_field_name("right"),
+                node.child_by_field_name("body"),
+            ) else {
+                return Statement::Unknown {
+                    source: node.utf8_text(source).unwrap_or("").to_string(),
+                    span,
+                };
+            };
+            // Deconstruction, as in `foreach (var (key, value) in pairs)`, is
+            // not lowered.
+            if name_node.kind() != "identifier" {
+                return Statement::Unknown {
+                    source: node.utf8_text(source).unwrap_or("").to_string(),
+                    span,
+                };
+            }
+
+            Statement::ForEachLoop(ForEachLoop {
+                variable: VarDecl {
+                    span: Span {
+                        start: type_node.start_byte(),
+                        end: name_node.end_byte(),
+                    },
+                    modifiers: None,
+                    var_type: Some(type_node.utf8_text(source).unwrap().to_string()),
+                    name: name_node.utf8_text(source).unwrap().to_string(),
+                    name_span: Span {
+                        start: name_node.start_byte(),
+                        end: name_node.end_byte(),
+                    },
+                    value: None,
+                },
+                collection: Box::new(lower_expressions(collection, source)),
+                body: Box::new(lower_block(body, source)),
+                span,
+            })
+        }
+        "for_statement" => {
+            let span = Span {
+                start: node.start_byte(),
+                end: node.end_byte(),
+            };
+
+            let initializer = node.child_by_field_name("initializer").map(|init| {
+                Box::new(if init.kind() == "variable_declaration" {
+                    let init_span = Span {
+                        start: init.start_byte(),
+                        end: init.end_byte(),
+                    };
+                    Statement::DeclStmt(DeclStmt {
+                        modifiers: None,
+                        var_decls: lower_variable_declaration(init, &init_span, source),
+                        span: init_span,
+                    })
+                } else {
+                    Statement::ExpressionStatement(ExpressionStatement {
+                        expression: Box::new(lower_expressions(init, source)),
+                        span: Span {
+                            start: init.start_byte(),
+                            end: init.end_byte(),
+                        },
+                    })
+                })
+            });
+
+            let condition = node
+                .child_by_field_name("condition")
+                .map(|cond| Box::new(lower_expressions(cond, source)));
+            let update = node
+                .child_by_field_name("update")
+                .map(|update| Box::new(lower_expressions(update, source)));
+
+            let body_node = node
+                .child_by_field_name("body")
+                .expect("Expected a body for for_statement");
+
+            Statement::ForLoop(ForLoop {
+                initializer,
+                condition,
+                update,
+                body: Box::new(lower_block(body_node, source)),
+                span,
+            })
+        }
+        "switch_statement" => {
+            let value_node = node
+                .named_child(0)
+                .expect("Expected a value for switch_statement");
+            let mut cursor = node.walk();
+            let body_node = node
+                .named_children(&mut cursor)
+                .find(|child| child.kind() == "switch_body")
+                .expect("Expected a body for switch_statement");
+
+            let mut cursor = body_node.walk();
+            let sections = body_node
+                .named_children(&mut cursor)
+                .filter(|child| child.kind() == "switch_section")
+                .map(|section| lower_switch_section(section, source))
+                .collect();
+
+            Statement::SwitchStatement(SwitchStatement {
+                value: Box::new(lower_expressions(value_node, source)),
+                sections,
+                span: Span {
+                    start: node.start_byte(),
+                    end: node.end_byte(),
+                },
+            })
         }
         "expression_statement" => {
             let expr_node = node
@@ -180,15 +387,93 @@ pub fn lower_statement(node: Node, source: &[u8]) -> Statement {
     }
 }
 
+fn lower_switch_section(node: Node, source: &[u8]) -> SwitchSection {
+    let mut labels = Vec::new();
+    let mut statements = Vec::new();
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        let span = Span {
+            start: child.start_byte(),
+            end: child.end_byte(),
+        };
+        match child.kind() {
+            "case_switch_label" => {
+                let value = child
+                    .named_child(0)
+                    .expect("Expected a value for case label");
+                labels.push(SwitchLabel::Case(lower_expressions(value, source)));
+            }
+            "case_pattern_switch_label" => {
+                // Everything between `case` and `:`, including a `when` clause.
+                let first = child
+                    .named_child(0)
+                    .expect("Expected a pattern for case label");
+                let last = child
+                    .named_child(child.named_child_count() - 1)
+                    .unwrap_or(first);
+                let span = Span {
+                    start: first.start_byte(),
+                    end: last.end_byte(),
+                };
+                labels.push(SwitchLabel::Case(Expression::Raw {
+                    source: String::from_utf8_lossy(&source[span.start..span.end]).to_string(),
+                    span,
+                }));
+            }
+            "default_switch_label" => labels.push(SwitchLabel::Default(span)),
+            _ => statements.push(lower_statement(child, source)),
+        }
+    }
+
+    let body_span = match (statements.first(), statements.last()) {
+        (Some(first), Some(last)) => Span {
+            start: first.span().start,
+            end: last.span().end,
+        },
+        _ => Span {
+            start: node.end_byte(),
+            end: node.end_byte(),
+        },
+    };
+    SwitchSection {
+        labels,
+        body: Block {
+            statements,
+            span: body_span,
+        },
+        span: Span {
+            start: node.start_byte(),
+            end: node.end_byte(),
+        },
+    }
+}
+
 pub fn lower_block(node: Node, source: &[u8]) -> Block {
     let mut statements: Vec<Statement> = Vec::new();
     let block_start_byte = node.start_byte();
     let block_end_byte = node.end_byte();
 
+    // Bodies such as `while (x) x--;` or `else if (...)` are a single statement
+    // rather than a braced block, so wrap them in a block of their own.
+    if node.kind() != "block" {
+        return Block {
+            statements: vec![lower_statement(node, source)],
+            span: Span {
+                start: block_start_byte,
+                end: block_end_byte,
+            },
+        };
+    }
+
     // Iterate over named children of the block node
-    // Assuming a block node contains statements as its named children
+    // Assuming a block node contains statements as its named children.
+    // Comments are named too, but they are not statements; edits that move
+    // code keep them through the source text.
     for i in 0..node.named_child_count() {
         if let Some(child_node) = node.named_child(i) {
+            if child_node.kind() == "comment" {
+                continue;
+            }
             statements.push(lower_statement(child_node, source));
         }
     }
@@ -207,12 +492,24 @@ pub fn lower_expressions(node: Node, source: &[u8]) -> Expression {
         "integer_literal" => {
             let text = node.utf8_text(source).unwrap();
             let value = text.parse::<i64>().unwrap_or(0);
-            Expression::Literal(Literal::Integer(value))
+            Expression::Literal(
+                Literal::Integer(value),
+                Span {
+                    start: node.start_byte(),
+                    end: node.end_byte(),
+                },
+            )
         }
         "real_literal" => {
             let text = node.utf8_text(source).unwrap();
             let value = text.parse::<f64>().unwrap_or(0.0);
-            Expression::Literal(Literal::Float(value))
+            Expression::Literal(
+                Literal::Float(value),
+                Span {
+                    start: node.start_byte(),
+                    end: node.end_byte(),
+                },
+            )
         }
         "string_literal" => {
             let text = node.utf8_text(source).unwrap();
@@ -221,12 +518,24 @@ pub fn lower_expressions(node: Node, source: &[u8]) -> Expression {
             } else {
                 text
             };
-            Expression::Literal(Literal::String(content.to_string()))
+            Expression::Literal(
+                Literal::String(content.to_string()),
+                Span {
+                    start: node.start_byte(),
+                    end: node.end_byte(),
+                },
+            )
         }
         "boolean_literal" => {
             let text = node.utf8_text(source).unwrap();
             let val = text == "true";
-            Expression::Literal(Literal::Boolean(val))
+            Expression::Literal(
+                Literal::Boolean(val),
+                Span {
+                    start: node.start_byte(),
+                    end: node.end_byte(),
+                },
+            )
         }
         "identifier" => {
             let text = node.utf8_text(source).unwrap();
@@ -255,19 +564,41 @@ pub fn lower_expressions(node: Node, source: &[u8]) -> Expression {
                 "-" => BinaryOperator::Sub,
                 "*" => BinaryOperator::Mul,
                 "/" => BinaryOperator::Div,
+                "%" => BinaryOperator::Mod,
+                "<<" => BinaryOperator::LeftShift,
+                ">>" => BinaryOperator::RightShift,
+                "??" => BinaryOperator::Coalesce,
                 "==" => BinaryOperator::Equal,
                 "!=" => BinaryOperator::NotEqual,
                 ">" => BinaryOperator::GreaterThan,
                 "<" => BinaryOperator::LessThan,
                 ">=" => BinaryOperator::GreaterThanEqual,
                 "<=" => BinaryOperator::LessThanEqual,
-                _ => BinaryOperator::Add, // Default/Fallback
+                "&&" => BinaryOperator::Logical(LogicalOperator::CondAnd),
+                "||" => BinaryOperator::Logical(LogicalOperator::CondOr),
+                "&" => BinaryOperator::Logical(LogicalOperator::And),
+                "|" => BinaryOperator::Logical(LogicalOperator::Or),
+                "^" => BinaryOperator::Logical(LogicalOperator::Xor),
+                // Anything else stays opaque rather than passing for another operator.
+                _ => {
+                    return Expression::Raw {
+                        source: node.utf8_text(source).unwrap_or("").to_string(),
+                        span: Span {
+                            start: node.start_byte(),
+                            end: node.end_byte(),
+                        },
+                    }
+                }
             };
 
             Expression::BinaryOp(BinaryOp {
                 left: Box::new(lower_expressions(left_node, source)),
                 operator,
                 right: Box::new(lower_expressions(right_node, source)),
+                span: Span {
+                    start: node.start_byte(),
+                    end: node.end_byte(),
+                },
             })
         }
         "assignment_expression" => {
@@ -282,12 +613,10 @@ pub fn lower_expressions(node: Node, source: &[u8]) -> Expression {
                 .child_by_field_name("operator")
                 .or_else(|| {
                     let mut cursor = node.walk();
-                    for child in node.children(&mut cursor) {
-                        if child.id() != left_node.id() && child.id() != right_node.id() {
-                            return Some(child);
-                        }
-                    }
-                    None
+                    let operator = node.children(&mut cursor).find(|child| {
+                        child.id() != left_node.id() && child.id() != right_node.id()
+                    });
+                    operator
                 })
                 .expect("Assignment missing operator");
 
@@ -298,13 +627,34 @@ pub fn lower_expressions(node: Node, source: &[u8]) -> Expression {
                 "-=" => AssignmentOperator::SubAssign,
                 "*=" => AssignmentOperator::MulAssign,
                 "/=" => AssignmentOperator::DivAssign,
-                _ => AssignmentOperator::Assign,
+                "%=" => AssignmentOperator::ModAssign,
+                "&=" => AssignmentOperator::AndAssign,
+                "|=" => AssignmentOperator::OrAssign,
+                "^=" => AssignmentOperator::XorAssign,
+                "<<=" => AssignmentOperator::LeftShiftAssign,
+                ">>=" => AssignmentOperator::RightShiftAssign,
+                ">>>=" => AssignmentOperator::UnsignedRightShiftAssign,
+                "??=" => AssignmentOperator::CoalesceAssign,
+                // Anything else stays opaque rather than passing for a plain `=`.
+                _ => {
+                    return Expression::Raw {
+                        source: node.utf8_text(source).unwrap_or("").to_string(),
+                        span: Span {
+                            start: node.start_byte(),
+                            end: node.end_byte(),
+                        },
+                    }
+                }
             };
 
             Expression::Assignment(Assignment {
                 left: Box::new(lower_expressions(left_node, source)),
                 operator,
                 right: Box::new(lower_expressions(right_node, source)),
+                span: Span {
+                    start: node.start_byte(),
+                    end: node.end_byte(),
+                },
             })
         }
         "invocation_expression" => {
@@ -315,7 +665,10 @@ pub fn lower_expressions(node: Node, source: &[u8]) -> Expression {
             let mut cursor = arguments_node.walk();
             for child in arguments_node.children(&mut cursor) {
                 if child.kind() == "argument" {
-                    if let Some(expr_node) = child.named_child(0) {
+                    // Skip the `name:` of a named argument to reach its value.
+                    let count = child.named_child_count();
+                    if let Some(expr_node) = count.checked_sub(1).and_then(|i| child.named_child(i))
+                    {
                         arguments.push(lower_expressions(expr_node, source));
                     }
                 }
@@ -324,6 +677,10 @@ pub fn lower_expressions(node: Node, source: &[u8]) -> Expression {
             Expression::Invocation(Invocation {
                 function: Box::new(lower_expressions(function_node, source)),
                 arguments,
+                span: Span {
+                    start: node.start_byte(),
+                    end: node.end_byte(),
+                },
             })
         }
         "member_access_expression" => {
@@ -337,6 +694,116 @@ pub fn lower_expressions(node: Node, source: &[u8]) -> Expression {
                     start: name_node.start_byte(),
                     end: name_node.end_byte(),
                 },
+                span: Span {
+                    start: node.start_byte(),
+                    end: node.end_byte(),
+                },
+            })
+        }
+        "prefix_unary_expression" | "postfix_unary_expression" => {
+            let is_prefix = node.kind() == "prefix_unary_expression";
+            let operand_node = node
+                .named_child(0)
+                .expect("Unary expression missing operand");
+
+            let mut cursor = node.walk();
+            let op_text = node
+                .children(&mut cursor)
+                .find(|child| !child.is_named())
+                .and_then(|child| child.utf8_text(source).ok())
+                .unwrap_or("");
+
+            let operator = match (op_text, is_prefix) {
+                ("++", true) => Some(UnaryOperator::PreIncrement),
+                ("++", false) => Some(UnaryOperator::PostIncrement),
+                ("--", true) => Some(UnaryOperator::PreDecrement),
+                ("--", false) => Some(UnaryOperator::PostDecrement),
+                ("!", true) => Some(UnaryOperator::Not),
+                ("-", true) => Some(UnaryOperator::Negate),
+                _ => None,
+            };
+
+            match operator {
+                Some(operator) => Expression::UnaryOp(UnaryOp {
+                    operator,
+                    operand: Box::new(lower_expressions(operand_node, source)),
+                    span: Span {
+                        start: node.start_byte(),
+                        end: node.end_byte(),
+                    },
+                }),
+                None => Expression::Raw {
+                    source: node.utf8_text(source).unwrap_or("").to_string(),
+                    span: Span {
+                        start: node.start_byte(),
+                        end: node.end_byte(),
+                    },
+                },
+            }
+        }
+        "conditional_expression" => {
+            let field = |name: &str| {
+                lower_expressions(
+                    node.child_by_field_name(name)
+                        .expect("Conditional expr missing operand"),
+                    source,
+                )
+            };
+            Expression::Conditional(Conditional {
+                condition: Box::new(field("condition")),
+                consequence: Box::new(field("consequence")),
+                alternative: Box::new(field("alternative")),
+                span: Span {
+                    start: node.start_byte(),
+                    end: node.end_byte(),
+                },
+            })
+        }
+        "lambda_expression" => {
+            let span = Span {
+                start: node.start_byte(),
+                end: node.end_byte(),
+            };
+            // A single parameter written without parentheses carries no field
+            // name.
+            let parameters = node.child_by_field_name("parameters").or_else(|| {
+                node.named_child(0)
+                    .filter(|child| child.kind() == "identifier")
+            });
+            let (Some(parameters), Some(body)) = (parameters, node.child_by_field_name("body"))
+            else {
+                return Expression::Raw {
+                    source: node.utf8_text(source).unwrap_or("").to_string(),
+                    span,
+                };
+            };
+            let parameters = if parameters.kind() == "identifier" {
+                vec![VarDecl {
+                    span: Span {
+                        start: parameters.start_byte(),
+                        end: parameters.end_byte(),
+                    },
+                    modifiers: None,
+                    var_type: None,
+                    name: parameters.utf8_text(source).unwrap().to_string(),
+                    name_span: Span {
+                        start: parameters.start_byte(),
+                        end: parameters.end_byte(),
+                    },
+                    value: None,
+                }]
+            } else {
+                lower_lambda_parameters(parameters, source)
+            };
+            let body = if body.kind() == "block" {
+                LambdaBody::Block(lower_block(body, source))
+            } else {
+                LambdaBody::Expression(Box::new(lower_expressions(body, source)))
+            };
+            Expression::Lambda(Lambda {
+                parameters,
+                body,
+                span,
             })
         }
         "parenthesized_expression" => {
@@ -353,6 +820,64 @@ pub fn lower_expressions(node: Node, source: &[u8]) -> Expression {
     }
 }
 
+/// Lower the `parameter_list` of a lambda, whose parameters may leave out
+/// their types as in `(x, y) => x + y`.
+fn lower_lambda_parameters(node: Node, source: &[u8]) -> Vec<VarDecl> {
+    let mut cursor = node.walk();
+    node.children(&mut cursor)
+        .filter(|child| child.kind() == "parameter")
+        .filter_map(|child| {
+            let name_node = child.child_by_field_name("name")?;
+            let var_type = child
+                .child_by_field_name("type")
+                .map(|type_node| type_node.utf8_text(source).unwrap().to_string());
+            Some(VarDecl {
+                span: Span {
+                    start: child.start_byte(),
+                    end: child.end_byte(),
+                },
+                modifiers: None,
+                var_type,
+                name: name_node.utf8_text(source).unwrap().to_string(),
+                name_span: Span {
+                    start: name_node.start_byte(),
+                    end: name_node.end_byte(),
+                },
+                value: None,
+            })
+        })
+        .collect()
+}
+
+/// Lower every class declared in a file, including those inside namespaces,
+/// into a single module.
+pub fn lower_compilation_unit(node: Node, source: &[u8]) -> TopLevel {
+    let mut body = Vec::new();
+    collect_classes(node, source, &mut body);
+    TopLevel::Module(ModuleDef {
+        name: String::new(),
+        body,
+        span: Span {
+            start: node.start_byte(),
+            end: node.end_byte(),
+        },
+        metadata: Metadata::new(),
+    })
+}
+
+fn collect_classes(node: Node, source: &[u8], body: &mut Vec<TopLevel>) {
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        match child.kind() {
+            "class_declaration" => body.push(lower_top_level(child, source)),
+            "namespace_declaration" | "file_scoped_namespace_declaration" | "declaration_list" => {
+                collect_classes(child, source, body)
+            }
+            _ => {}
+        }
+    }
+}
+
 pub fn lower_top_level(node: Node, source: &[u8]) -> TopLevel {
     match node.kind() {
         "class_declaration" => {
@@ -368,7 +893,7 @@ pub fn lower_top_level(node: Node, source: &[u8]) -> TopLevel {
             if let Some(body_node) = node.child_by_field_name("body") {
                 let mut cursor = body_node.walk();
                 for child in body_node.children(&mut cursor) {
-                    if child.kind() == "method_declaration" {
+                    if child.kind().ends_with("_declaration") {
                         top_levels.push(lower_top_level(child, source));
                     }
                 }
@@ -384,18 +909,16 @@ pub fn lower_top_level(node: Node, source: &[u8]) -> TopLevel {
                 },
 
                 body: Some(top_levels),
-                modifiers: modifiers,
+                modifiers,
                 annotations: None,
                 metadata: None,
             })
         }
         "method_declaration" => {
-            let name = node
+            let name_node = node
                 .child_by_field_name("name")
-                .expect("unable to find method name")
-                .utf8_text(source)
-                .unwrap()
-                .to_string();
+                .expect("unable to find method name");
+            let name = name_node.utf8_text(source).unwrap().to_string();
 
             let return_type_node = node
                 .child_by_field_name("type")
@@ -413,6 +936,10 @@ pub fn lower_top_level(node: Node, source: &[u8]) -> TopLevel {
 
             TopLevel::Function(FunctionDef {
                 name,
+                name_span: Span {
+                    start: name_node.start_byte(),
+                    end: name_node.end_byte(),
+                },
                 span: Span {
                     start: node.start_byte(),
                     end: node.end_byte(),
@@ -425,6 +952,102 @@ pub fn lower_top_level(node: Node, source: &[u8]) -> TopLevel {
                 metadata: None,
             })
         }
+        // A constructor is a function named after its class, without a return
+        // type. One chaining to `base(...)` or `this(...)` is left unlowered,
+        // since the call in its header is not part of the body.
+        "constructor_declaration" if !has_child(node, "constructor_initializer") => {
+            let name_node = node
+                .child_by_field_name("name")
+                .expect("unable to find constructor name");
+
+            let mut body_parts: Vec<FunctionBodyItems> = vec![];
+            if let Some(body_node) = node.child_by_field_name("body") {
+                body_parts.push(FunctionBodyItems::Block(lower_block(body_node, source)));
+            }
+
+            TopLevel::Function(FunctionDef {
+                name: name_node.utf8_text(source).unwrap().to_string(),
+                name_span: Span {
+                    start: name_node.start_byte(),
+                    end: name_node.end_byte(),
+                },
+                span: Span {
+                    start: node.start_byte(),
+                    end: node.end_byte(),
+                },
+                body: Some(body_parts),
+                modifiers: extract_modifiers(node, source),
+                parameters: extract_parameters(node, source),
+                return_type: None,
+                annotations: None,
+                metadata: None,
+            })
+        }
+        "field_declaration" => {
+            let span = Span {
+                start: node.start_byte(),
+                end: node.end_byte(),
+            };
+            let mut cursor = node.walk();
+            let var_decls = node
+                .children(&mut cursor)
+                .find(|child| child.kind() == "variable_declaration")
+                .map(|declaration| lower_variable_declaration(declaration, &span, source))
+                .unwrap_or_default();
+
+            TopLevel::Field(FieldDef {
+                span,
+                modifiers: extract_modifiers(node, source),
+                var_decls,
+            })
+        }
+        "property_declaration" => {
+            let name_node = node
+                .child_by_field_name("name")
+                .expect("unable to find property name");
+            let var_type = node
+                .child_by_field_name("type")
+                .map(|type_node| type_node.utf8_text(source).unwrap().to_string());
+
+            let mut accessors = Vec::new();
+            let mut value = None;
+            let mut cursor = node.walk();
+            let children: Vec<Node> = node.children(&mut cursor).collect();
+            for (i, child) in children.iter().enumerate() {
+                match child.kind() {
+                    "accessor_list" => {
+                        let mut cursor = child.walk();
+                        for accessor in child.children(&mut cursor) {
+                            if accessor.kind() == "accessor_declaration" {
+                                accessors.push(lower_accessor(accessor, source));
+                            }
+                        }
+                    }
+                    // `int Twice => count * 2;` is a getter without the braces.
+                    "arrow_expression_clause" => accessors.push(lower_accessor(*child, source)),
+                    _ if i > 0 && children[i - 1].kind() == "=" => {
+                        value = Some(Box::new(lower_expressions(*child, source)));
+                    }
+                    _ => {}
+                }
+            }
+
+            TopLevel::Property(PropertyDef {
+                name: name_node.utf8_text(source).unwrap().to_string(),
+                name_span: Span {
+                    start: name_node.start_byte(),
+                    end: name_node.end_byte(),
+                },
+                span: Span {
+                    start: node.start_byte(),
+                    end: node.end_byte(),
+                },
+                modifiers: extract_modifiers(node, source),
+                var_type,
+                accessors,
+                value,
+            })
+        }
         _ => TopLevel::Unknown {
             source: node.utf8_text(source).unwrap_or("").to_string(),
             span: Span {
@@ -434,3 +1057,9 @@ pub fn lower_top_level(node: Node, source: &[u8]) -> TopLevel {
         },
     }
 }
+
+fn has_child(node: Node, kind: &str) -> bool {
+    let mut cursor = node.walk();
+    let found = node.children(&mut cursor).any(|child| child.kind() == kind);
+    found
+}
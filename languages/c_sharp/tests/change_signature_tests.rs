@@ -0,0 +1,142 @@
+mod common;
+
+use common::refactor;
+use core::{ChangeSignature, ParameterSpec, RefactorError};
+
+fn added(declaration: &str, default: &str) -> ParameterSpec {
+    ParameterSpec::Added {
+        declaration: declaration.to_string(),
+        default: default.to_string(),
+    }
+}
+
+#[test]
+fn test_add_parameter_with_default_argument() {
+    let source = r#"public class Report {
+    public void Print(string title) {
+        Console.WriteLine(title);
+    }
+
+    public void Run() {
+        Print("Summary");
+        this.Print("Details");
+    }
+}"#;
+    let expected = r#"public class Report {
+    public void Print(string title, int copies) {
+        Console.WriteLine(title);
+    }
+
+    public void Run() {
+        Print("Summary", 1);
+        this.Print("Details", 1);
+    }
+}"#;
+    let refactoring = ChangeSignature::new(
+        "Print",
+        vec![ParameterSpec::Existing(0), added("int copies", "1")],
+    );
+    let result = refactor(source, &refactoring).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_remove_unused_parameter_and_reorder() {
+    let source = r#"public class Shop {
+    public int Price(int amount, bool unused, int unit) {
+        return amount * unit;
+    }
+
+    public int Run(Shop other) {
+        return Price(3, true, 10) + other.Price(1, false, (2 + 3));
+    }
+}"#;
+    let expected = r#"public class Shop {
+    public int Price(int unit, int amount) {
+        return amount * unit;
+    }
+
+    public int Run(Shop other) {
+        return Price(10, 3) + other.Price((2 + 3), 1);
+    }
+}"#;
+    let refactoring = ChangeSignature::new(
+        "Price",
+        vec![ParameterSpec::Existing(2), ParameterSpec::Existing(0)],
+    );
+    let result = refactor(source, &refactoring).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_add_first_parameter_to_empty_list() {
+    let source = r#"public class Clock {
+    public void Tick() {
+    }
+
+    public void Run() {
+        Tick();
+    }
+}"#;
+    let expected = r#"public class Clock {
+    public void Tick(int steps) {
+    }
+
+    public void Run() {
+        Tick(1);
+    }
+}"#;
+    let refactoring = ChangeSignature::new("Tick", vec![added("int steps", "1")]);
+    let result = refactor(source, &refactoring).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_refuses_removing_used_parameter() {
+    let source = r#"public class Shop {
+    public int Price(int amount, int unit) {
+        return amount * unit;
+    }
+}"#;
+    let refactoring = ChangeSignature::new("Price", vec![ParameterSpec::Existing(0)]);
+    let result = refactor(source, &refactoring);
+    assert!(matches!(result, Err(RefactorError::Unsupported(_))));
+}
+
+#[test]
+fn test_refuses_reordering_arguments_with_side_effects() {
+    let source = r#"public class Shop {
+    public int Price(int amount, int unit) {
+        return amount * unit;
+    }
+
+    public int Run() {
+        return Price(Next(), Next());
+    }
+}"#;
+    let refactoring = ChangeSignature::new(
+        "Price",
+        vec![ParameterSpec::Existing(1), ParameterSpec::Existing(0)],
+    );
+    let result = refactor(source, &refactoring);
+    assert!(matches!(result, Err(RefactorError::Unsupported(_))));
+}
+
+#[test]
+fn test_refuses_named_arguments() {
+    let source = r#"public class Shop {
+    public int Price(int amount, int unit) {
+        return amount * unit;
+    }
+
+    public int Run() {
+        return Price(unit: 2, amount: 3);
+    }
+}"#;
+    let refactoring = ChangeSignature::new(
+        "Price",
+        vec![ParameterSpec::Existing(1), ParameterSpec::Existing(0)],
+    );
+    let result = refactor(source, &refactoring);
+    assert!(matches!(result, Err(RefactorError::Unsupported(_))));
+}
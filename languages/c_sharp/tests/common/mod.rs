@@ -0,0 +1,90 @@
+#![allow(dead_code)]
+
+use c_sharp::{lower_compilation_unit, lower_top_level};
+use core::{apply_refactoring, RefactorError, Refactoring, SourceFile, WorkspaceRefactoring};
+use tree_sitter::Parser;
+use uast::TopLevel;
+
+/// Lower the first class declaration of `source_code`.
+pub fn lower_class(source_code: &str) -> TopLevel {
+    let mut parser = Parser::new();
+    parser
+        .set_language(tree_sitter_c_sharp::language())
+        .expect("Error loading C# grammar");
+    let tree = parser.parse(source_code, None).unwrap();
+    let root = tree.root_node();
+
+    let mut cursor = root.walk();
+    let class_node = root
+        .children(&mut cursor)
+        .find(|n| n.kind() == "class_declaration")
+        .expect("No class declaration found in test source");
+
+    lower_top_level(class_node, source_code.as_bytes())
+}
+
+/// Run a refactoring over the first class of `source_code` and return the new source.
+pub fn refactor(source_code: &str, refactoring: &dyn Refactoring) -> Result<String, RefactorError> {
+    let uast = lower_class(source_code);
+    let edits = refactoring.apply(&uast, source_code)?;
+    Ok(apply_refactoring(source_code, edits))
+}
+
+/// Run a refactoring over every class of `source_code` and return the new source.
+pub fn refactor_file(
+    source_code: &str,
+    refactoring: &dyn Refactoring,
+) -> Result<String, RefactorError> {
+    let mut parser = Parser::new();
+    parser
+        .set_language(tree_sitter_c_sharp::language())
+        .expect("Error loading C# grammar");
+    let tree = parser.parse(source_code, None).unwrap();
+    let uast = lower_compilation_unit(tree.root_node(), source_code.as_bytes());
+    let edits = refactoring.apply(&uast, source_code)?;
+    Ok(apply_refactoring(source_code, edits))
+}
+
+/// Run a refactoring over several files, given as `(path, source)` pairs, and
+/// return the new source of each file in the same order.
+pub fn refactor_files(
+    files: &[(&str, &str)],
+    refactoring: &dyn WorkspaceRefactoring,
+) -> Result<Vec<String>, RefactorError> {
+    let mut parser = Parser::new();
+    parser
+        .set_language(tree_sitter_c_sharp::language())
+        .expect("Error loading C# grammar");
+    let uasts: Vec<TopLevel> = files
+        .iter()
+        .map(|(_, source_code)| {
+            let tree = parser.parse(source_code, None).unwrap();
+            lower_compilation_unit(tree.root_node(), source_code.as_bytes())
+        })
+        .collect();
+    let sources: Vec<SourceFile> = files
+        .iter()
+        .zip(&uasts)
+        .map(|((path, source), uast)| SourceFile { path, uast, source })
+        .collect();
+    let mut edits = refactoring.apply_workspace(&sources)?;
+    Ok(files
+        .iter()
+        .map(|(path, source_code)| {
+            let file_edits = edits
+                .iter_mut()
+                .find(|file| file.path == *path)
+                .map(|file| std::mem::take(&mut file.edits))
+                .unwrap_or_default();
+            apply_refactoring(source_code, file_edits)
+        })
+        .collect())
+}
+
+/// The byte range of the first occurrence of `needle` in `source_code`.
+pub fn span_of(source_code: &str, needle: &str) -> (usize, usize) {
+    let start = source_code
+        .find(needle)
+        .unwrap_or_else(|| panic!("`{}` not found in test source", needle));
+    (start, start + needle.len())
+}
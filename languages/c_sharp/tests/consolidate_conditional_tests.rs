@@ -0,0 +1,155 @@
+mod common;
+
+use common::{refactor, span_of};
+use core::{ConsolidateConditional, RefactorError};
+
+#[test]
+fn test_join_sequence_with_or() {
+    let source = r#"public class Disability {
+    public int Amount(int seniority, int months, bool partTime) {
+        if (seniority < 2) {
+            return 0;
+        }
+        if (months > 12) {
+            return 0;
+        }
+        if (partTime) {
+            return 0;
+        }
+        return 100;
+    }
+}"#;
+    let expected = r#"public class Disability {
+    public int Amount(int seniority, int months, bool partTime) {
+        if (seniority < 2 || months > 12 || partTime) {
+            return 0;
+        }
+        return 100;
+    }
+}"#;
+    let start = span_of(source, "if (seniority").0;
+    let end = span_of(source, "return 100").0;
+    let refactoring = ConsolidateConditional::new(start, end, None);
+    let result = refactor(source, &refactoring).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_join_nested_with_and() {
+    let source = r#"public class Bonus {
+    public void Grant(bool onVacation, int lengthOfService, bool eligible) {
+        if (onVacation) {
+            if (lengthOfService > 10 || eligible) {
+                Console.WriteLine(1);
+            }
+        }
+    }
+}"#;
+    let expected = r#"public class Bonus {
+    public void Grant(bool onVacation, int lengthOfService, bool eligible) {
+        if (onVacation && (lengthOfService > 10 || eligible)) {
+            Console.WriteLine(1);
+        }
+    }
+}"#;
+    let (start, end) = span_of(
+        source,
+        r#"if (onVacation) {
+            if (lengthOfService > 10 || eligible) {
+                Console.WriteLine(1);
+            }
+        }"#,
+    );
+    let refactoring = ConsolidateConditional::new(start, end, None);
+    let result = refactor(source, &refactoring).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_extract_combined_condition() {
+    let source = r#"public class Disability {
+    public int Amount(int seniority, int months) {
+        if (seniority < 2) return 0;
+        if (months > 12) return 0;
+        return 100;
+    }
+}"#;
+    let expected = r#"public class Disability {
+    public int Amount(int seniority, int months) {
+        if (IsNotEligible(seniority, months)) return 0;
+        return 100;
+    }
+
+    private bool IsNotEligible(int seniority, int months) {
+        return seniority < 2 || months > 12;
+    }
+}"#;
+    let (start, end) = span_of(
+        source,
+        "if (seniority < 2) return 0;\n        if (months > 12) return 0;",
+    );
+    let refactoring = ConsolidateConditional::new(start, end, Some("IsNotEligible"));
+    let result = refactor(source, &refactoring).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_refuse_bodies_that_fall_through() {
+    let source = r#"public class Counter {
+    public int Count(int a, int b) {
+        int n = 0;
+        if (a > 0) {
+            n++;
+        }
+        if (b > 0) {
+            n++;
+        }
+        return n;
+    }
+}"#;
+    let start = span_of(source, "if (a").0;
+    let end = span_of(source, "return n").0;
+    let refactoring = ConsolidateConditional::new(start, end, None);
+    let result = refactor(source, &refactoring);
+    assert!(matches!(result, Err(RefactorError::Unsupported(_))));
+}
+
+#[test]
+fn test_refuse_different_bodies() {
+    let source = r#"public class Counter {
+    public int Count(int a, int b) {
+        if (a > 0) {
+            return 1;
+        }
+        if (b > 0) {
+            return 2;
+        }
+        return 0;
+    }
+}"#;
+    let start = span_of(source, "if (a").0;
+    let end = span_of(source, "return 0").0;
+    let refactoring = ConsolidateConditional::new(start, end, None);
+    let result = refactor(source, &refactoring);
+    assert!(matches!(result, Err(RefactorError::Unsupported(_))));
+}
+
+#[test]
+fn test_refuse_existing_field_name() {
+    let source = r#"public class Disability {
+    private bool IsNotEligible;
+
+    public int Amount(int seniority, int months) {
+        if (seniority < 2) return 0;
+        if (months > 12) return 0;
+        return 100;
+    }
+}"#;
+    let (start, end) = span_of(
+        source,
+        "if (seniority < 2) return 0;\n        if (months > 12) return 0;",
+    );
+    let refactoring = ConsolidateConditional::new(start, end, Some("IsNotEligible"));
+    let result = refactor(source, &refactoring);
+    assert!(matches!(result, Err(RefactorError::Conflict(_))));
+}
@@ -0,0 +1,138 @@
+mod common;
+
+use common::{refactor, span_of};
+use core::{DecomposeConditional, RefactorError};
+
+#[test]
+fn test_decompose_condition_and_both_branches() {
+    let source = r#"public class Billing {
+    public double Charge(int day, int quantity, double rate) {
+        double charge;
+        if (day >= 100 && day <= 200) {
+            charge = quantity * rate;
+        } else {
+            charge = quantity * rate + 10;
+        }
+        return charge;
+    }
+}"#;
+    let expected = r#"public class Billing {
+    public double Charge(int day, int quantity, double rate) {
+        double charge;
+        if (IsSummer(day)) {
+            charge = SummerCharge(quantity, rate);
+        } else {
+            charge = RegularCharge(quantity, rate);
+        }
+        return charge;
+    }
+
+    private bool IsSummer(int day) {
+        return day >= 100 && day <= 200;
+    }
+
+    private double SummerCharge(int quantity, double rate) {
+        double charge;
+        charge = quantity * rate;
+        return charge;
+    }
+
+    private double RegularCharge(int quantity, double rate) {
+        double charge;
+        charge = quantity * rate + 10;
+        return charge;
+    }
+}"#;
+    let (offset, _) = span_of(source, "if (day");
+    let refactoring =
+        DecomposeConditional::new(offset, "IsSummer", "SummerCharge", Some("RegularCharge"));
+    let result = refactor(source, &refactoring).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_keep_else_branch_without_name() {
+    let source = r#"public class Alarm {
+    public void Check(int level) {
+        if (level > 3) {
+            Console.WriteLine("high");
+            Console.WriteLine(level);
+        } else {
+            Console.WriteLine("ok");
+        }
+    }
+}"#;
+    let expected = r#"public class Alarm {
+    public void Check(int level) {
+        if (IsHigh(level)) {
+            Warn(level);
+        } else {
+            Console.WriteLine("ok");
+        }
+    }
+
+    private bool IsHigh(int level) {
+        return level > 3;
+    }
+
+    private void Warn(int level) {
+        Console.WriteLine("high");
+        Console.WriteLine(level);
+    }
+}"#;
+    let (offset, _) = span_of(source, "\"high\"");
+    let refactoring = DecomposeConditional::new(offset, "IsHigh", "Warn", None);
+    let result = refactor(source, &refactoring).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_refuse_branch_with_return() {
+    let source = r#"public class Alarm {
+    public int Check(int level) {
+        if (level > 3) {
+            return 1;
+        }
+        return 0;
+    }
+}"#;
+    let (offset, _) = span_of(source, "if");
+    let refactoring = DecomposeConditional::new(offset, "IsHigh", "Warn", None);
+    let result = refactor(source, &refactoring);
+    assert!(matches!(result, Err(RefactorError::Unsupported(_))));
+}
+
+#[test]
+fn test_refuse_existing_method_name() {
+    let source = r#"public class Alarm {
+    public void Check(int level) {
+        if (level > 3) {
+            Console.WriteLine("high");
+        }
+    }
+
+    private void Warn() {
+    }
+}"#;
+    let (offset, _) = span_of(source, "if");
+    let refactoring = DecomposeConditional::new(offset, "IsHigh", "Warn", None);
+    let result = refactor(source, &refactoring);
+    assert!(matches!(result, Err(RefactorError::Conflict(_))));
+}
+
+#[test]
+fn test_refuse_existing_property_name() {
+    let source = r#"public class Alarm {
+    public bool IsHigh { get; set; }
+
+    public void Check(int level) {
+        if (level > 3) {
+            Console.WriteLine("high");
+        }
+    }
+}"#;
+    let (offset, _) = span_of(source, "if");
+    let refactoring = DecomposeConditional::new(offset, "IsHigh", "Warn", None);
+    let result = refactor(source, &refactoring);
+    assert!(matches!(result, Err(RefactorError::Conflict(_))));
+}
@@ -0,0 +1,122 @@
+mod common;
+
+use common::{refactor, refactor_file};
+use core::{EncapsulateField, RefactorError};
+
+#[test]
+fn test_encapsulate_public_field() {
+    let source = r#"public class Order {
+    public decimal total = 0;
+
+    public void Add(decimal amount) {
+        total = total + amount;
+    }
+
+    public void Merge(Order other) {
+        this.total = this.total + other.total;
+        other.total = 0;
+    }
+}"#;
+    let expected = r#"public class Order {
+    private decimal total = 0;
+    public decimal Total {
+        get { return total; }
+        set { total = value; }
+    }
+
+    public void Add(decimal amount) {
+        total = total + amount;
+    }
+
+    public void Merge(Order other) {
+        this.total = this.total + other.Total;
+        other.Total = 0;
+    }
+}"#;
+    let result = refactor(source, &EncapsulateField::new("total", "Total")).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_encapsulate_static_readonly_field_in_allman_class() {
+    let source = r#"public class Config
+{
+    public static readonly int limit = 10;
+
+    public int Twice()
+    {
+        return limit * 2;
+    }
+}"#;
+    let expected = r#"public class Config
+{
+    private static readonly int limit = 10;
+    public static int Limit
+    {
+        get { return limit; }
+    }
+
+    public int Twice()
+    {
+        return limit * 2;
+    }
+}"#;
+    let result = refactor(source, &EncapsulateField::new("limit", "Limit")).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_encapsulate_refuses_field_passed_by_reference() {
+    let source = r#"public class Counter {
+    public int count;
+
+    public void Bump(Counter other) {
+        Interlocked.Increment(ref other.count);
+    }
+}"#;
+    let result = refactor(source, &EncapsulateField::new("count", "Count"));
+    assert!(matches!(result, Err(RefactorError::Unsupported(_))));
+}
+
+#[test]
+fn test_encapsulate_refuses_existing_member_name() {
+    let source = r#"public class Counter {
+    public int count;
+
+    public int Count() {
+        return count;
+    }
+}"#;
+    let result = refactor(source, &EncapsulateField::new("count", "Count"));
+    assert!(matches!(result, Err(RefactorError::Conflict(_))));
+}
+
+#[test]
+fn test_encapsulate_updates_accesses_from_other_classes() {
+    let source = r#"public class Order {
+    public decimal total = 0;
+}
+
+public class Checkout {
+    public decimal Charge(Order order) {
+        order.total = order.total * 2;
+        return order.total;
+    }
+}"#;
+    let expected = r#"public class Order {
+    private decimal total = 0;
+    public decimal Total {
+        get { return total; }
+        set { total = value; }
+    }
+}
+
+public class Checkout {
+    public decimal Charge(Order order) {
+        order.Total = order.Total * 2;
+        return order.Total;
+    }
+}"#;
+    let result = refactor_file(source, &EncapsulateField::new("total", "Total")).unwrap();
+    assert_eq!(result, expected);
+}
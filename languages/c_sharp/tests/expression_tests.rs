@@ -1,6 +1,9 @@
 use c_sharp::lower_expressions;
 use parser::GenericParser;
-use uast::{BinaryOperator, Expression, Literal};
+use uast::{
+    AssignmentOperator, BinaryOp, BinaryOperator, Expression, LambdaBody, Literal, LogicalOperator,
+    UnaryOperator,
+};
 
 fn print_tree(node: tree_sitter::Node, source: &str, depth: usize) {
     let indent = "  ".repeat(depth);
@@ -45,7 +48,7 @@ fn test_lower_integer_literal() {
 
     let result = lower_expressions(literal_node, code.as_bytes());
 
-    if let Expression::Literal(Literal::Integer(val)) = result {
+    if let Expression::Literal(Literal::Integer(val), _) = result {
         assert_eq!(val, 123);
     } else {
         panic!("Expected Integer Literal, got {:?}", result);
@@ -84,13 +87,13 @@ fn test_lower_binary_expression() {
     if let Expression::BinaryOp(bin_op) = result {
         assert_eq!(bin_op.operator, BinaryOperator::Add);
 
-        if let Expression::Literal(Literal::Integer(left_val)) = *bin_op.left {
+        if let Expression::Literal(Literal::Integer(left_val), _) = *bin_op.left {
             assert_eq!(left_val, 1);
         } else {
             panic!("Expected left operand to be 1");
         }
 
-        if let Expression::Literal(Literal::Integer(right_val)) = *bin_op.right {
+        if let Expression::Literal(Literal::Integer(right_val), _) = *bin_op.right {
             assert_eq!(right_val, 2);
         } else {
             panic!("Expected right operand to be 2");
@@ -129,7 +132,7 @@ fn test_lower_string_literal() {
 
     let result = lower_expressions(literal_node, code.as_bytes());
 
-    if let Expression::Literal(Literal::String(val)) = result {
+    if let Expression::Literal(Literal::String(val), _) = result {
         assert_eq!(val, "Hello World");
     } else {
         panic!("Expected String Literal, got {:?}", result);
@@ -193,3 +196,259 @@ fn test_lower_invocation_expression() {
         panic!("Expected Invocation, got {:?}", result);
     }
 }
+
+#[test]
+fn test_lower_prefix_unary_expression() {
+    let language = tree_sitter_c_sharp::language();
+    let mut parser = GenericParser::new(language);
+    let code = "!done";
+    let tree = parser.parse(code);
+    let root = tree.root_node();
+
+    fn find_node<'a>(node: tree_sitter::Node<'a>, kind: &str) -> Option<tree_sitter::Node<'a>> {
+        if node.kind() == kind {
+            return Some(node);
+        }
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            if let Some(found) = find_node(child, kind) {
+                return Some(found);
+            }
+        }
+        None
+    }
+
+    let unary_node = find_node(root, "prefix_unary_expression")
+        .expect("Could not find prefix_unary_expression in the tree");
+
+    let result = lower_expressions(unary_node, code.as_bytes());
+
+    if let Expression::UnaryOp(op) = result {
+        assert_eq!(op.operator, UnaryOperator::Not);
+        if let Expression::Identifier(name, _) = *op.operand {
+            assert_eq!(name, "done");
+        } else {
+            panic!("Expected Identifier operand, got {:?}", op.operand);
+        }
+    } else {
+        panic!("Expected UnaryOp, got {:?}", result);
+    }
+}
+
+#[test]
+fn test_lower_conditional_logical_expression() {
+    let language = tree_sitter_c_sharp::language();
+    let mut parser = GenericParser::new(language);
+    let code = "a && b || c";
+    let tree = parser.parse(code);
+    let root = tree.root_node();
+
+    fn find_node<'a>(node: tree_sitter::Node<'a>, kind: &str) -> Option<tree_sitter::Node<'a>> {
+        if node.kind() == kind {
+            return Some(node);
+        }
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            if let Some(found) = find_node(child, kind) {
+                return Some(found);
+            }
+        }
+        None
+    }
+
+    let binary_node =
+        find_node(root, "binary_expression").expect("Could not find binary_expression in the tree");
+
+    let result = lower_expressions(binary_node, code.as_bytes());
+
+    if let Expression::BinaryOp(or) = result {
+        assert_eq!(
+            or.operator,
+            BinaryOperator::Logical(LogicalOperator::CondOr)
+        );
+        if let Expression::BinaryOp(and) = *or.left {
+            assert_eq!(
+                and.operator,
+                BinaryOperator::Logical(LogicalOperator::CondAnd)
+            );
+        } else {
+            panic!("Expected `a && b` on the left, got {:?}", or.left);
+        }
+    } else {
+        panic!("Expected BinaryOp, got {:?}", result);
+    }
+}
+
+#[test]
+fn test_lower_non_conditional_logical_expression() {
+    fn find_node<'a>(node: tree_sitter::Node<'a>, kind: &str) -> Option<tree_sitter::Node<'a>> {
+        if node.kind() == kind {
+            return Some(node);
+        }
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            if let Some(found) = find_node(child, kind) {
+                return Some(found);
+            }
+        }
+        None
+    }
+
+    for (code, operator) in [
+        ("a & b", LogicalOperator::And),
+        ("a | b", LogicalOperator::Or),
+        ("a ^ b", LogicalOperator::Xor),
+    ] {
+        let language = tree_sitter_c_sharp::language();
+        let mut parser = GenericParser::new(language);
+        let tree = parser.parse(code);
+        let binary_node = find_node(tree.root_node(), "binary_expression")
+            .expect("Could not find binary_expression in the tree");
+
+        let result = lower_expressions(binary_node, code.as_bytes());
+
+        if let Expression::BinaryOp(op) = result {
+            assert_eq!(op.operator, BinaryOperator::Logical(operator));
+        } else {
+            panic!("Expected BinaryOp, got {:?}", result);
+        }
+    }
+}
+
+#[test]
+fn test_lower_compound_assignment() {
+    fn find_node<'a>(node: tree_sitter::Node<'a>, kind: &str) -> Option<tree_sitter::Node<'a>> {
+        if node.kind() == kind {
+            return Some(node);
+        }
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            if let Some(found) = find_node(child, kind) {
+                return Some(found);
+            }
+        }
+        None
+    }
+
+    for (code, operator) in [
+        ("x %= 7;", AssignmentOperator::ModAssign),
+        ("x &= y;", AssignmentOperator::AndAssign),
+        ("x |= y;", AssignmentOperator::OrAssign),
+        ("x ^= y;", AssignmentOperator::XorAssign),
+        ("x <<= 2;", AssignmentOperator::LeftShiftAssign),
+        ("x >>= 2;", AssignmentOperator::RightShiftAssign),
+        ("x ??= y;", AssignmentOperator::CoalesceAssign),
+    ] {
+        let language = tree_sitter_c_sharp::language();
+        let mut parser = GenericParser::new(language);
+        let tree = parser.parse(code);
+        let assignment_node = find_node(tree.root_node(), "assignment_expression")
+            .expect("Could not find assignment_expression in the tree");
+
+        let result = lower_expressions(assignment_node, code.as_bytes());
+
+        if let Expression::Assignment(assign) = result {
+            assert_eq!(assign.operator, operator, "{}", code);
+        } else {
+            panic!("Expected Assignment, got {:?}", result);
+        }
+    }
+}
+
+#[test]
+fn test_lower_conditional_expression() {
+    let language = tree_sitter_c_sharp::language();
+    let mut parser = GenericParser::new(language);
+    let code = "var name = customer == null ? \"occupant\" : customer.Name;";
+    let tree = parser.parse(code);
+    let root = tree.root_node();
+
+    fn find_node<'a>(node: tree_sitter::Node<'a>, kind: &str) -> Option<tree_sitter::Node<'a>> {
+        if node.kind() == kind {
+            return Some(node);
+        }
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            if let Some(found) = find_node(child, kind) {
+                return Some(found);
+            }
+        }
+        None
+    }
+
+    let conditional_node = find_node(root, "conditional_expression")
+        .expect("Could not find conditional_expression in the tree");
+
+    let result = lower_expressions(conditional_node, code.as_bytes());
+
+    if let Expression::Conditional(cond) = result {
+        assert!(matches!(
+            *cond.condition,
+            Expression::BinaryOp(BinaryOp {
+                operator: BinaryOperator::Equal,
+                ..
+            })
+        ));
+        assert!(matches!(
+            *cond.consequence,
+            Expression::Literal(Literal::String(ref s), _) if s == "occupant"
+        ));
+        assert!(
+            matches!(*cond.alternative, Expression::MemberAccess(ref ma) if ma.member == "Name")
+        );
+    } else {
+        panic!("Expected Conditional, got {:?}", result);
+    }
+}
+
+#[test]
+fn test_lower_lambda_expressions() {
+    let language = tree_sitter_c_sharp::language();
+    let mut parser = GenericParser::new(language);
+    let code = "var total = items.Where(x => x > 0).Sum((int y) => { return y; });";
+    let tree = parser.parse(code);
+    let root = tree.root_node();
+
+    fn find_nodes<'a>(
+        node: tree_sitter::Node<'a>,
+        kind: &str,
+        found: &mut Vec<tree_sitter::Node<'a>>,
+    ) {
+        if node.kind() == kind {
+            found.push(node);
+        }
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            find_nodes(child, kind, found);
+        }
+    }
+
+    let mut lambdas = Vec::new();
+    find_nodes(root, "lambda_expression", &mut lambdas);
+    assert_eq!(lambdas.len(), 2);
+
+    match lower_expressions(lambdas[0], code.as_bytes()) {
+        Expression::Lambda(lambda) => {
+            assert_eq!(lambda.parameters.len(), 1);
+            assert_eq!(lambda.parameters[0].name, "x");
+            assert_eq!(lambda.parameters[0].var_type, None);
+            assert!(matches!(
+                lambda.body,
+                LambdaBody::Expression(ref body) if matches!(**body, Expression::BinaryOp(_))
+            ));
+        }
+        other => panic!("Expected Lambda, got {:?}", other),
+    }
+
+    match lower_expressions(lambdas[1], code.as_bytes()) {
+        Expression::Lambda(lambda) => {
+            assert_eq!(lambda.parameters.len(), 1);
+            assert_eq!(lambda.parameters[0].name, "y");
+            assert_eq!(lambda.parameters[0].var_type, Some("int".to_string()));
+            assert!(
+                matches!(lambda.body, LambdaBody::Block(ref block) if block.statements.len() == 1)
+            );
+        }
+        other => panic!("Expected Lambda, got {:?}", other),
+    }
+}
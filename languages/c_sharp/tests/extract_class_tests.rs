@@ -0,0 +1,176 @@
+mod common;
+
+use common::refactor_file;
+use core::{ExtractClass, RefactorError};
+
+#[test]
+fn test_extract_class_with_fields_and_method() {
+    let source = r#"public class Person {
+    private string name;
+    private string areaCode;
+    private string number;
+
+    public string Name() {
+        return name;
+    }
+
+    /// The number with its area code.
+    public string TelephoneNumber() {
+        return "(" + areaCode + ") " + number;
+    }
+
+    public void Move(string code) {
+        this.areaCode = code;
+    }
+}"#;
+    let expected = r#"public class Person {
+    private string name;
+    private readonly Telephone telephone = new Telephone();
+
+    public string Name() {
+        return name;
+    }
+
+    public void Move(string code) {
+        this.telephone.areaCode = code;
+    }
+}
+
+public class Telephone {
+    internal string areaCode;
+    private string number;
+
+    /// The number with its area code.
+    public string TelephoneNumber() {
+        return "(" + areaCode + ") " + number;
+    }
+}"#;
+    let refactoring = ExtractClass::new(
+        "Person",
+        "Telephone",
+        &["areaCode", "number", "TelephoneNumber"],
+    );
+    let result = refactor_file(source, &refactoring).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_extract_class_rewrites_calls_and_other_instances() {
+    let source = r#"public class Order {
+    private decimal total;
+    private decimal rate;
+
+    private decimal Tax() {
+        return total * rate;
+    }
+
+    public decimal Gross() {
+        return total + Tax();
+    }
+
+    public bool SameRate(Order other) {
+        return rate == other.rate;
+    }
+}"#;
+    let expected = r#"public class Order {
+    private decimal total;
+    private readonly TaxPolicy taxPolicy = new TaxPolicy();
+
+    private decimal Tax() {
+        return total * taxPolicy.rate;
+    }
+
+    public decimal Gross() {
+        return total + Tax();
+    }
+
+    public bool SameRate(Order other) {
+        return taxPolicy.rate == other.taxPolicy.rate;
+    }
+}
+
+public class TaxPolicy {
+    internal decimal rate;
+}"#;
+    let refactoring = ExtractClass::new("Order", "TaxPolicy", &["rate"]);
+    let result = refactor_file(source, &refactoring).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_extract_class_of_methods_adds_field_after_fields() {
+    let source = r#"public class Report {
+    private int pages;
+
+    private string Header(string title) {
+        return Rule() + title;
+    }
+
+    private string Rule() {
+        return "----";
+    }
+
+    public string Print() {
+        return Header("Report") + pages;
+    }
+}"#;
+    let expected = r#"public class Report {
+    private int pages;
+    private readonly Layout layout = new Layout();
+
+    public string Print() {
+        return layout.Header("Report") + pages;
+    }
+}
+
+public class Layout {
+    internal string Header(string title) {
+        return Rule() + title;
+    }
+
+    private string Rule() {
+        return "----";
+    }
+}"#;
+    let refactoring = ExtractClass::new("Report", "Layout", &["Header", "Rule"]);
+    let result = refactor_file(source, &refactoring).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_extract_class_refuses_member_using_what_stays() {
+    let source = r#"public class Order {
+    private decimal total;
+    private decimal rate;
+
+    private decimal Tax() {
+        return total * rate;
+    }
+}"#;
+    let refactoring = ExtractClass::new("Order", "TaxPolicy", &["rate", "Tax"]);
+    let result = refactor_file(source, &refactoring);
+    assert!(matches!(result, Err(RefactorError::Unsupported(msg)) if msg.contains("`total`")));
+}
+
+#[test]
+fn test_extract_class_refuses_existing_class() {
+    let source = r#"public class Order {
+    private decimal rate;
+}
+
+public class TaxPolicy {
+}"#;
+    let refactoring = ExtractClass::new("Order", "TaxPolicy", &["rate"]);
+    let result = refactor_file(source, &refactoring);
+    assert!(matches!(result, Err(RefactorError::Conflict(_))));
+}
+
+#[test]
+fn test_extract_class_refuses_unknown_member() {
+    let source = r#"public class Order {
+    private decimal rate;
+}"#;
+    let refactoring = ExtractClass::new("Order", "TaxPolicy", &["fee"]);
+    let result = refactor_file(source, &refactoring);
+    assert!(matches!(result, Err(RefactorError::NotFound(msg)) if msg.contains("`fee`")));
+}
@@ -0,0 +1,242 @@
+mod common;
+
+use common::{refactor, span_of};
+use core::{ExtractFunction, RefactorError};
+
+#[test]
+fn test_extract_statements_without_data_flow() {
+    let source = r#"public class Report {
+    public void Print() {
+        Console.WriteLine("*****");
+        Console.WriteLine("Banner");
+        Console.WriteLine("Done");
+    }
+}"#;
+    let expected = r#"public class Report {
+    public void Print() {
+        PrintBanner();
+        Console.WriteLine("Done");
+    }
+
+    private void PrintBanner() {
+        Console.WriteLine("*****");
+        Console.WriteLine("Banner");
+    }
+}"#;
+    let (start, _) = span_of(source, "Console.WriteLine(\"*****\")");
+    let (_, end) = span_of(source, "Console.WriteLine(\"Banner\");");
+    let result = refactor(source, &ExtractFunction::new(start, end, "PrintBanner")).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_extract_with_parameters_and_return_value() {
+    let source = r#"public class Invoice {
+    public int Total(int price, int quantity) {
+        int discount = 5;
+        int subtotal = price * quantity;
+        subtotal = subtotal - discount;
+        return subtotal;
+    }
+}"#;
+    let expected = r#"public class Invoice {
+    public int Total(int price, int quantity) {
+        int discount = 5;
+        int subtotal = ComputeSubtotal(price, quantity, discount);
+        return subtotal;
+    }
+
+    private int ComputeSubtotal(int price, int quantity, int discount) {
+        int subtotal = price * quantity;
+        subtotal = subtotal - discount;
+        return subtotal;
+    }
+}"#;
+    let (start, _) = span_of(source, "int subtotal");
+    let (_, end) = span_of(source, "subtotal - discount;");
+    let result = refactor(source, &ExtractFunction::new(start, end, "ComputeSubtotal")).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_extract_assignment_to_outer_local_in_static_method() {
+    let source = r#"class Painting
+{
+    static void Main()
+    {
+        double length;
+        string lengthStr;
+        lengthStr = Console.ReadLine();
+        length = double.Parse(lengthStr);
+        Console.WriteLine(length);
+    }
+}"#;
+    let expected = r#"class Painting
+{
+    static void Main()
+    {
+        double length;
+        string lengthStr;
+        length = ReadLength();
+        Console.WriteLine(length);
+    }
+
+    private static double ReadLength()
+    {
+        double length;
+        string lengthStr;
+        lengthStr = Console.ReadLine();
+        length = double.Parse(lengthStr);
+        return length;
+    }
+}"#;
+    let (start, _) = span_of(source, "lengthStr = Console");
+    let (_, end) = span_of(source, "length = double.Parse(lengthStr);");
+    let result = refactor(source, &ExtractFunction::new(start, end, "ReadLength"));
+    assert_eq!(result.unwrap(), expected);
+}
+
+#[test]
+fn test_extract_rejects_several_results() {
+    let source = r#"public class Test {
+    public int Run() {
+        int a = 1;
+        int b = 2;
+        return a + b;
+    }
+}"#;
+    let (start, _) = span_of(source, "int a");
+    let (_, end) = span_of(source, "int b = 2;");
+    let result = refactor(source, &ExtractFunction::new(start, end, "Setup"));
+    assert!(matches!(result, Err(RefactorError::Unsupported(_))));
+}
+
+#[test]
+fn test_extract_rejects_partial_statement() {
+    let source = r#"public class Test {
+    public void Run() {
+        int a = 1;
+        Console.WriteLine(a);
+    }
+}"#;
+    let (start, end) = span_of(source, "Console.WriteLine");
+    let result = refactor(source, &ExtractFunction::new(start, end, "Print"));
+    assert!(matches!(result, Err(RefactorError::InvalidSelection(_))));
+}
+
+#[test]
+fn test_extract_returns_value_read_on_next_iteration() {
+    let source = r#"public class Reader {
+    public void Run() {
+        int last = 0;
+        while (true) {
+            Console.WriteLine(last);
+            last = Next();
+        }
+    }
+}"#;
+    let expected = r#"public class Reader {
+    public void Run() {
+        int last = 0;
+        while (true) {
+            Console.WriteLine(last);
+            last = ReadNext();
+        }
+    }
+
+    private int ReadNext() {
+        int last;
+        last = Next();
+        return last;
+    }
+}"#;
+    let (start, end) = span_of(source, "last = Next();");
+    let result = refactor(source, &ExtractFunction::new(start, end, "ReadNext")).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_extract_rejects_break_leaving_selection() {
+    let source = r#"public class Reader {
+    public void Run() {
+        for (int i = 0; i < 10; i++) {
+            if (i > 5) {
+                break;
+            }
+        }
+    }
+}"#;
+    let (start, _) = span_of(source, "if (i > 5)");
+    let (_, end) = span_of(source, "break;\n            }");
+    let result = refactor(source, &ExtractFunction::new(start, end, "Stop"));
+    assert!(matches!(result, Err(RefactorError::Unsupported(msg)) if msg.contains("`break`")));
+}
+
+#[test]
+fn test_extract_rejects_name_of_property() {
+    let source = r#"public class Reader {
+    public int P { get; set; }
+
+    public void Run() {
+        Console.WriteLine("a");
+    }
+}"#;
+    let (start, end) = span_of(source, "Console.WriteLine(\"a\");");
+    let result = refactor(source, &ExtractFunction::new(start, end, "P"));
+    assert!(matches!(result, Err(RefactorError::Conflict(_))));
+}
+
+#[test]
+fn test_extract_compound_assignment_takes_variable() {
+    let source = r#"public class Hash {
+    public int Bucket(int a) {
+        int x = a;
+        x %= 7;
+        return x;
+    }
+}"#;
+    let expected = r#"public class Hash {
+    public int Bucket(int a) {
+        int x = a;
+        x = Reduce(x);
+        return x;
+    }
+
+    private int Reduce(int x) {
+        x %= 7;
+        return x;
+    }
+}"#;
+    let (start, end) = span_of(source, "x %= 7;");
+    let result = refactor(source, &ExtractFunction::new(start, end, "Reduce")).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_extract_rejects_jumps_inside_unlowered_statements() {
+    let source = r#"public class Reader {
+    public int Run(Stream stream) {
+        for (int i = 0; i < 10; i++) {
+            lock (stream) {
+                if (stream.Done) break;
+            }
+        }
+        try {
+            return stream.Read();
+        } finally {
+            stream.Close();
+        }
+    }
+}"#;
+    let (start, end) = span_of(
+        source,
+        "lock (stream) {\n                if (stream.Done) break;\n            }",
+    );
+    let result = refactor(source, &ExtractFunction::new(start, end, "Stop"));
+    assert!(matches!(result, Err(RefactorError::Unsupported(msg)) if msg.contains("`break`")));
+
+    let (start, _) = span_of(source, "try {");
+    let (_, end) = span_of(source, "stream.Close();\n        }");
+    let result = refactor(source, &ExtractFunction::new(start, end, "Read"));
+    assert!(matches!(result, Err(RefactorError::Unsupported(msg)) if msg.contains("return")));
+}
@@ -0,0 +1,127 @@
+mod common;
+
+use common::{refactor, span_of};
+use core::{ExtractVariable, RefactorError};
+
+#[test]
+fn test_extract_variable_before_statement() {
+    let source = r#"public class Order {
+    public int Price(int quantity, int itemPrice) {
+        return quantity * itemPrice - quantity / 2;
+    }
+}"#;
+    let expected = r#"public class Order {
+    public int Price(int quantity, int itemPrice) {
+        var basePrice = quantity * itemPrice;
+        return basePrice - quantity / 2;
+    }
+}"#;
+    let (start, end) = span_of(source, "quantity * itemPrice");
+    let refactoring = ExtractVariable::new(start, end, "basePrice", false);
+    assert_eq!(refactor(source, &refactoring).unwrap(), expected);
+}
+
+#[test]
+fn test_extract_variable_replaces_identical_occurrences_until_write() {
+    let source = r#"public class Order {
+    public void Print(int a, int b) {
+        Console.WriteLine(a + b);
+        if (a + b > 10) {
+            Console.WriteLine((a + b) * 2);
+        }
+        a = 0;
+        Console.WriteLine(a + b);
+    }
+}"#;
+    let expected = r#"public class Order {
+    public void Print(int a, int b) {
+        var sum = a + b;
+        Console.WriteLine(sum);
+        if (sum > 10) {
+            Console.WriteLine(sum * 2);
+        }
+        a = 0;
+        Console.WriteLine(a + b);
+    }
+}"#;
+    let (start, end) = span_of(source, "a + b");
+    let refactoring = ExtractVariable::new(start, end, "sum", true);
+    assert_eq!(refactor(source, &refactoring).unwrap(), expected);
+}
+
+#[test]
+fn test_extract_variable_rejects_loop_condition() {
+    let source = r#"public class Counter {
+    public void Run(int n) {
+        int i = 0;
+        while (i < n * 2) {
+            i = i + 1;
+        }
+    }
+}"#;
+    let (start, end) = span_of(source, "n * 2");
+    let refactoring = ExtractVariable::new(start, end, "limit", false);
+    assert!(matches!(
+        refactor(source, &refactoring),
+        Err(RefactorError::Unsupported(_))
+    ));
+}
+
+#[test]
+fn test_extract_variable_rejects_short_circuited_operand() {
+    let source = r#"public class Checker {
+    public bool IsLong(string s) {
+        if (s != null && s.Length > 5) {
+            return true;
+        }
+        return false;
+    }
+}"#;
+    let (start, end) = span_of(source, "s.Length");
+    let refactoring = ExtractVariable::new(start, end, "length", false);
+    assert!(matches!(
+        refactor(source, &refactoring),
+        Err(RefactorError::Unsupported(_))
+    ));
+}
+
+#[test]
+fn test_extract_variable_rejects_existing_name() {
+    let source = r#"public class Counter {
+    public int Run(int n) {
+        int total = 0;
+        return n + 1;
+    }
+}"#;
+    let (start, end) = span_of(source, "n + 1");
+    let refactoring = ExtractVariable::new(start, end, "total", false);
+    assert!(matches!(
+        refactor(source, &refactoring),
+        Err(RefactorError::Conflict(_))
+    ));
+}
+
+#[test]
+fn test_extract_variable_keeps_other_operators_apart() {
+    let source = r#"public class Calc {
+    public int Mix(int a, int b, int? c) {
+        Log(a + b);
+        Log(a % b);
+        Log(a << b);
+        return c ?? b;
+    }
+}"#;
+    let expected = r#"public class Calc {
+    public int Mix(int a, int b, int? c) {
+        var sum = a + b;
+        Log(sum);
+        Log(a % b);
+        Log(a << b);
+        return c ?? b;
+    }
+}"#;
+    let (start, end) = span_of(source, "a + b");
+    let refactoring = ExtractVariable::new(start, end, "sum", true);
+    let result = refactor(source, &refactoring).unwrap();
+    assert_eq!(result, expected);
+}
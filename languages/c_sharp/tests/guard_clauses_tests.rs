@@ -0,0 +1,144 @@
+mod common;
+
+use common::refactor;
+use core::{RefactorError, ReplaceNestedConditionalWithGuardClauses};
+
+#[test]
+fn test_flatten_else_chain() {
+    let source = r#"public class Payroll {
+    public double PayAmount(Employee employee) {
+        double result;
+        if (employee.IsDead) {
+            return DeadAmount();
+        } else {
+            if (employee.IsSeparated) {
+                return SeparatedAmount();
+            } else if (employee.IsRetired) {
+                return RetiredAmount();
+            } else {
+                result = NormalAmount();
+                return result;
+            }
+        }
+    }
+}"#;
+    let expected = r#"public class Payroll {
+    public double PayAmount(Employee employee) {
+        double result;
+        if (employee.IsDead) {
+            return DeadAmount();
+        }
+        if (employee.IsSeparated) {
+            return SeparatedAmount();
+        }
+        if (employee.IsRetired) {
+            return RetiredAmount();
+        }
+        result = NormalAmount();
+        return result;
+    }
+}"#;
+    let refactoring = ReplaceNestedConditionalWithGuardClauses::new("PayAmount");
+    let result = refactor(source, &refactoring).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_invert_conditions_nested_in_then_branch() {
+    let source = r#"public class Loan {
+    public double Adjusted(double capital, double rate, bool open) {
+        if (capital > 0) {
+            if (!open) {
+                return 0;
+            } else {
+                double income = capital * rate;
+                return income;
+            }
+        } else {
+            return -1;
+        }
+    }
+}"#;
+    let expected = r#"public class Loan {
+    public double Adjusted(double capital, double rate, bool open) {
+        if (capital <= 0) {
+            return -1;
+        }
+        if (!open) {
+            return 0;
+        }
+        double income = capital * rate;
+        return income;
+    }
+}"#;
+    let refactoring = ReplaceNestedConditionalWithGuardClauses::new("Adjusted");
+    let result = refactor(source, &refactoring).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_wrap_other_conditions_in_negation() {
+    let source = r#"public class Loan {
+    public int Grade(object value, bool a) {
+        if (value is string) {
+            if (a) {
+                return 1;
+            } else {
+                return 2;
+            }
+        } else {
+            return 0;
+        }
+    }
+}"#;
+    let expected = r#"public class Loan {
+    public int Grade(object value, bool a) {
+        if (!(value is string)) {
+            return 0;
+        }
+        if (a) {
+            return 1;
+        }
+        return 2;
+    }
+}"#;
+    let refactoring = ReplaceNestedConditionalWithGuardClauses::new("Grade");
+    let result = refactor(source, &refactoring).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_refuse_branch_that_falls_through() {
+    let source = r#"public class Loan {
+    public int Grade(int score) {
+        int grade = 0;
+        if (score > 10) {
+            grade = 1;
+        } else {
+            return 0;
+        }
+        return grade;
+    }
+}"#;
+    let refactoring = ReplaceNestedConditionalWithGuardClauses::new("Grade");
+    let result = refactor(source, &refactoring);
+    assert!(matches!(result, Err(RefactorError::Unsupported(_))));
+}
+
+#[test]
+fn test_refuse_clashing_locals() {
+    let source = r#"public class Loan {
+    public int Grade(int score) {
+        if (score > 10) {
+            int grade = 1;
+            return grade;
+        } else {
+            int grade = 0;
+            return grade;
+        }
+    }
+}"#;
+    let refactoring = ReplaceNestedConditionalWithGuardClauses::new("Grade");
+    let result = refactor(source, &refactoring);
+    assert!(matches!(result, Err(RefactorError::Conflict(_))));
+}
@@ -0,0 +1,165 @@
+mod common;
+
+use common::{refactor_file, refactor_files};
+use core::{InlineClass, RefactorError};
+
+#[test]
+fn test_inline_class_into_holder() {
+    let source = r#"public class Person {
+    private readonly TelephoneNumber telephoneNumber = new TelephoneNumber();
+    public string Name;
+
+    public string Phone() {
+        return telephoneNumber.Format();
+    }
+
+    public void Move(string areaCode) {
+        this.telephoneNumber.Number = "";
+        telephoneNumber.areaCode = areaCode;
+    }
+}
+
+public class TelephoneNumber {
+    /// The dialing prefix.
+    internal string areaCode;
+    internal string Number;
+
+    internal string Format() {
+        return "(" + areaCode + ") " + this.Number;
+    }
+}"#;
+    let expected = r#"public class Person {
+    public string Name;
+
+    public string Phone() {
+        return Format();
+    }
+
+    public void Move(string areaCode) {
+        this.Number = "";
+        this.areaCode = areaCode;
+    }
+
+    /// The dialing prefix.
+    internal string areaCode;
+    internal string Number;
+
+    internal string Format() {
+        return "(" + areaCode + ") " + this.Number;
+    }
+}"#;
+    let result = refactor_file(source, &InlineClass::new("TelephoneNumber")).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_inline_class_across_files() {
+    let person = r#"public class Person {
+    public string Name;
+    internal TelephoneNumber phone = new TelephoneNumber();
+}"#;
+    let number = r#"public class TelephoneNumber {
+    public string AreaCode { get; set; }
+}
+"#;
+    let directory = r#"public class Directory {
+    public string Code(Person person) {
+        return person.phone.AreaCode;
+    }
+}"#;
+    let result = refactor_files(
+        &[
+            ("Person.cs", person),
+            ("TelephoneNumber.cs", number),
+            ("Directory.cs", directory),
+        ],
+        &InlineClass::new("TelephoneNumber"),
+    )
+    .unwrap();
+    assert_eq!(
+        result,
+        vec![
+            r#"public class Person {
+    public string Name;
+    public string AreaCode { get; set; }
+}"#
+            .to_string(),
+            String::new(),
+            r#"public class Directory {
+    public string Code(Person person) {
+        return person.AreaCode;
+    }
+}"#
+            .to_string(),
+        ]
+    );
+}
+
+#[test]
+fn test_inline_class_refuses_second_user() {
+    let source = r#"public class Person {
+    private TelephoneNumber phone = new TelephoneNumber();
+}
+
+public class Office {
+    public string Dial(TelephoneNumber number) {
+        return number.Number;
+    }
+}
+
+public class TelephoneNumber {
+    public string Number;
+}"#;
+    let result = refactor_file(source, &InlineClass::new("TelephoneNumber"));
+    assert!(
+        matches!(result, Err(RefactorError::Unsupported(msg)) if msg.contains("`Person` and `Office`"))
+    );
+}
+
+#[test]
+fn test_inline_class_refuses_field_used_as_a_whole() {
+    let source = r#"public class Person {
+    private TelephoneNumber phone = new TelephoneNumber();
+
+    public void Print(Printer printer) {
+        printer.Print(phone);
+    }
+}
+
+public class TelephoneNumber {
+    public string Number;
+}"#;
+    let result = refactor_file(source, &InlineClass::new("TelephoneNumber"));
+    assert!(matches!(result, Err(RefactorError::Unsupported(msg)) if msg.contains("`phone`")));
+}
+
+#[test]
+fn test_inline_class_refuses_taken_name() {
+    let source = r#"public class Person {
+    private TelephoneNumber phone = new TelephoneNumber();
+    private string Number;
+}
+
+public class TelephoneNumber {
+    public string Number;
+}"#;
+    let result = refactor_file(source, &InlineClass::new("TelephoneNumber"));
+    assert!(matches!(result, Err(RefactorError::Conflict(msg)) if msg.contains("`Number`")));
+}
+
+#[test]
+fn test_inline_class_refuses_constructor() {
+    let source = r#"public class Person {
+    private TelephoneNumber phone = new TelephoneNumber();
+}
+
+public class TelephoneNumber {
+    public string Number;
+
+    public TelephoneNumber() {
+        Number = "unknown";
+    }
+}"#;
+    let result = refactor_file(source, &InlineClass::new("TelephoneNumber"));
+    assert!(matches!(result, Err(RefactorError::Unsupported(msg)) if msg.contains("constructor")));
+}
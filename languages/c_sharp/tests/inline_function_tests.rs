@@ -0,0 +1,235 @@
+mod common;
+
+use common::refactor;
+use core::{InlineFunction, RefactorError};
+
+#[test]
+fn test_inline_statement_call() {
+    let source = r#"public class Report {
+    public void Print(string name) {
+        PrintHeader(name);
+        Console.WriteLine("Done");
+    }
+
+    private void PrintHeader(string title) {
+        Console.WriteLine("*****");
+        Console.WriteLine(title);
+    }
+}"#;
+    let expected = r#"public class Report {
+    public void Print(string name) {
+        Console.WriteLine("*****");
+        Console.WriteLine(name);
+        Console.WriteLine("Done");
+    }
+}"#;
+    let result = refactor(source, &InlineFunction::new("PrintHeader")).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_inline_expression_call_respects_precedence() {
+    let source = r#"public class Order {
+    public int Total(int price, int tax) {
+        int total = Twice(price + tax) * 3;
+        return total + Twice(price);
+    }
+
+    private int Twice(int value) {
+        return value * 2;
+    }
+}"#;
+    let expected = r#"public class Order {
+    public int Total(int price, int tax) {
+        int total = (price + tax) * 2 * 3;
+        return total + price * 2;
+    }
+}"#;
+    let result = refactor(source, &InlineFunction::new("Twice")).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_inline_binds_argument_used_more_than_once() {
+    let source = r#"public class Shop {
+    public int Price() {
+        int price = Square(Base());
+        return price;
+    }
+
+    private int Square(int n) {
+        Console.WriteLine(n);
+        return n * n;
+    }
+}"#;
+    let expected = r#"public class Shop {
+    public int Price() {
+        int n = Base();
+        Console.WriteLine(n);
+        int price = n * n;
+        return price;
+    }
+}"#;
+    let result = refactor(source, &InlineFunction::new("Square")).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_inline_rejects_recursive_method() {
+    let source = r#"public class Math {
+    public int Fact(int n) {
+        return n * Fact(n - 1);
+    }
+}"#;
+    let result = refactor(source, &InlineFunction::new("Fact"));
+    assert!(matches!(result, Err(RefactorError::Unsupported(_))));
+}
+
+#[test]
+fn test_inline_rejects_virtual_method() {
+    let source = r#"public class Shape {
+    public virtual int Area() {
+        return 0;
+    }
+}"#;
+    let result = refactor(source, &InlineFunction::new("Area"));
+    assert!(matches!(result, Err(RefactorError::Unsupported(_))));
+}
+
+#[test]
+fn test_inline_rejects_early_return() {
+    let source = r#"public class Rating {
+    public int Score(int n) {
+        return Clamp(n);
+    }
+
+    private int Clamp(int n) {
+        if (n > 10) {
+            return 10;
+        }
+        return n;
+    }
+}"#;
+    let result = refactor(source, &InlineFunction::new("Clamp"));
+    assert!(matches!(result, Err(RefactorError::Unsupported(_))));
+}
+
+#[test]
+fn test_inline_binds_side_effect_read_inside_loop() {
+    let source = r#"public class Counter {
+    public int Run() {
+        return Repeat(Next());
+    }
+
+    private int Repeat(int step) {
+        int total = 0;
+        for (int i = 0; i < 3; i++) {
+            total += step;
+        }
+        return total;
+    }
+}"#;
+    let expected = r#"public class Counter {
+    public int Run() {
+        int step = Next();
+        int total = 0;
+        for (int i = 0; i < 3; i++) {
+            total += step;
+        }
+        return total;
+    }
+}"#;
+    let result = refactor(source, &InlineFunction::new("Repeat")).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_inline_rejects_body_under_short_circuit() {
+    let source = r#"public class Gate {
+    public void Open(bool ready) {
+        if (ready && Check(5)) {
+            Console.WriteLine("open");
+        }
+    }
+
+    private bool Check(int level) {
+        Console.WriteLine(level);
+        return level > 3;
+    }
+}"#;
+    let result = refactor(source, &InlineFunction::new("Check"));
+    assert!(matches!(result, Err(RefactorError::Unsupported(_))));
+}
+
+#[test]
+fn test_inline_call_in_constructor() {
+    let source = r#"public class Counter {
+    private int count;
+
+    public Counter() {
+        Reset();
+    }
+
+    private void Reset() {
+        count = 0;
+    }
+}"#;
+    let expected = r#"public class Counter {
+    private int count;
+
+    public Counter() {
+        count = 0;
+    }
+}"#;
+    let result = refactor(source, &InlineFunction::new("Reset")).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_inline_binds_side_effect_read_conditionally_or_after_effects() {
+    let source = r#"public class Logger {
+    public void Run() {
+        Report(Next(), false);
+    }
+
+    private void Report(string message, bool verbose) {
+        Log("start");
+        if (verbose) {
+            Console.WriteLine(message);
+        }
+    }
+}"#;
+    let expected = r#"public class Logger {
+    public void Run() {
+        string message = Next();
+        Log("start");
+        if (false) {
+            Console.WriteLine(message);
+        }
+    }
+}"#;
+    let result = refactor(source, &InlineFunction::new("Report")).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_inline_substitutes_side_effect_read_first() {
+    let source = r#"public class Logger {
+    public void Run() {
+        Report(Next());
+    }
+
+    private void Report(string message) {
+        Console.WriteLine(message);
+        Log("done");
+    }
+}"#;
+    let expected = r#"public class Logger {
+    public void Run() {
+        Console.WriteLine(Next());
+        Log("done");
+    }
+}"#;
+    let result = refactor(source, &InlineFunction::new("Report")).unwrap();
+    assert_eq!(result, expected);
+}
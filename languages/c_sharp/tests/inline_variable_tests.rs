@@ -0,0 +1,133 @@
+mod common;
+
+use common::{refactor, span_of};
+use core::{InlineVariable, RefactorError};
+
+#[test]
+fn test_inline_variable_with_parentheses() {
+    let source = r#"public class Order {
+    public int Price(int quantity, int itemPrice) {
+        int basePrice = quantity + itemPrice;
+        Console.WriteLine(basePrice);
+        return basePrice * 2;
+    }
+}"#;
+    let expected = r#"public class Order {
+    public int Price(int quantity, int itemPrice) {
+        Console.WriteLine(quantity + itemPrice);
+        return (quantity + itemPrice) * 2;
+    }
+}"#;
+    let (offset, _) = span_of(source, "basePrice");
+    let result = refactor(source, &InlineVariable::new(offset)).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_inline_variable_rejects_reassignment() {
+    let source = r#"public class Counter {
+    public int Run() {
+        int count = 0;
+        count++;
+        return count;
+    }
+}"#;
+    let (offset, _) = span_of(source, "count");
+    let result = refactor(source, &InlineVariable::new(offset));
+    assert!(matches!(result, Err(RefactorError::Unsupported(_))));
+}
+
+#[test]
+fn test_inline_variable_rejects_changed_input() {
+    let source = r#"public class Counter {
+    public int Run(int a) {
+        int twice = a * 2;
+        a = 5;
+        return twice;
+    }
+}"#;
+    let (offset, _) = span_of(source, "twice");
+    let result = refactor(source, &InlineVariable::new(offset));
+    assert!(matches!(result, Err(RefactorError::Unsupported(_))));
+}
+
+#[test]
+fn test_inline_variable_rejects_repeated_side_effect() {
+    let source = r#"public class Reader {
+    public void Run() {
+        string line = Console.ReadLine();
+        Console.WriteLine(line);
+        Console.WriteLine(line);
+    }
+}"#;
+    let (offset, _) = span_of(source, "line");
+    let result = refactor(source, &InlineVariable::new(offset));
+    assert!(matches!(result, Err(RefactorError::Unsupported(_))));
+}
+
+#[test]
+fn test_inline_variable_parenthesizes_low_precedence_operators() {
+    let source = r#"public class Flags {
+    public int Combine(int? a, int b, int c) {
+        int fallback = a ?? b;
+        int mask = b | c;
+        return fallback + mask + 1;
+    }
+}"#;
+    let expected = r#"public class Flags {
+    public int Combine(int? a, int b, int c) {
+        return (a ?? b) + (b | c) + 1;
+    }
+}"#;
+    let (first, _) = span_of(source, "fallback");
+    let once = refactor(source, &InlineVariable::new(first)).unwrap();
+    let (second, _) = span_of(&once, "mask");
+    let result = refactor(&once, &InlineVariable::new(second)).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_inline_variable_rejects_side_effect_moved_into_loop_or_branch() {
+    for body in [
+        "for (int i = 0; i < 3; i++) {\n            Console.WriteLine(x);\n        }",
+        "if (flag) {\n            Console.WriteLine(x);\n        }",
+        "Console.WriteLine(flag && x > 0);",
+        "Console.WriteLine(\"start\");\n        Console.WriteLine(x);",
+    ] {
+        let source = format!(
+            r#"public class Counter {{
+    public void Run(bool flag) {{
+        int x = Next();
+        {}
+    }}
+}}"#,
+            body
+        );
+        let (offset, _) = span_of(&source, "x =");
+        let result = refactor(&source, &InlineVariable::new(offset));
+        assert!(
+            matches!(&result, Err(RefactorError::Unsupported(msg)) if msg.contains("would not run")),
+            "{}: {:?}",
+            body,
+            result
+        );
+    }
+}
+
+#[test]
+fn test_inline_variable_moves_side_effect_into_first_use() {
+    let source = r#"public class Counter {
+    public int Run() {
+        int x = Next();
+        return x + 1;
+    }
+}"#;
+    let expected = r#"public class Counter {
+    public int Run() {
+        return Next() + 1;
+    }
+}"#;
+    let (offset, _) = span_of(source, "x =");
+    let result = refactor(source, &InlineVariable::new(offset)).unwrap();
+    assert_eq!(result, expected);
+}
@@ -0,0 +1,109 @@
+mod common;
+
+use common::refactor;
+use core::{IntroduceParameterObject, RefactorError};
+
+#[test]
+fn test_group_parameters_into_record() {
+    let source = r#"public class Booking {
+    public int Nights(int start, int end, string guest) {
+        Console.WriteLine(guest);
+        return end - start;
+    }
+
+    public int Run(Booking other) {
+        return Nights(1, 5, "Ann") + other.Nights(2, 3, "Bob");
+    }
+}"#;
+    let expected = r#"public class Booking {
+    public int Nights(DateRange range, string guest) {
+        Console.WriteLine(guest);
+        return range.End - range.Start;
+    }
+
+    public int Run(Booking other) {
+        return Nights(new DateRange(1, 5), "Ann") + other.Nights(new DateRange(2, 3), "Bob");
+    }
+}
+
+public record DateRange(int Start, int End);"#;
+    let refactoring =
+        IntroduceParameterObject::new("Nights", &["start", "end"], "DateRange", "range");
+    let result = refactor(source, &refactoring).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_group_non_adjacent_parameters() {
+    let source = r#"public class Shape {
+    public int Area(int width, string label, int height) {
+        Console.WriteLine(label);
+        return width * height;
+    }
+
+    public int Run() {
+        return Area(2, "box", 3);
+    }
+}"#;
+    let expected = r#"public class Shape {
+    public int Area(Size size, string label) {
+        Console.WriteLine(label);
+        return size.Width * size.Height;
+    }
+
+    public int Run() {
+        return Area(new Size(2, 3), "box");
+    }
+}
+
+public record Size(int Width, int Height);"#;
+    let refactoring = IntroduceParameterObject::new("Area", &["height", "width"], "Size", "size");
+    let result = refactor(source, &refactoring).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_refuse_assigned_parameter() {
+    let source = r#"public class Counter {
+    public int Count(int from, int to) {
+        from = from + 1;
+        return to - from;
+    }
+}"#;
+    let refactoring = IntroduceParameterObject::new("Count", &["from", "to"], "Bounds", "bounds");
+    let result = refactor(source, &refactoring);
+    assert!(matches!(result, Err(RefactorError::Unsupported(_))));
+}
+
+#[test]
+fn test_refuse_taken_parameter_name() {
+    let source = r#"public class Counter {
+    public int Count(int from, int to) {
+        int bounds = to - from;
+        return bounds;
+    }
+}"#;
+    let refactoring = IntroduceParameterObject::new("Count", &["from", "to"], "Bounds", "bounds");
+    let result = refactor(source, &refactoring);
+    assert!(matches!(result, Err(RefactorError::Conflict(_))));
+}
+
+#[test]
+fn test_refuse_reordered_side_effects() {
+    let source = r#"public class Counter {
+    public int Count(int from, int step, int to) {
+        return (to - from) / step;
+    }
+
+    public int Run() {
+        return Count(1, Next(), Next());
+    }
+
+    public int Next() {
+        return 2;
+    }
+}"#;
+    let refactoring = IntroduceParameterObject::new("Count", &["from", "to"], "Bounds", "bounds");
+    let result = refactor(source, &refactoring);
+    assert!(matches!(result, Err(RefactorError::Unsupported(_))));
+}
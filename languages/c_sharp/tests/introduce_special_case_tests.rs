@@ -0,0 +1,329 @@
+mod common;
+
+use common::refactor_file;
+use core::{IntroduceSpecialCase, RefactorError};
+
+#[test]
+fn test_conditional_default_becomes_override() {
+    let source = r#"public class Customer {
+    public string Name { get; set; }
+}
+
+public class Site {
+    private Customer customer = null;
+
+    public string Greeting() {
+        string name = customer == null ? "occupant" : customer.Name;
+        return "Dear " + name;
+    }
+
+    public string Label() {
+        return this.customer != null ? this.customer.Name : "occupant";
+    }
+}"#;
+    let expected = r#"public class Customer {
+    public virtual string Name { get; set; }
+}
+
+public class NullCustomer : Customer {
+    public override string Name => "occupant";
+}
+
+public class Site {
+    private Customer customer = new NullCustomer();
+
+    public string Greeting() {
+        string name = customer.Name;
+        return "Dear " + name;
+    }
+
+    public string Label() {
+        return this.customer.Name;
+    }
+}"#;
+    let result = refactor_file(source, &IntroduceSpecialCase::new("Customer")).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_null_tests_use_is_null_and_producers_create_special_case() {
+    let source = r#"public class Customer {
+    public string Name { get; set; }
+}
+
+public class Site {
+    private Customer customer = null;
+
+    public void Vacate() {
+        customer = null;
+    }
+
+    public Customer Find(int id) {
+        if (id < 0) {
+            return null;
+        }
+        return customer;
+    }
+
+    public bool IsOccupied() {
+        if (customer == null) {
+            return false;
+        }
+        return customer != null && customer.Name.Length > 0;
+    }
+}"#;
+    let expected = r#"public class Customer {
+    public string Name { get; set; }
+
+    public virtual bool IsNull => false;
+}
+
+public class NullCustomer : Customer {
+    public override bool IsNull => true;
+}
+
+public class Site {
+    private Customer customer = new NullCustomer();
+
+    public void Vacate() {
+        customer = new NullCustomer();
+    }
+
+    public Customer Find(int id) {
+        if (id < 0) {
+            return null;
+        }
+        return customer;
+    }
+
+    public bool IsOccupied() {
+        if (customer.IsNull) {
+            return false;
+        }
+        return !customer.IsNull && customer.Name.Length > 0;
+    }
+}"#;
+    let result = refactor_file(source, &IntroduceSpecialCase::new("Customer")).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_parameterless_method_and_local_variable() {
+    let source = r#"namespace Billing
+{
+    public class Customer
+    {
+        public virtual Plan BillingPlan()
+        {
+            return Plan.Premium;
+        }
+    }
+
+    public class Invoice
+    {
+        public Plan PlanFor(bool registered)
+        {
+            Customer customer = null;
+            if (registered)
+            {
+                customer = new Customer();
+            }
+            return customer == null ? Plan.Basic : customer.BillingPlan();
+        }
+    }
+}"#;
+    let expected = r#"namespace Billing
+{
+    public class Customer
+    {
+        public virtual Plan BillingPlan()
+        {
+            return Plan.Premium;
+        }
+    }
+
+    public class NullCustomer : Customer
+    {
+        public override Plan BillingPlan() => Plan.Basic;
+    }
+
+    public class Invoice
+    {
+        public Plan PlanFor(bool registered)
+        {
+            Customer customer = new NullCustomer();
+            if (registered)
+            {
+                customer = new Customer();
+            }
+            return customer.BillingPlan();
+        }
+    }
+}"#;
+    let result = refactor_file(source, &IntroduceSpecialCase::new("Customer")).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_refuse_disagreeing_defaults() {
+    let source = r#"public class Customer {
+    public string Name { get; set; }
+}
+
+public class Site {
+    private Customer customer = null;
+
+    public string Greeting() {
+        return customer == null ? "occupant" : customer.Name;
+    }
+
+    public string Label() {
+        return customer == null ? "nobody" : customer.Name;
+    }
+}"#;
+    let result = refactor_file(source, &IntroduceSpecialCase::new("Customer"));
+    assert!(matches!(result, Err(RefactorError::Conflict(_))));
+}
+
+#[test]
+fn test_refuse_default_depending_on_caller() {
+    let source = r#"public class Customer {
+    public string Name { get; set; }
+}
+
+public class Site {
+    private Customer customer = null;
+    private string fallback;
+
+    public string Greeting() {
+        return customer == null ? fallback : customer.Name;
+    }
+}"#;
+    let result = refactor_file(source, &IntroduceSpecialCase::new("Customer"));
+    assert!(matches!(result, Err(RefactorError::Unsupported(_))));
+}
+
+#[test]
+fn test_refuse_field_read() {
+    let source = r#"public class Customer {
+    public string name;
+}
+
+public class Site {
+    private Customer customer = null;
+
+    public string Greeting() {
+        return customer == null ? "occupant" : customer.name;
+    }
+}"#;
+    let result = refactor_file(source, &IntroduceSpecialCase::new("Customer"));
+    assert!(matches!(result, Err(RefactorError::Unsupported(_))));
+}
+
+#[test]
+fn test_refuse_field_never_initialized() {
+    let source = r#"public class Customer {
+    public string Name { get; set; }
+}
+
+public class Site {
+    private Customer customer;
+
+    public string Greeting() {
+        return customer == null ? "occupant" : customer.Name;
+    }
+}"#;
+    let result = refactor_file(source, &IntroduceSpecialCase::new("Customer"));
+    assert!(
+        matches!(result, Err(RefactorError::Unsupported(msg)) if msg.contains("never initialized"))
+    );
+}
+
+#[test]
+fn test_refuse_parameter() {
+    let source = r#"public class Customer {
+    public string Name { get; set; }
+}
+
+public class Site {
+    public bool IsVacant(Customer c) {
+        return c == null;
+    }
+}"#;
+    let result = refactor_file(source, &IntroduceSpecialCase::new("Customer"));
+    assert!(matches!(result, Err(RefactorError::Unsupported(msg)) if msg.contains("parameter")));
+}
+
+#[test]
+fn test_refuse_value_from_elsewhere() {
+    let source = r#"public class Customer {
+    public string Name { get; set; }
+}
+
+public class Site {
+    public string Greeting(Registry registry) {
+        Customer customer = registry.Lookup();
+        return customer == null ? "occupant" : customer.Name;
+    }
+}"#;
+    let result = refactor_file(source, &IntroduceSpecialCase::new("Customer"));
+    assert!(
+        matches!(result, Err(RefactorError::Unsupported(msg)) if msg.contains("`registry.Lookup()`"))
+    );
+}
+
+#[test]
+fn test_unchecked_null_left_alone() {
+    let source = r#"public class Customer {
+    public string Name { get; set; }
+}
+
+public class Site {
+    public string Greeting() {
+        Customer customer = null;
+        return customer == null ? "occupant" : customer.Name;
+    }
+
+    public string Describe() {
+        Customer c = null;
+        return c.Name;
+    }
+}"#;
+    let result = refactor_file(source, &IntroduceSpecialCase::new("Customer")).unwrap();
+    assert!(result.contains("Customer customer = new NullCustomer();"));
+    assert!(result.contains("Customer c = null;"));
+}
+
+#[test]
+fn test_refuse_null_aware_use() {
+    for use_ in [
+        "c?.Name ?? \"nobody\"",
+        "(c ?? other).Name",
+        "c is null ? \"nobody\" : c.Name",
+        "ReferenceEquals(c, null) ? \"nobody\" : c.Name",
+    ] {
+        let source = format!(
+            r#"public class Customer {{
+    public string Name {{ get; set; }}
+}}
+
+public class Site {{
+    public string Describe(Customer other) {{
+        Customer c = null;
+        if (c == null) {{
+            c = new Customer();
+        }}
+        return {};
+    }}
+}}"#,
+            use_
+        );
+        let result = refactor_file(&source, &IntroduceSpecialCase::new("Customer"));
+        assert!(
+            matches!(&result, Err(RefactorError::Unsupported(msg)) if msg.contains("also used")),
+            "{}: {:?}",
+            use_,
+            result
+        );
+    }
+}
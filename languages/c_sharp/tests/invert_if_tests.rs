@@ -0,0 +1,120 @@
+mod common;
+
+use common::{refactor, span_of};
+use core::{InvertIf, RefactorError};
+
+#[test]
+fn test_swap_branches_and_flip_comparison() {
+    let source = r#"public class Thermostat {
+    public void Adjust(int temperature) {
+        if (temperature > 20) {
+            Cool();
+        } else {
+            Heat();
+        }
+    }
+}"#;
+    let expected = r#"public class Thermostat {
+    public void Adjust(int temperature) {
+        if (temperature <= 20) {
+            Heat();
+        } else {
+            Cool();
+        }
+    }
+}"#;
+    let (offset, _) = span_of(source, "if");
+    let result = refactor(source, &InvertIf::new(offset)).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_apply_de_morgan() {
+    let source = r#"public class Thermostat {
+    public int Mode(int temperature, bool manual, bool away) {
+        if (!manual && (temperature < 15 || away)) {
+            return 1;
+        } else {
+            return 2;
+        }
+    }
+}"#;
+    let expected = r#"public class Thermostat {
+    public int Mode(int temperature, bool manual, bool away) {
+        if (manual || temperature >= 15 && !away) {
+            return 2;
+        } else {
+            return 1;
+        }
+    }
+}"#;
+    let (offset, _) = span_of(source, "if");
+    let result = refactor(source, &InvertIf::new(offset)).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_brace_else_if_moved_into_consequence() {
+    let source = r#"public class Thermostat {
+    public void Adjust(int temperature) {
+        if (temperature > 20) {
+            Cool();
+        } else if (temperature < 10) {
+            Heat();
+        }
+    }
+}"#;
+    let expected = r#"public class Thermostat {
+    public void Adjust(int temperature) {
+        if (temperature <= 20) {
+            if (temperature < 10) {
+                Heat();
+            }
+        } else {
+            Cool();
+        }
+    }
+}"#;
+    let (offset, _) = span_of(source, "if");
+    let result = refactor(source, &InvertIf::new(offset)).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_early_return_at_end_of_method() {
+    let source = r#"public class Thermostat {
+    public void Adjust(int temperature) {
+        Console.WriteLine(temperature);
+        if (temperature > 20) {
+            int excess = temperature - 20;
+            Cool(excess);
+        }
+    }
+}"#;
+    let expected = r#"public class Thermostat {
+    public void Adjust(int temperature) {
+        Console.WriteLine(temperature);
+        if (temperature <= 20) return;
+        int excess = temperature - 20;
+        Cool(excess);
+    }
+}"#;
+    let (offset, _) = span_of(source, "if");
+    let result = refactor(source, &InvertIf::new(offset)).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_refuse_if_without_else_before_other_statements() {
+    let source = r#"public class Thermostat {
+    public void Adjust(int temperature) {
+        if (temperature > 20) {
+            Cool();
+        }
+        Console.WriteLine(temperature);
+    }
+}"#;
+    let (offset, _) = span_of(source, "if");
+    let result = refactor(source, &InvertIf::new(offset));
+    assert!(matches!(result, Err(RefactorError::Unsupported(_))));
+}
@@ -1,6 +1,6 @@
 use c_sharp::lower_statement;
 use parser::GenericParser;
-use uast::{Expression, Literal, Statement};
+use uast::{Expression, Literal, Span, Statement, SwitchLabel, UnaryOp, UnaryOperator};
 
 #[test]
 fn test_lower_variable_declaration() {
@@ -28,7 +28,10 @@ fn test_lower_variable_declaration() {
 
         assert_eq!(
             decl.value,
-            Some(Box::new(Expression::Literal(Literal::Float(5.0))))
+            Some(Box::new(Expression::Literal(
+                Literal::Float(5.0),
+                Span { start: 17, end: 20 }
+            )))
         );
     } else {
         panic!("Expected a DeclStmt, but got {:?}", result);
@@ -50,7 +53,10 @@ fn test_lower_if_statement() {
     if let Statement::IfStatement(if_stmt) = result {
         assert_eq!(
             if_stmt.condition,
-            Box::new(Expression::Literal(Literal::Boolean(true)))
+            Box::new(Expression::Literal(
+                Literal::Boolean(true),
+                Span { start: 4, end: 8 }
+            ))
         );
         assert_eq!(if_stmt.alternative, None);
         assert_eq!(if_stmt.span.start, 0);
@@ -64,7 +70,10 @@ fn test_lower_if_statement() {
             assert_eq!(var_decl.var_type, Some("int".to_string()));
             assert_eq!(
                 var_decl.value,
-                Some(Box::new(Expression::Literal(Literal::Integer(1))))
+                Some(Box::new(Expression::Literal(
+                    Literal::Integer(1),
+                    Span { start: 20, end: 21 }
+                )))
             );
         } else {
             panic!(
@@ -94,7 +103,10 @@ fn test_lower_if_else_statement() {
     if let Statement::IfStatement(if_stmt) = result {
         assert_eq!(
             if_stmt.condition,
-            Box::new(Expression::Literal(Literal::Boolean(false)))
+            Box::new(Expression::Literal(
+                Literal::Boolean(false),
+                Span { start: 4, end: 9 }
+            ))
         );
         assert_eq!(if_stmt.span.start, 0);
         assert_eq!(if_stmt.span.end, 45); // Span for "if (false) { int y = 2; } else { int z = 3; }"
@@ -107,7 +119,10 @@ fn test_lower_if_else_statement() {
             assert_eq!(var_decl.var_type, Some("int".to_string()));
             assert_eq!(
                 var_decl.value,
-                Some(Box::new(Expression::Literal(Literal::Integer(2))))
+                Some(Box::new(Expression::Literal(
+                    Literal::Integer(2),
+                    Span { start: 21, end: 22 }
+                )))
             );
         } else {
             panic!(
@@ -126,7 +141,10 @@ fn test_lower_if_else_statement() {
                 assert_eq!(var_decl.var_type, Some("int".to_string()));
                 assert_eq!(
                     var_decl.value,
-                    Some(Box::new(Expression::Literal(Literal::Integer(3))))
+                    Some(Box::new(Expression::Literal(
+                        Literal::Integer(3),
+                        Span { start: 41, end: 42 }
+                    )))
                 );
             } else {
                 panic!(
@@ -139,3 +157,150 @@ fn test_lower_if_else_statement() {
         panic!("Expected IfStatement, got {:?}", result);
     }
 }
+
+#[test]
+fn test_lower_while_statement_with_single_statement_body() {
+    let language = tree_sitter_c_sharp::language();
+    let mut parser = GenericParser::new(language);
+    let code = "while (i > 0) i--;";
+    let tree = parser.parse(code);
+    let root = tree.root_node();
+    let while_node = root.child(0).expect("Code should have a while statement");
+
+    let result = lower_statement(while_node, code.as_bytes());
+
+    if let Statement::WhileLoop(while_loop) = result {
+        assert!(matches!(*while_loop.condition, Expression::BinaryOp(_)));
+        assert_eq!(while_loop.body.statements.len(), 1);
+        if let Statement::ExpressionStatement(expr_stmt) = &while_loop.body.statements[0] {
+            if let Expression::UnaryOp(op) = expr_stmt.expression.as_ref() {
+                assert_eq!(op.operator, UnaryOperator::PostDecrement);
+            } else {
+                panic!("Expected UnaryOp, got {:?}", expr_stmt.expression);
+            }
+        } else {
+            panic!(
+                "Expected ExpressionStatement in while body, got {:?}",
+                while_loop.body.statements[0]
+            );
+        }
+    } else {
+        panic!("Expected WhileLoop, got {:?}", result);
+    }
+}
+
+#[test]
+fn test_lower_for_statement() {
+    let language = tree_sitter_c_sharp::language();
+    let mut parser = GenericParser::new(language);
+    let code = "for (int i = 0; i < 10; i++) { total += i; }";
+    let tree = parser.parse(code);
+    let root = tree.root_node();
+    let for_node = root.child(0).expect("Code should have a for statement");
+
+    let result = lower_statement(for_node, code.as_bytes());
+
+    if let Statement::ForLoop(for_loop) = result {
+        assert_eq!(for_loop.span.start, 0);
+        assert_eq!(for_loop.span.end, 44);
+
+        if let Some(Statement::DeclStmt(decl_stmt)) = for_loop.initializer.as_deref() {
+            assert_eq!(decl_stmt.var_decls[0].name, "i");
+            assert_eq!(decl_stmt.var_decls[0].var_type, Some("int".to_string()));
+        } else {
+            panic!(
+                "Expected DeclStmt initializer, got {:?}",
+                for_loop.initializer
+            );
+        }
+
+        assert!(matches!(
+            for_loop.condition.as_deref(),
+            Some(Expression::BinaryOp(_))
+        ));
+        assert!(matches!(
+            for_loop.update.as_deref(),
+            Some(Expression::UnaryOp(UnaryOp {
+                operator: UnaryOperator::PostIncrement,
+                ..
+            }))
+        ));
+        assert_eq!(for_loop.body.statements.len(), 1);
+    } else {
+        panic!("Expected ForLoop, got {:?}", result);
+    }
+}
+
+#[test]
+fn test_lower_foreach_statement() {
+    let language = tree_sitter_c_sharp::language();
+    let mut parser = GenericParser::new(language);
+    let code = "foreach (var item in items) { total += item; }";
+    let tree = parser.parse(code);
+    let root = tree.root_node();
+    let foreach_node = root.child(0).expect("Code should have a foreach statement");
+
+    let result = lower_statement(foreach_node, code.as_bytes());
+
+    if let Statement::ForEachLoop(foreach_loop) = result {
+        assert_eq!(foreach_loop.span.start, 0);
+        assert_eq!(foreach_loop.span.end, 46);
+        assert_eq!(foreach_loop.variable.name, "item");
+        assert_eq!(foreach_loop.variable.var_type, Some("var".to_string()));
+        assert_eq!(foreach_loop.variable.name_span.start, 13);
+        assert!(foreach_loop.variable.value.is_none());
+        assert!(matches!(
+            foreach_loop.collection.as_ref(),
+            Expression::Identifier(name, _) if name == "items"
+        ));
+        assert_eq!(foreach_loop.body.statements.len(), 1);
+    } else {
+        panic!("Expected ForEachLoop, got {:?}", result);
+    }
+}
+
+#[test]
+fn test_lower_switch_statement() {
+    let language = tree_sitter_c_sharp::language();
+    let mut parser = GenericParser::new(language);
+    let code = "switch (kind) { case 1: case 2: total++; break; case Foo f when f.Ok: break; default: return; }";
+    let tree = parser.parse(code);
+    let root = tree.root_node();
+    let switch_node = root.child(0).expect("Code should have a switch statement");
+
+    let result = lower_statement(switch_node, code.as_bytes());
+
+    if let Statement::SwitchStatement(switch) = result {
+        assert!(matches!(*switch.value, Expression::Identifier(ref name, _) if name == "kind"));
+        assert_eq!(switch.sections.len(), 3);
+
+        let first = &switch.sections[0];
+        assert!(matches!(
+            first.labels.as_slice(),
+            [
+                SwitchLabel::Case(Expression::Literal(Literal::Integer(1), _)),
+                SwitchLabel::Case(Expression::Literal(Literal::Integer(2), _))
+            ]
+        ));
+        assert_eq!(first.body.statements.len(), 2);
+        assert_eq!(
+            &code[first.body.span.start..first.body.span.end],
+            "total++; break;"
+        );
+
+        assert!(matches!(
+            switch.sections[1].labels.as_slice(),
+            [SwitchLabel::Case(Expression::Raw { source, .. })] if source == "Foo f when f.Ok"
+        ));
+        assert!(matches!(
+            switch.sections[2].labels.as_slice(),
+            [SwitchLabel::Default(_)]
+        ));
+        assert!(matches!(
+            switch.sections[2].body.statements.as_slice(),
+            [Statement::ReturnStatement(_)]
+        ));
+    } else {
+        panic!("Expected SwitchStatement, got {:?}", result);
+    }
+}
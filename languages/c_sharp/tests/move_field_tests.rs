@@ -0,0 +1,178 @@
+mod common;
+
+use common::{refactor_file, refactor_files};
+use core::{MoveField, RefactorError};
+
+#[test]
+fn test_move_field_through_field() {
+    let source = r#"public class Customer {
+    private CustomerContract contract;
+    /// The share taken off every order.
+    private double discountRate;
+
+    public double Discount(double amount) {
+        return amount * discountRate;
+    }
+
+    public void Raise() {
+        this.discountRate += 0.1;
+    }
+}
+
+public class CustomerContract {
+    public DateTime Start { get; set; }
+}"#;
+    let expected = r#"public class Customer {
+    private CustomerContract contract;
+
+    public double Discount(double amount) {
+        return amount * contract.discountRate;
+    }
+
+    public void Raise() {
+        this.contract.discountRate += 0.1;
+    }
+}
+
+public class CustomerContract {
+    public DateTime Start { get; set; }
+
+    /// The share taken off every order.
+    internal double discountRate;
+}"#;
+    let result = refactor_file(
+        source,
+        &MoveField::new("Customer", "discountRate", "CustomerContract"),
+    )
+    .unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_move_field_along_path_across_files() {
+    let order = r#"public class Order {
+    private Customer customer;
+    public string Region;
+
+    public bool Domestic() {
+        var customer = Region;
+        return customer == "US";
+    }
+}"#;
+    let customer = r#"public class Customer {
+    private Contract Contract { get; set; }
+}"#;
+    let contract = r#"public class Contract {
+}"#;
+    let report = r#"public class Report {
+    public string Describe(Order order) {
+        return order.Region;
+    }
+}"#;
+    let result = refactor_files(
+        &[
+            ("Order.cs", order),
+            ("Customer.cs", customer),
+            ("Contract.cs", contract),
+            ("Report.cs", report),
+        ],
+        &MoveField::new("Order", "Region", "Contract"),
+    )
+    .unwrap();
+    assert_eq!(
+        result[0],
+        r#"public class Order {
+    internal Customer customer;
+
+    public bool Domestic() {
+        var customer = this.customer.Contract.Region;
+        return customer == "US";
+    }
+}"#
+    );
+    assert_eq!(
+        result[1],
+        r#"public class Customer {
+    internal Contract Contract { get; set; }
+}"#
+    );
+    assert_eq!(
+        result[2],
+        r#"public class Contract {
+    public string Region;
+}"#
+    );
+    assert_eq!(
+        result[3],
+        r#"public class Report {
+    public string Describe(Order order) {
+        return order.customer.Contract.Region;
+    }
+}"#
+    );
+}
+
+#[test]
+fn test_move_field_refuses_name_taken_in_target() {
+    let source = r#"public class Customer {
+    private Contract contract;
+    private double rate;
+}
+
+public class Contract {
+    public double rate;
+}"#;
+    let result = refactor_file(source, &MoveField::new("Customer", "rate", "Contract"));
+    assert!(matches!(result, Err(RefactorError::Conflict(_))));
+}
+
+#[test]
+fn test_move_field_refuses_unreachable_target() {
+    let source = r#"public class Customer {
+    private double rate;
+}
+
+public class Contract {
+    public DateTime Start;
+}"#;
+    let result = refactor_file(source, &MoveField::new("Customer", "rate", "Contract"));
+    assert!(
+        matches!(result, Err(RefactorError::Unsupported(msg)) if msg.contains("cannot be reached"))
+    );
+}
+
+#[test]
+fn test_move_field_refuses_ambiguous_path() {
+    let source = r#"public class Transfer {
+    private Account from;
+    private Account to;
+    private decimal fee;
+}
+
+public class Account {
+    public decimal Balance;
+}"#;
+    let result = refactor_file(source, &MoveField::new("Transfer", "fee", "Account"));
+    assert!(
+        matches!(result, Err(RefactorError::Unsupported(msg)) if msg.contains("`from` and `to`"))
+    );
+}
+
+#[test]
+fn test_move_field_refuses_use_in_constructor() {
+    let source = r#"public class Customer {
+    private Contract contract;
+    private double rate;
+
+    public Customer(double rate) {
+        this.rate = rate;
+    }
+}
+
+public class Contract {
+}"#;
+    let result = refactor_file(source, &MoveField::new("Customer", "rate", "Contract"));
+    assert!(
+        matches!(result, Err(RefactorError::Unsupported(msg)) if msg.contains("constructor of `Customer`"))
+    );
+}
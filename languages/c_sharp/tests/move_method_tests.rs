@@ -0,0 +1,219 @@
+mod common;
+
+use common::{refactor_file, refactor_files};
+use core::{MoveMethod, RefactorError};
+
+#[test]
+fn test_move_method_to_parameter_type() {
+    let source = r#"public class Account {
+    private AccountType type;
+    private double overdraftFee;
+
+    public double OverdraftCharge(AccountType type, int daysOverdrawn) {
+        if (type.IsPremium) {
+            return 10 + (daysOverdrawn - 7) * overdraftFee;
+        }
+        return daysOverdrawn * 1.75;
+    }
+
+    public double BankCharge(int days) {
+        return 4.5 + OverdraftCharge(type, days);
+    }
+}
+
+public class AccountType {
+    public bool IsPremium { get; set; }
+}"#;
+    let expected = r#"public class Account {
+    private AccountType type;
+    internal double overdraftFee;
+
+    public double BankCharge(int days) {
+        return 4.5 + type.OverdraftCharge(days, this);
+    }
+}
+
+public class AccountType {
+    public bool IsPremium { get; set; }
+
+    public double OverdraftCharge(int daysOverdrawn, Account account) {
+        if (IsPremium) {
+            return 10 + (daysOverdrawn - 7) * account.overdraftFee;
+        }
+        return daysOverdrawn * 1.75;
+    }
+}"#;
+    let refactoring = MoveMethod::new("Account", "OverdraftCharge", "AccountType", false);
+    let result = refactor_file(source, &refactoring).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_move_method_through_field() {
+    let source = r#"public class Order {
+    private Customer customer;
+    private decimal total;
+
+    /// The discount the customer gets on this order.
+    public decimal Discount() {
+        return customer.Rate * total;
+    }
+
+    public decimal Net() {
+        return total - Discount();
+    }
+}
+
+public class Customer {
+    public decimal Rate { get; set; }
+}"#;
+    let expected = r#"public class Order {
+    private Customer customer;
+    internal decimal total;
+
+    public decimal Net() {
+        return total - customer.Discount(this);
+    }
+}
+
+public class Customer {
+    public decimal Rate { get; set; }
+
+    /// The discount the customer gets on this order.
+    public decimal Discount(Order order) {
+        return Rate * order.total;
+    }
+}"#;
+    let refactoring = MoveMethod::new("Order", "Discount", "Customer", false);
+    let result = refactor_file(source, &refactoring).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_move_method_leaves_delegating_stub() {
+    let source = r#"public class Order {
+    private Customer customer;
+
+    public string Greeting(string prefix) {
+        return prefix + " " + customer.Name;
+    }
+}
+
+public class Customer {
+    public string Name { get; set; }
+}"#;
+    let expected = r#"public class Order {
+    private Customer customer;
+
+    public string Greeting(string prefix) {
+        return customer.Greeting(prefix);
+    }
+}
+
+public class Customer {
+    public string Name { get; set; }
+
+    public string Greeting(string prefix) {
+        return prefix + " " + Name;
+    }
+}"#;
+    let refactoring = MoveMethod::new("Order", "Greeting", "Customer", true);
+    let result = refactor_file(source, &refactoring).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_move_method_across_files() {
+    let order = r#"public class Order {
+    public decimal Shipping(Address address, decimal weight) {
+        if (address.Country == "US") {
+            return weight * 2;
+        }
+        return weight * 5;
+    }
+}"#;
+    let address = r#"public class Address {
+    public string Country { get; set; }
+}"#;
+    let checkout = r#"public class Checkout {
+    public decimal Quote(Order order, Address address) {
+        return order.Shipping(address, 3);
+    }
+}"#;
+    let refactoring = MoveMethod::new("Order", "Shipping", "Address", false);
+    let result = refactor_files(
+        &[
+            ("Order.cs", order),
+            ("Address.cs", address),
+            ("Checkout.cs", checkout),
+        ],
+        &refactoring,
+    )
+    .unwrap();
+    assert_eq!(
+        result[0],
+        r#"public class Order {
+}"#
+    );
+    assert_eq!(
+        result[1],
+        r#"public class Address {
+    public string Country { get; set; }
+
+    public decimal Shipping(decimal weight) {
+        if (Country == "US") {
+            return weight * 2;
+        }
+        return weight * 5;
+    }
+}"#
+    );
+    assert_eq!(
+        result[2],
+        r#"public class Checkout {
+    public decimal Quote(Order order, Address address) {
+        return address.Shipping(3);
+    }
+}"#
+    );
+}
+
+#[test]
+fn test_move_method_refuses_name_taken_in_target() {
+    let source = r#"public class Order {
+    private Customer customer;
+
+    public string Label() {
+        return customer.Name;
+    }
+}
+
+public class Customer {
+    public string Name { get; set; }
+
+    public string Label() {
+        return Name;
+    }
+}"#;
+    let refactoring = MoveMethod::new("Order", "Label", "Customer", false);
+    let result = refactor_file(source, &refactoring);
+    assert!(matches!(result, Err(RefactorError::Conflict(_))));
+}
+
+#[test]
+fn test_move_method_refuses_unreachable_target() {
+    let source = r#"public class Order {
+    private decimal total;
+
+    public decimal Tax() {
+        return total * 0.2m;
+    }
+}
+
+public class Customer {
+    public string Name { get; set; }
+}"#;
+    let refactoring = MoveMethod::new("Order", "Tax", "Customer", false);
+    let result = refactor_file(source, &refactoring);
+    assert!(matches!(result, Err(RefactorError::Unsupported(msg)) if msg.contains("no field")));
+}
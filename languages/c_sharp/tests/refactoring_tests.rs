@@ -1,27 +1,12 @@
-use c_sharp::lower_top_level;
-use core::{apply_refactoring, Refactoring, RenameVariable};
-use tree_sitter::Parser;
+mod common;
 
-fn run_test(source_code: &str, old_name: &str, new_name: &str, expected_code: &str) {
-    let mut parser = Parser::new();
-    parser
-        .set_language(tree_sitter_c_sharp::language())
-        .expect("Error loading C# grammar");
-    let tree = parser.parse(source_code, None).unwrap();
-    let root = tree.root_node();
+use common::{refactor, span_of};
+use core::{RefactorError, RenameVariable};
 
-    // Find class declaration - our lower_top_level expects a class or similar
-    let mut cursor = root.walk();
-    let class_node = root
-        .children(&mut cursor)
-        .find(|n| n.kind() == "class_declaration")
-        .expect("No class declaration found in test source");
-
-    let uast = lower_top_level(class_node, source_code.as_bytes());
-
-    let refactoring = RenameVariable::new(old_name, new_name);
-    let edits = refactoring.apply(&uast);
-    let new_code = apply_refactoring(source_code, edits);
+/// Rename the variable at the first occurrence of `at` to `new_name`.
+fn run_test(source_code: &str, at: &str, new_name: &str, expected_code: &str) {
+    let (offset, _) = span_of(source_code, at);
+    let new_code = refactor(source_code, &RenameVariable::new(offset, new_name)).unwrap();
 
     assert_eq!(new_code, expected_code);
 }
@@ -38,7 +23,7 @@ fn test_rename_parameter() {
         return b + 5;
     }
 }"#;
-    run_test(source, "a", "b", expected);
+    run_test(source, "a)", "b", expected);
 }
 
 #[test]
@@ -63,7 +48,7 @@ fn test_rename_local_variable() {
         }
     }
 }"#;
-    run_test(source, "x", "y", expected);
+    run_test(source, "x =", "y", expected);
 }
 
 #[test]
@@ -78,7 +63,7 @@ fn test_rename_variable_in_invocation() {
         Console.WriteLine(newValue);
     }
 }"#;
-    run_test(source, "val", "newValue", expected);
+    run_test(source, "val)", "newValue", expected);
 }
 
 #[test]
@@ -87,11 +72,81 @@ fn test_rename_object_in_member_access() {
     public void Run() {
         Console.WriteLine("Hello");
     }
+}"#;
+    let (offset, _) = span_of(source, "Console");
+    let result = refactor(source, &RenameVariable::new(offset, "MyConsole"));
+
+    assert!(matches!(result, Err(RefactorError::InvalidSelection(_))));
+}
+
+#[test]
+fn test_rename_leaves_other_methods_alone() {
+    let source = r#"public class Test {
+    public int First(int count) {
+        return count + 1;
+    }
+
+    public int Second(int count) {
+        int total = count * 2;
+        return total;
+    }
 }"#;
     let expected = r#"public class Test {
-    public void Run() {
-        MyConsole.WriteLine("Hello");
+    public int First(int count) {
+        return count + 1;
+    }
+
+    public int Second(int amount) {
+        int total = amount * 2;
+        return total;
+    }
+}"#;
+    run_test(source, "count * 2", "amount", expected);
+}
+
+#[test]
+fn test_rename_respects_sibling_scopes() {
+    let source = r#"public class Test {
+    public void Run(bool flag) {
+        if (flag) {
+            int item = 1;
+            Console.WriteLine(item);
+        } else {
+            int item = 2;
+            Console.WriteLine(item);
+        }
+        for (int i = 0; i < 3; i++) {
+            Console.WriteLine(i);
+        }
+    }
+}"#;
+    let expected = r#"public class Test {
+    public void Run(bool flag) {
+        if (flag) {
+            int item = 1;
+            Console.WriteLine(item);
+        } else {
+            int other = 2;
+            Console.WriteLine(other);
+        }
+        for (int i = 0; i < 3; i++) {
+            Console.WriteLine(i);
+        }
     }
 }"#;
-    run_test(source, "Console", "MyConsole", expected);
+    run_test(source, "item = 2", "other", expected);
+}
+
+#[test]
+fn test_rename_refuses_name_already_in_use() {
+    let source = r#"public class Test {
+    public int Run(int a) {
+        int b = a + 1;
+        return b;
+    }
+}"#;
+    let (offset, _) = span_of(source, "a)");
+    let result = refactor(source, &RenameVariable::new(offset, "b"));
+
+    assert!(matches!(result, Err(RefactorError::Conflict(_))));
 }
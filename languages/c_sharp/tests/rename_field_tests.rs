@@ -0,0 +1,131 @@
+mod common;
+
+use common::refactor;
+use core::{RefactorError, RenameField};
+
+#[test]
+fn test_rename_field_updates_bare_and_qualified_references() {
+    let source = r#"public class Account {
+    private int balance = 0;
+    public int Balance { get { return balance; } }
+
+    public void Deposit(int amount) {
+        balance = balance + amount;
+        this.balance = this.balance;
+    }
+
+    public void Merge(Account other) {
+        balance = balance + other.balance;
+    }
+
+    public int Audit() {
+        int balance = 5;
+        return balance;
+    }
+}"#;
+    let expected = r#"public class Account {
+    private int total = 0;
+    public int Balance { get { return total; } }
+
+    public void Deposit(int amount) {
+        total = total + amount;
+        this.total = this.total;
+    }
+
+    public void Merge(Account other) {
+        total = total + other.total;
+    }
+
+    public int Audit() {
+        int balance = 5;
+        return balance;
+    }
+}"#;
+    let result = refactor(source, &RenameField::new("balance", "total")).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_rename_property() {
+    let source = r#"public class Order {
+    public int Count { get; set; }
+    public int Twice => Count * 2;
+
+    public void Add(Order other, List<int> items) {
+        Count = other.Count + items.Count;
+    }
+}"#;
+    let expected = r#"public class Order {
+    public int Quantity { get; set; }
+    public int Twice => Quantity * 2;
+
+    public void Add(Order other, List<int> items) {
+        Quantity = other.Quantity + items.Count;
+    }
+}"#;
+    let result = refactor(source, &RenameField::new("Count", "Quantity")).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_rename_field_refuses_existing_member() {
+    let source = r#"public class Account {
+    private int balance;
+
+    public int total() {
+        return balance;
+    }
+}"#;
+    let result = refactor(source, &RenameField::new("balance", "total"));
+    assert!(matches!(result, Err(RefactorError::Conflict(_))));
+}
+
+#[test]
+fn test_rename_field_refuses_capture_by_local() {
+    let source = r#"public class Account {
+    private int balance;
+
+    public int Audit() {
+        int total = 1;
+        return balance + total;
+    }
+}"#;
+    let result = refactor(source, &RenameField::new("balance", "total"));
+    assert!(matches!(result, Err(RefactorError::Conflict(_))));
+}
+
+#[test]
+fn test_rename_field_in_constructor() {
+    let source = r#"public class Counter {
+    private int count;
+
+    public Counter(int start) {
+        count = start;
+    }
+}"#;
+    let expected = r#"public class Counter {
+    private int total;
+
+    public Counter(int start) {
+        total = start;
+    }
+}"#;
+    let result = refactor(source, &RenameField::new("count", "total")).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_rename_field_refuses_chained_constructor() {
+    let source = r#"public class Counter {
+    private int count;
+
+    public Counter() : this(count) {
+    }
+
+    public Counter(int start) {
+        count = start;
+    }
+}"#;
+    let result = refactor(source, &RenameField::new("count", "total"));
+    assert!(matches!(result, Err(RefactorError::Unsupported(_))));
+}
@@ -0,0 +1,197 @@
+mod common;
+
+use common::{refactor, refactor_file};
+use core::{RefactorError, RenameMethod};
+
+#[test]
+fn test_rename_updates_every_kind_of_call() {
+    let source = r#"public class Order {
+    public int Total(int price) {
+        return price * 2;
+    }
+
+    public void Print(Order other) {
+        int own = Total(1) + this.Total(2);
+        Console.WriteLine(other.Total(own));
+        var copy = new Order();
+        Func<int, int> total = Total;
+        Console.WriteLine(copy.Total(3));
+    }
+}"#;
+    let expected = r#"public class Order {
+    public int ComputeTotal(int price) {
+        return price * 2;
+    }
+
+    public void Print(Order other) {
+        int own = ComputeTotal(1) + this.ComputeTotal(2);
+        Console.WriteLine(other.ComputeTotal(own));
+        var copy = new Order();
+        Func<int, int> total = ComputeTotal;
+        Console.WriteLine(copy.ComputeTotal(3));
+    }
+}"#;
+    let result = refactor(source, &RenameMethod::new("Total", "ComputeTotal")).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_rename_leaves_calls_on_other_types_alone() {
+    let source = r#"public class Order {
+    public void Save() {
+    }
+
+    public void Flush(Store store) {
+        Save();
+        store.Save();
+    }
+}"#;
+    let expected = r#"public class Order {
+    public void Persist() {
+    }
+
+    public void Flush(Store store) {
+        Persist();
+        store.Save();
+    }
+}"#;
+    let result = refactor(source, &RenameMethod::new("Save", "Persist")).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_rename_renames_overloads_together() {
+    let source = r#"public class Log {
+    public void Write(string text) {
+        Write(text, 0);
+    }
+
+    public void Write(string text, int level) {
+    }
+}"#;
+    let expected = r#"public class Log {
+    public void Append(string text) {
+        Append(text, 0);
+    }
+
+    public void Append(string text, int level) {
+    }
+}"#;
+    let result = refactor(source, &RenameMethod::new("Write", "Append")).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_rename_refuses_existing_method_with_same_arity() {
+    let source = r#"public class Log {
+    public void Write(string text) {
+    }
+
+    public void Append(string line) {
+    }
+}"#;
+    let result = refactor(source, &RenameMethod::new("Write", "Append"));
+    assert!(matches!(result, Err(RefactorError::Conflict(_))));
+}
+
+#[test]
+fn test_rename_refuses_existing_field_or_property() {
+    for member in ["private int Total;", "public int Total { get; set; }"] {
+        let source = format!(
+            r#"public class Invoice {{
+    {}
+
+    public int Sum() {{
+        return 0;
+    }}
+}}"#,
+            member
+        );
+        let result = refactor(&source, &RenameMethod::new("Sum", "Total"));
+        assert!(
+            matches!(result, Err(RefactorError::Conflict(_))),
+            "{}",
+            member
+        );
+    }
+}
+
+#[test]
+fn test_rename_allows_new_overload_with_different_arity() {
+    let source = r#"public class Log {
+    public void Write(string text) {
+    }
+
+    public void Append() {
+    }
+}"#;
+    let expected = r#"public class Log {
+    public void Append(string text) {
+    }
+
+    public void Append() {
+    }
+}"#;
+    let result = refactor(source, &RenameMethod::new("Write", "Append")).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_rename_refuses_receiver_of_unknown_type() {
+    let source = r#"public class Log {
+    public void Write() {
+    }
+
+    public void Run() {
+        GetLog().Write();
+    }
+}"#;
+    let result = refactor(source, &RenameMethod::new("Write", "Append"));
+    assert!(matches!(result, Err(RefactorError::Unsupported(_))));
+}
+
+#[test]
+fn test_rename_leaves_same_named_method_of_other_class_alone() {
+    let source = r#"public class Order {
+    public int Total() {
+        return 1;
+    }
+
+    public int Twice() {
+        return Total() + this.Total();
+    }
+}
+
+public class Invoice {
+    public int Total() {
+        return 2;
+    }
+
+    public int Twice() {
+        Func<int> total = Total;
+        return Total() + this.Total();
+    }
+}"#;
+    let expected = r#"public class Order {
+    public int Sum() {
+        return 1;
+    }
+
+    public int Twice() {
+        return Sum() + this.Sum();
+    }
+}
+
+public class Invoice {
+    public int Total() {
+        return 2;
+    }
+
+    public int Twice() {
+        Func<int> total = Total;
+        return Total() + this.Total();
+    }
+}"#;
+    let result = refactor_file(source, &RenameMethod::new("Total", "Sum")).unwrap();
+    assert_eq!(result, expected);
+}
@@ -0,0 +1,180 @@
+mod common;
+
+use common::refactor;
+use core::{RefactorError, ReplaceConditionalWithPolymorphism};
+
+#[test]
+fn test_switch_on_type_code_becomes_subclasses() {
+    let source = r#"public class Bird {
+    private BirdType type;
+    private double voltage;
+
+    public double Speed() {
+        switch (type) {
+            case BirdType.European:
+                return 10;
+            case BirdType.African:
+                return 10 - voltage;
+            default:
+                throw new ArgumentException("unknown bird");
+        }
+    }
+}"#;
+    let expected = r#"public abstract class Bird {
+    private BirdType type;
+    protected double voltage;
+
+    public abstract double Speed();
+}
+
+public class EuropeanBird : Bird {
+    public override double Speed() {
+        return 10;
+    }
+}
+
+public class AfricanBird : Bird {
+    public override double Speed() {
+        return 10 - voltage;
+    }
+}"#;
+    let result = refactor(source, &ReplaceConditionalWithPolymorphism::new("Speed")).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_if_chain_on_string_type_code() {
+    let source = r#"public class Employee {
+    private string kind;
+
+    public int Bonus(int salary) {
+        if (kind == "engineer") {
+            int bonus = salary / 10;
+            return bonus;
+        } else if (this.kind == "sales manager") {
+            return salary / 5;
+        } else {
+            throw new InvalidOperationException();
+        }
+    }
+}"#;
+    let expected = r#"public abstract class Employee {
+    private string kind;
+
+    public abstract int Bonus(int salary);
+}
+
+public class EngineerEmployee : Employee {
+    public override int Bonus(int salary) {
+        int bonus = salary / 10;
+        return bonus;
+    }
+}
+
+public class SalesManagerEmployee : Employee {
+    public override int Bonus(int salary) {
+        return salary / 5;
+    }
+}"#;
+    let result = refactor(source, &ReplaceConditionalWithPolymorphism::new("Bonus")).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_default_branch_stays_as_virtual_method() {
+    let source = r#"public class Shape {
+    private ShapeKind kind;
+
+    public void Draw() {
+        switch (kind) {
+            case ShapeKind.Circle:
+            case ShapeKind.Ellipse:
+                DrawCurve();
+                break;
+            default:
+                DrawLines();
+                break;
+        }
+    }
+}"#;
+    let expected = r#"public class Shape {
+    private ShapeKind kind;
+
+    public virtual void Draw() {
+        DrawLines();
+    }
+}
+
+public class CircleShape : Shape {
+    public override void Draw() {
+        DrawCurve();
+    }
+}
+
+public class EllipseShape : Shape {
+    public override void Draw() {
+        DrawCurve();
+    }
+}"#;
+    let result = refactor(source, &ReplaceConditionalWithPolymorphism::new("Draw")).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_refuse_dispatch_on_parameter() {
+    let source = r#"public class Bird {
+    public double Speed(BirdType type) {
+        switch (type) {
+            case BirdType.European:
+                return 10;
+            default:
+                return 0;
+        }
+    }
+}"#;
+    let result = refactor(source, &ReplaceConditionalWithPolymorphism::new("Speed"));
+    assert!(matches!(result, Err(RefactorError::Unsupported(_))));
+}
+
+#[test]
+fn test_refuse_existing_subclass_name() {
+    let source = r#"public class Bird {
+    private BirdType type;
+
+    public double Speed() {
+        switch (type) {
+            case BirdType.European:
+                return 10;
+            default:
+                return 0;
+        }
+    }
+
+    private class EuropeanBird {
+    }
+}"#;
+    let result = refactor(source, &ReplaceConditionalWithPolymorphism::new("Speed"));
+    assert!(matches!(result, Err(RefactorError::Conflict(_))));
+}
+
+#[test]
+fn test_refuse_class_with_constructor() {
+    let source = r#"public class Bird {
+    private BirdType type;
+
+    public Bird(BirdType type) {
+        this.type = type;
+    }
+
+    public double Speed() {
+        switch (type) {
+            case BirdType.European:
+                return 10;
+            default:
+                return 0;
+        }
+    }
+}"#;
+    let result = refactor(source, &ReplaceConditionalWithPolymorphism::new("Speed"));
+    assert!(matches!(result, Err(RefactorError::Unsupported(_))));
+}
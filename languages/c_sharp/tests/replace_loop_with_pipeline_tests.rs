@@ -0,0 +1,196 @@
+mod common;
+
+use common::{refactor, span_of};
+use core::{RefactorError, ReplaceLoopWithPipeline};
+
+fn replace(source: &str, at: &str) -> Result<String, RefactorError> {
+    let (offset, _) = span_of(source, at);
+    refactor(source, &ReplaceLoopWithPipeline::new(offset))
+}
+
+#[test]
+fn test_replace_filter_and_map_into_new_list() {
+    let source = r#"using System;
+using System.Collections.Generic;
+
+public class Directory {
+    public List<string> IndiaPhones(List<Office> offices) {
+        var phones = new List<string>();
+        foreach (var office in offices) {
+            if (office.Country == "India") {
+                phones.Add(office.Phone);
+            }
+        }
+        return phones;
+    }
+}"#;
+    let expected = r#"using System;
+using System.Collections.Generic;
+using System.Linq;
+
+public class Directory {
+    public List<string> IndiaPhones(List<Office> offices) {
+        var phones = offices.Where(office => office.Country == "India").Select(office => office.Phone).ToList();
+        return phones;
+    }
+}"#;
+    let result = replace(source, "foreach").unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_replace_continue_filter_and_local_with_sum() {
+    let source = r#"using System.Linq;
+
+public class Orders {
+    public decimal Revenue(Order[] orders) {
+        decimal total = 0;
+        foreach (var order in orders) {
+            if (order.Cancelled) continue;
+            decimal net = order.Price - order.Discount;
+            total += net;
+        }
+        return total;
+    }
+}"#;
+    let expected = r#"using System.Linq;
+
+public class Orders {
+    public decimal Revenue(Order[] orders) {
+        decimal total = orders.Where(order => !order.Cancelled).Select(order => order.Price - order.Discount).Sum();
+        return total;
+    }
+}"#;
+    let result = replace(source, "foreach").unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_replace_counting_for_loop() {
+    let source = r#"public class Stats {
+    public int PositiveTotal(int[] values) {
+        int total = 0;
+        for (int i = 0; i < values.Length; i++) {
+            if (values[i] > 0) {
+                total += values[i];
+            }
+        }
+        return total;
+    }
+}"#;
+    let expected = r#"using System.Linq;
+
+public class Stats {
+    public int PositiveTotal(int[] values) {
+        int total = values.Where(item => item > 0).Sum();
+        return total;
+    }
+}"#;
+    let result = replace(source, "for (").unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_replace_into_existing_accumulators() {
+    let source = r#"using System.Linq;
+
+public class Report {
+    public void Collect(List<Line> lines, List<string> names, int[] quantities, int count) {
+        foreach (var line in lines) {
+            names.Add(line.Name.Trim());
+        }
+        foreach (var quantity in quantities) {
+            count += quantity * 2;
+        }
+    }
+}"#;
+    let expected = r#"using System.Linq;
+
+public class Report {
+    public void Collect(List<Line> lines, List<string> names, int[] quantities, int count) {
+        names.AddRange(lines.Select(line => line.Name.Trim()));
+        count += quantities.Sum(quantity => quantity * 2);
+    }
+}"#;
+    let once = replace(source, "foreach").unwrap();
+    let (second, _) = span_of(&once, "foreach");
+    let result = refactor(&once, &ReplaceLoopWithPipeline::new(second)).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_replace_refuses_earlier_item_after_map() {
+    let source = r#"public class Directory {
+    public List<string> Labels(List<Office> offices) {
+        var labels = new List<string>();
+        foreach (var office in offices) {
+            var phone = office.Phone;
+            labels.Add(office.Name + phone);
+        }
+        return labels;
+    }
+}"#;
+    let result = replace(source, "foreach");
+    assert!(matches!(result, Err(RefactorError::Unsupported(msg)) if msg.contains("`office`")));
+}
+
+#[test]
+fn test_replace_refuses_index_used_as_value() {
+    let source = r#"public class Stats {
+    public int Weighted(int[] values) {
+        int total = 0;
+        for (int i = 0; i < values.Length; i++) {
+            total += values[i] * i;
+        }
+        return total;
+    }
+}"#;
+    let result = replace(source, "for (");
+    assert!(matches!(result, Err(RefactorError::Unsupported(msg)) if msg.contains("`i`")));
+}
+
+#[test]
+fn test_replace_refuses_other_loop_shapes() {
+    let source = r#"public class Stats {
+    public int Total(int[] values) {
+        int total = 0;
+        foreach (var v in values) {
+            Console.WriteLine(v);
+            total += v;
+        }
+        return total;
+    }
+}"#;
+    let result = replace(source, "foreach");
+    assert!(matches!(result, Err(RefactorError::Unsupported(_))));
+}
+
+#[test]
+fn test_replace_refuses_string_total() {
+    let source = r#"public class Text {
+    public string Join(List<string> words) {
+        string text = "";
+        foreach (var word in words) {
+            text += word;
+        }
+        return text;
+    }
+}"#;
+    let result = replace(source, "foreach");
+    assert!(matches!(result, Err(RefactorError::Unsupported(msg)) if msg.contains("`text`")));
+}
+
+#[test]
+fn test_replace_refuses_total_wider_than_items() {
+    let source = r#"public class Stats {
+    public long Total(List<int> values) {
+        long total = 0;
+        foreach (var value in values) {
+            total += value;
+        }
+        return total;
+    }
+}"#;
+    let result = replace(source, "foreach");
+    assert!(matches!(result, Err(RefactorError::Unsupported(msg)) if msg.contains("`int`")));
+}
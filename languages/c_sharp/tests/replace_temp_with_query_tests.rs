@@ -0,0 +1,174 @@
+mod common;
+
+use common::{refactor, span_of};
+use core::{RefactorError, ReplaceTempWithQuery};
+
+#[test]
+fn test_replace_temp_with_method() {
+    let source = r#"public class Order {
+    private int quantity;
+    private double itemPrice;
+
+    public double Price(double discount) {
+        double basePrice = quantity * itemPrice * discount;
+        if (basePrice > 1000) {
+            return basePrice * 0.95;
+        }
+        return basePrice * 0.98;
+    }
+}"#;
+    let expected = r#"public class Order {
+    private int quantity;
+    private double itemPrice;
+
+    public double Price(double discount) {
+        if (BasePrice(discount) > 1000) {
+            return BasePrice(discount) * 0.95;
+        }
+        return BasePrice(discount) * 0.98;
+    }
+
+    private double BasePrice(double discount) {
+        return quantity * itemPrice * discount;
+    }
+}"#;
+    let (offset, _) = span_of(source, "basePrice");
+    let result = refactor(
+        source,
+        &ReplaceTempWithQuery::new(offset, "BasePrice", false),
+    )
+    .unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_replace_temp_with_property() {
+    let source = r#"public class Order {
+    private int quantity;
+    private double itemPrice;
+
+    public double Price() {
+        double basePrice = quantity * itemPrice;
+        return basePrice - basePrice / 10;
+    }
+}"#;
+    let expected = r#"public class Order {
+    private int quantity;
+    private double itemPrice;
+
+    public double Price() {
+        return BasePrice - BasePrice / 10;
+    }
+
+    private double BasePrice => quantity * itemPrice;
+}"#;
+    let (offset, _) = span_of(source, "basePrice");
+    let result = refactor(
+        source,
+        &ReplaceTempWithQuery::new(offset, "BasePrice", true),
+    )
+    .unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_refuse_initializer_reading_local() {
+    let source = r#"public class Order {
+    private double itemPrice;
+
+    public double Price(int count) {
+        int quantity = count * 2;
+        double basePrice = quantity * itemPrice;
+        return basePrice;
+    }
+}"#;
+    let (offset, _) = span_of(source, "basePrice");
+    let result = refactor(
+        source,
+        &ReplaceTempWithQuery::new(offset, "BasePrice", false),
+    );
+    assert!(matches!(result, Err(RefactorError::Unsupported(_))));
+}
+
+#[test]
+fn test_refuse_input_changed_before_use() {
+    let source = r#"public class Order {
+    private int quantity;
+    private double itemPrice;
+
+    public double Price() {
+        double basePrice = quantity * itemPrice;
+        quantity = 0;
+        return basePrice;
+    }
+}"#;
+    let (offset, _) = span_of(source, "basePrice");
+    let result = refactor(
+        source,
+        &ReplaceTempWithQuery::new(offset, "BasePrice", false),
+    );
+    assert!(matches!(result, Err(RefactorError::Unsupported(_))));
+}
+
+#[test]
+fn test_refuse_property_with_parameters() {
+    let source = r#"public class Order {
+    private double itemPrice;
+
+    public double Price(int quantity) {
+        double basePrice = quantity * itemPrice;
+        return basePrice;
+    }
+}"#;
+    let (offset, _) = span_of(source, "basePrice");
+    let result = refactor(
+        source,
+        &ReplaceTempWithQuery::new(offset, "BasePrice", true),
+    );
+    assert!(matches!(result, Err(RefactorError::Unsupported(_))));
+}
+
+#[test]
+fn test_refuse_existing_member() {
+    let source = r#"public class Order {
+    private double itemPrice;
+    private double BasePrice;
+
+    public double Price() {
+        double basePrice = itemPrice * 2;
+        return basePrice;
+    }
+}"#;
+    let (offset, _) = span_of(source, "basePrice");
+    let result = refactor(
+        source,
+        &ReplaceTempWithQuery::new(offset, "BasePrice", false),
+    );
+    assert!(matches!(result, Err(RefactorError::Conflict(_))));
+}
+
+#[test]
+fn test_refuse_call_between_declaration_and_use() {
+    let source = r#"public class Order {
+    private int quantity;
+    private double itemPrice;
+
+    public double Price() {
+        double basePrice = quantity * itemPrice;
+        Bump();
+        return basePrice;
+    }
+
+    private void Bump() {
+        quantity++;
+    }
+}"#;
+    let (offset, _) = span_of(source, "basePrice");
+    let result = refactor(
+        source,
+        &ReplaceTempWithQuery::new(offset, "BasePrice", false),
+    );
+    assert!(
+        matches!(result, Err(RefactorError::Unsupported(msg)) if msg.contains("changes before"))
+    );
+}
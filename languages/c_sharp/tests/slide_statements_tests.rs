@@ -0,0 +1,116 @@
+mod common;
+
+use common::{refactor, span_of};
+use core::{RefactorError, SlideStatements};
+
+const SOURCE: &str = r#"public class Summary {
+    private int total;
+
+    public int Summarize(int[] items) {
+        string label = "items";
+        int count = items.Length;
+        int doubled = count * 2;
+        Console.WriteLine(label);
+        total = doubled;
+        return doubled;
+    }
+}"#;
+
+fn slide(statements: &str, target: &str) -> Result<String, RefactorError> {
+    let (start, end) = span_of(SOURCE, statements);
+    let (target, _) = span_of(SOURCE, target);
+    refactor(SOURCE, &SlideStatements::new(start, end, target))
+}
+
+#[test]
+fn test_slide_declaration_down_to_its_use() {
+    let expected = r#"public class Summary {
+    private int total;
+
+    public int Summarize(int[] items) {
+        int count = items.Length;
+        int doubled = count * 2;
+        string label = "items";
+        Console.WriteLine(label);
+        total = doubled;
+        return doubled;
+    }
+}"#;
+    let result = slide(r#"string label = "items";"#, "int doubled").unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_slide_several_statements_up() {
+    let expected = r#"public class Summary {
+    private int total;
+
+    public int Summarize(int[] items) {
+        int count = items.Length;
+        int doubled = count * 2;
+        string label = "items";
+        Console.WriteLine(label);
+        total = doubled;
+        return doubled;
+    }
+}"#;
+    let result = slide(
+        "int count = items.Length;\n        int doubled = count * 2;",
+        "string label",
+    )
+    .unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_slide_call_past_local_computation() {
+    let expected = r#"public class Summary {
+    private int total;
+
+    public int Summarize(int[] items) {
+        string label = "items";
+        int count = items.Length;
+        Console.WriteLine(label);
+        int doubled = count * 2;
+        total = doubled;
+        return doubled;
+    }
+}"#;
+    let result = slide("Console.WriteLine(label);", "int doubled").unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_refuse_crossing_write_of_read_variable() {
+    let result = slide("int doubled = count * 2;", "int count");
+    assert!(matches!(result, Err(RefactorError::Unsupported(_))));
+}
+
+#[test]
+fn test_refuse_crossing_call() {
+    let result = slide("int count = items.Length;", "total = doubled;");
+    assert!(matches!(result, Err(RefactorError::Unsupported(_))));
+}
+
+#[test]
+fn test_refuse_call_crossing_field_write() {
+    let result = slide("Console.WriteLine(label);", "total = doubled;");
+    assert!(matches!(result, Err(RefactorError::Unsupported(_))));
+}
+
+#[test]
+fn test_refuse_call_crossing_read_of_its_receiver() {
+    let source = r#"public class Summary {
+    public int Count(List<int> list) {
+        int n = list.Count;
+        list.Add(1);
+        return n;
+    }
+}"#;
+    let (start, end) = span_of(source, "list.Add(1);");
+    let (target, _) = span_of(source, "int n");
+    let result = refactor(source, &SlideStatements::new(start, end, target));
+    assert!(
+        matches!(result, Err(RefactorError::Unsupported(msg)) if msg.contains("cannot be analyzed"))
+    );
+}
@@ -0,0 +1,202 @@
+mod common;
+
+use common::{refactor, span_of};
+use core::{RefactorError, SplitLoop};
+
+fn split(source: &str, at: &str) -> Result<String, RefactorError> {
+    let (offset, _) = span_of(source, at);
+    refactor(source, &SplitLoop::new(offset))
+}
+
+#[test]
+fn test_split_foreach_with_two_accumulators() {
+    let source = r#"public class Roster {
+    public string Describe(Person[] people) {
+        int youngest = int.MaxValue;
+        int totalSalary = 0;
+        foreach (var p in people) {
+            if (p.Age < youngest) {
+                youngest = p.Age;
+            }
+            totalSalary += p.Salary;
+        }
+        return youngest + " " + totalSalary;
+    }
+}"#;
+    let expected = r#"public class Roster {
+    public string Describe(Person[] people) {
+        int youngest = int.MaxValue;
+        int totalSalary = 0;
+        foreach (var p in people) {
+            if (p.Age < youngest) {
+                youngest = p.Age;
+            }
+        }
+        foreach (var p in people) {
+            totalSalary += p.Salary;
+        }
+        return youngest + " " + totalSalary;
+    }
+}"#;
+    let result = split(source, "foreach").unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_split_for_loop_keeps_dependent_statements_together() {
+    let source = r#"public class Stats {
+    public void Compute(int n) {
+        int sum = 0;
+        int squares = 0;
+        for (int i = 0; i < n; i++) {
+            int square = i * i;
+            sum += i;
+            // Squares are summed separately.
+            squares += square;
+        }
+        Report(sum, squares);
+    }
+}"#;
+    let expected = r#"public class Stats {
+    public void Compute(int n) {
+        int sum = 0;
+        int squares = 0;
+        for (int i = 0; i < n; i++) {
+            int square = i * i;
+            // Squares are summed separately.
+            squares += square;
+        }
+        for (int i = 0; i < n; i++) {
+            sum += i;
+        }
+        Report(sum, squares);
+    }
+}"#;
+    let result = split(source, "sum += i").unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_split_refuses_break() {
+    let source = r#"public class Stats {
+    public void Compute(int[] values) {
+        int sum = 0;
+        int count = 0;
+        foreach (var v in values) {
+            if (v < 0) {
+                break;
+            }
+            sum += v;
+            count++;
+        }
+        Report(sum, count);
+    }
+}"#;
+    let result = split(source, "foreach");
+    assert!(matches!(result, Err(RefactorError::Unsupported(msg)) if msg.contains("`break`")));
+}
+
+#[test]
+fn test_split_allows_break_in_nested_loop() {
+    let source = r#"public class Stats {
+    public void Compute(int[][] rows) {
+        int first = 0;
+        int count = 0;
+        foreach (var row in rows) {
+            foreach (var v in row) {
+                first = v;
+                break;
+            }
+            count++;
+        }
+        Report(first, count);
+    }
+}"#;
+    let expected = r#"public class Stats {
+    public void Compute(int[][] rows) {
+        int first = 0;
+        int count = 0;
+        foreach (var row in rows) {
+            foreach (var v in row) {
+                first = v;
+                break;
+            }
+        }
+        foreach (var row in rows) {
+            count++;
+        }
+        Report(first, count);
+    }
+}"#;
+    let result = split(source, "foreach (var row").unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_split_refuses_while_loop_driven_by_its_body() {
+    let source = r#"public class Stats {
+    public void Compute(int[] values) {
+        int i = 0;
+        int sum = 0;
+        int count = 0;
+        while (i < values.Length) {
+            sum += i;
+            count++;
+            i++;
+        }
+        Report(sum, count);
+    }
+}"#;
+    let result = split(source, "while");
+    assert!(matches!(result, Err(RefactorError::Unsupported(msg)) if msg.contains("`i`")));
+}
+
+#[test]
+fn test_split_refuses_shared_mutable_state() {
+    let source = r#"public class Stats {
+    private int seen;
+
+    public void Compute(int[] values) {
+        int sum = 0;
+        foreach (var v in values) {
+            Record(v);
+            sum += v + seen;
+        }
+        Report(sum);
+    }
+}"#;
+    let result = split(source, "foreach");
+    assert!(matches!(result, Err(RefactorError::Unsupported(_))));
+}
+
+#[test]
+fn test_split_refuses_single_result() {
+    let source = r#"public class Stats {
+    public int Compute(int[] values) {
+        int sum = 0;
+        foreach (var v in values) {
+            int doubled = v * 2;
+            sum += doubled;
+        }
+        return sum;
+    }
+}"#;
+    let result = split(source, "foreach");
+    assert!(matches!(result, Err(RefactorError::Unsupported(msg)) if msg.contains("depend")));
+}
+
+#[test]
+fn test_split_refuses_call_changing_a_local_another_statement_reads() {
+    let source = r#"public class Stats {
+    public int Compute(int[] values, List<int> list) {
+        int seen = 0;
+        foreach (var x in values) {
+            list.Add(x);
+            seen += list.Count;
+        }
+        return seen;
+    }
+}"#;
+    let result = split(source, "foreach");
+    assert!(matches!(result, Err(RefactorError::Unsupported(msg)) if msg.contains("depend")));
+}
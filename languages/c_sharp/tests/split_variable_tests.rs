@@ -0,0 +1,204 @@
+mod common;
+
+use common::{refactor, span_of};
+use core::{RefactorError, SplitVariable};
+
+#[test]
+fn test_split_reused_temp() {
+    let source = r#"public class Rectangle {
+    public void Print(double height, double width) {
+        double temp = 2 * (height + width);
+        Console.WriteLine(temp);
+        temp = height * width;
+        Console.WriteLine(temp);
+    }
+}"#;
+    let expected = r#"public class Rectangle {
+    public void Print(double height, double width) {
+        double temp = 2 * (height + width);
+        Console.WriteLine(temp);
+        double area = height * width;
+        Console.WriteLine(area);
+    }
+}"#;
+    let (offset, _) = span_of(source, "temp");
+    let result = refactor(source, &SplitVariable::new(offset, &["area"])).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_split_inside_branch_and_loop() {
+    let source = r#"public class Report {
+    public int Run(int[] values, bool verbose) {
+        int result = values.Length;
+        Log(result);
+        if (verbose) {
+            result = Sum(values);
+            Log(result);
+        }
+        for (int i = 0; i < 3; i++) {
+            result = i * 2;
+            Log(result);
+        }
+        return 0;
+    }
+}"#;
+    let expected = r#"public class Report {
+    public int Run(int[] values, bool verbose) {
+        int result = values.Length;
+        Log(result);
+        if (verbose) {
+            int sum = Sum(values);
+            Log(sum);
+        }
+        for (int i = 0; i < 3; i++) {
+            int doubled = i * 2;
+            Log(doubled);
+        }
+        return 0;
+    }
+}"#;
+    let (offset, _) = span_of(source, "result");
+    let result = refactor(source, &SplitVariable::new(offset, &["sum", "doubled"])).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_refuse_accumulator() {
+    let source = r#"public class Report {
+    public int Total(int[] values) {
+        int total = 0;
+        for (int i = 0; i < values.Length; i++) {
+            total += values[i];
+        }
+        return total;
+    }
+}"#;
+    let (offset, _) = span_of(source, "total");
+    let result = refactor(source, &SplitVariable::new(offset, &["other"]));
+    assert!(matches!(result, Err(RefactorError::Unsupported(_))));
+}
+
+#[test]
+fn test_refuse_read_seeing_two_assignments() {
+    let source = r#"public class Report {
+    public void Print(bool wide) {
+        string label = "narrow";
+        if (wide) {
+            label = "wide";
+        }
+        Console.WriteLine(label);
+    }
+}"#;
+    let (offset, _) = span_of(source, "label");
+    let result = refactor(source, &SplitVariable::new(offset, &["wideLabel"]));
+    assert!(matches!(result, Err(RefactorError::Unsupported(_))));
+}
+
+#[test]
+fn test_refuse_value_carried_around_loop() {
+    let source = r#"public class Report {
+    public void Print(int count) {
+        int last = 0;
+        while (count > 0) {
+            Console.WriteLine(last);
+            last = count;
+            count--;
+        }
+    }
+}"#;
+    let (offset, _) = span_of(source, "last");
+    let result = refactor(source, &SplitVariable::new(offset, &["current"]));
+    assert!(matches!(result, Err(RefactorError::Unsupported(_))));
+}
+
+#[test]
+fn test_refuse_name_in_use() {
+    let source = r#"public class Rectangle {
+    public void Print(double height, double width) {
+        double temp = 2 * (height + width);
+        Console.WriteLine(temp);
+        temp = height * width;
+        Console.WriteLine(temp);
+    }
+}"#;
+    let (offset, _) = span_of(source, "temp");
+    let result = refactor(source, &SplitVariable::new(offset, &["width"]));
+    assert!(matches!(result, Err(RefactorError::Conflict(_))));
+}
+
+#[test]
+fn test_refuse_value_leaving_loop_through_break() {
+    let source = r#"public class Report {
+    public void Print(int count) {
+        int step;
+        while (count > 0) {
+            step = 1;
+            if (count == 3) {
+                break;
+            }
+            step = 2;
+            count -= step;
+        }
+        Console.WriteLine(step);
+    }
+}"#;
+    let (offset, _) = span_of(source, "step");
+    let result = refactor(source, &SplitVariable::new(offset, &["other"]));
+    assert!(matches!(result, Err(RefactorError::Unsupported(_))));
+}
+
+#[test]
+fn test_split_writes_out_type_of_var() {
+    let source = r#"public class Rate {
+    public double Halve() {
+        var total = 0.5;
+        Log(total);
+        total = 1;
+        return total / 2;
+    }
+}"#;
+    let expected = r#"public class Rate {
+    public double Halve() {
+        var total = 0.5;
+        Log(total);
+        double whole = 1;
+        return whole / 2;
+    }
+}"#;
+    let (offset, _) = span_of(source, "total");
+    let result = refactor(source, &SplitVariable::new(offset, &["whole"])).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_refuse_var_of_unknown_type() {
+    let source = r#"public class Rate {
+    public double Halve(Reader reader) {
+        var total = reader.Read();
+        Log(total);
+        total = 1;
+        return total / 2;
+    }
+}"#;
+    let (offset, _) = span_of(source, "total");
+    let result = refactor(source, &SplitVariable::new(offset, &["whole"]));
+    assert!(matches!(result, Err(RefactorError::Unsupported(msg)) if msg.contains("implicit")));
+}
+
+#[test]
+fn test_refuse_compound_assignment() {
+    let source = r#"public class Hash {
+    public int Bucket(int a) {
+        int x = a;
+        Log(x);
+        x %= 7;
+        return x;
+    }
+}"#;
+    let (offset, _) = span_of(source, "x");
+    let result = refactor(source, &SplitVariable::new(offset, &["y"]));
+    assert!(
+        matches!(result, Err(RefactorError::Unsupported(msg)) if msg.contains("previous value"))
+    );
+}
@@ -62,7 +62,7 @@ fn test_lower_class_with_method() {
                 match &block.statements[0] {
                     Statement::ReturnStatement(ret) => {
                         if let Some(val) = &ret.value {
-                            if let Expression::Literal(Literal::Integer(i)) = **val {
+                            if let Expression::Literal(Literal::Integer(i), _) = **val {
                                 assert_eq!(i, 5);
                             } else {
                                 panic!("Expected integer literal 5");
@@ -83,3 +83,119 @@ fn test_lower_class_with_method() {
         panic!("Expected TopLevel::Class");
     }
 }
+
+#[test]
+fn test_lower_class_with_fields_and_properties() {
+    let code = r#"public class Counter {
+    private int count = 1, step;
+    public int Count { get { return count; } private set { count = value; } }
+    public int Twice => count * 2;
+}"#;
+
+    let mut parser = Parser::new();
+    parser
+        .set_language(tree_sitter_c_sharp::language())
+        .expect("Error loading C# grammar");
+    let tree = parser.parse(code, None).unwrap();
+    let root = tree.root_node();
+
+    let mut cursor = root.walk();
+    let class_node = root
+        .children(&mut cursor)
+        .find(|n| n.kind() == "class_declaration")
+        .expect("No class declaration found");
+
+    let TopLevel::Class(class_def) = lower_top_level(class_node, code.as_bytes()) else {
+        panic!("Expected a class");
+    };
+    let body = class_def.body.expect("Class body should be present");
+    assert_eq!(body.len(), 3);
+
+    let TopLevel::Field(field) = &body[0] else {
+        panic!("Expected a field, but got {:?}", body[0]);
+    };
+    assert_eq!(field.modifiers, Some(vec!["private".to_string()]));
+    let names: Vec<&str> = field.var_decls.iter().map(|v| v.name.as_str()).collect();
+    assert_eq!(names, ["count", "step"]);
+    assert_eq!(field.var_decls[0].var_type, Some("int".to_string()));
+    assert!(field.var_decls[0].value.is_some());
+
+    let TopLevel::Property(property) = &body[1] else {
+        panic!("Expected a property, but got {:?}", body[1]);
+    };
+    assert_eq!(property.name, "Count");
+    assert_eq!(
+        &code[property.name_span.start..property.name_span.end],
+        "Count"
+    );
+    let accessors: Vec<&str> = property.accessors.iter().map(|a| a.name.as_str()).collect();
+    assert_eq!(accessors, ["get", "set"]);
+    assert_eq!(
+        property.accessors[1].modifiers,
+        Some(vec!["private".to_string()])
+    );
+
+    let TopLevel::Property(twice) = &body[2] else {
+        panic!("Expected a property, but got {:?}", body[2]);
+    };
+    assert_eq!(twice.accessors.len(), 1);
+    assert!(matches!(
+        twice.accessors[0].body.as_deref(),
+        Some([FunctionBodyItems::Expression(Expression::BinaryOp(_))])
+    ));
+}
+
+#[test]
+fn test_lower_class_with_constructors() {
+    let code = r#"
+        public class Counter {
+            private int count;
+
+            public Counter(int start) {
+                count = start;
+            }
+
+            public Counter() : this(0) {
+            }
+        }
+    "#;
+
+    let mut parser = Parser::new();
+    parser
+        .set_language(tree_sitter_c_sharp::language())
+        .expect("Error loading C# grammar");
+    let tree = parser.parse(code, None).unwrap();
+    let root = tree.root_node();
+
+    let mut cursor = root.walk();
+    let class_node = root
+        .children(&mut cursor)
+        .find(|n| n.kind() == "class_declaration")
+        .expect("No class declaration found");
+
+    let result = lower_top_level(class_node, code.as_bytes());
+
+    let TopLevel::Class(class_def) = result else {
+        panic!("Expected Class, got {:?}", result);
+    };
+    let body = class_def.body.expect("Class body should be present");
+    assert_eq!(body.len(), 3);
+
+    // A constructor is a function named after the class, without a return type.
+    let TopLevel::Function(constructor) = &body[1] else {
+        panic!("Expected the constructor as a Function, got {:?}", body[1]);
+    };
+    assert_eq!(constructor.name, "Counter");
+    assert_eq!(constructor.return_type, None);
+    assert_eq!(constructor.parameters.as_ref().map(Vec::len), Some(1));
+    let Some(FunctionBodyItems::Block(block)) = constructor.body.iter().flatten().next() else {
+        panic!("Expected a constructor body, got {:?}", constructor.body);
+    };
+    assert!(matches!(
+        block.statements[0],
+        Statement::ExpressionStatement(_)
+    ));
+
+    // Chaining to another constructor happens outside the body, so it stays unlowered.
+    assert!(matches!(&body[2], TopLevel::Unknown { source, .. } if source.contains(": this(0)")));
+}
@@ -10,6 +10,18 @@ pub struct Span {
     pub end: usize,
 }
 
+impl Span {
+    /// Returns true if `other` lies entirely within this span.
+    pub fn contains(&self, other: &Span) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+
+    /// Returns true if the two spans share at least one byte.
+    pub fn overlaps(&self, other: &Span) -> bool {
+        self.start < other.end && other.start < self.end
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum Literal {
     Integer(i64),
@@ -26,12 +38,18 @@ pub enum BinaryOperator {
     Sub,
     Mul,
     Div,
+    Mod,
+    LeftShift,
+    RightShift,
     Equal,
     NotEqual,
     GreaterThan,
     LessThan,
     GreaterThanEqual,
     LessThanEqual,
+    Logical(LogicalOperator),
+    /// `??`
+    Coalesce,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -89,6 +107,15 @@ pub enum AssignmentOperator {
     SubAssign,
     MulAssign,
     DivAssign,
+    ModAssign,
+    AndAssign,
+    OrAssign,
+    XorAssign,
+    LeftShiftAssign,
+    RightShiftAssign,
+    UnsignedRightShiftAssign,
+    /// `??=`
+    CoalesceAssign,
 }
 
 // --- Expressions ---
@@ -97,12 +124,14 @@ pub struct BinaryOp {
     pub left: Box<Expression>,
     pub operator: BinaryOperator,
     pub right: Box<Expression>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct UnaryOp {
     pub operator: UnaryOperator,
     pub operand: Box<Expression>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -110,12 +139,14 @@ pub struct Assignment {
     pub left: Box<Expression>,
     pub operator: AssignmentOperator,
     pub right: Box<Expression>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Invocation {
     pub function: Box<Expression>,
     pub arguments: Vec<Expression>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -123,20 +154,64 @@ pub struct MemberAccess {
     pub expression: Box<Expression>,
     pub member: String,
     pub member_span: Span,
+    pub span: Span,
+}
+
+/// `condition ? consequence : alternative`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Conditional {
+    pub condition: Box<Expression>,
+    pub consequence: Box<Expression>,
+    pub alternative: Box<Expression>,
+    pub span: Span,
+}
+
+/// `(parameters) => body`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Lambda {
+    /// Implicitly typed parameters have no `var_type`.
+    pub parameters: Vec<VarDecl>,
+    pub body: LambdaBody,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum LambdaBody {
+    Expression(Box<Expression>),
+    Block(Block),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum Expression {
     Identifier(String, Span),
-    Literal(Literal),
+    Literal(Literal, Span),
     BinaryOp(BinaryOp),
     UnaryOp(UnaryOp),
     Assignment(Assignment),
     Invocation(Invocation),
     MemberAccess(MemberAccess),
+    Conditional(Conditional),
+    Lambda(Lambda),
     Raw { source: String, span: Span },
 }
 
+impl Expression {
+    pub fn span(&self) -> &Span {
+        match self {
+            Expression::Identifier(_, span) => span,
+            Expression::Literal(_, span) => span,
+            Expression::BinaryOp(op) => &op.span,
+            Expression::UnaryOp(op) => &op.span,
+            Expression::Assignment(assign) => &assign.span,
+            Expression::Invocation(inv) => &inv.span,
+            Expression::MemberAccess(ma) => &ma.span,
+            Expression::Conditional(cond) => &cond.span,
+            Expression::Lambda(lambda) => &lambda.span,
+            Expression::Raw { span, .. } => span,
+        }
+    }
+}
+
 // --- Statements ---
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Block {
@@ -168,6 +243,40 @@ pub struct ForLoop {
     pub span: Span,
 }
 
+/// `foreach (Type variable in collection) body`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ForEachLoop {
+    /// The iteration variable, declared without a value.
+    pub variable: VarDecl,
+    pub collection: Box<Expression>,
+    pub body: Box<Block>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SwitchStatement {
+    pub value: Box<Expression>,
+    pub sections: Vec<SwitchSection>,
+    pub span: Span,
+}
+
+/// The labels of a `switch` section and the statements they select.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SwitchSection {
+    pub labels: Vec<SwitchLabel>,
+    /// The statements after the labels, spanning from the first to the last.
+    pub body: Block,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum SwitchLabel {
+    /// `case value:`. Patterns, along with any `when` clause, are kept as
+    /// [`Expression::Raw`].
+    Case(Expression),
+    Default(Span),
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct VarDecl {
     pub span: Span,
@@ -182,12 +291,14 @@ pub struct VarDecl {
 pub struct DeclStmt {
     pub modifiers: Option<Vec<String>>,
     pub var_decls: Vec<VarDecl>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ReturnStatement {
     // Need to somehow store the type if inferred, or just rely on value expression
     pub value: Option<Box<Expression>>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -203,15 +314,35 @@ pub enum Statement {
     Unknown { source: String, span: Span },
     WhileLoop(WhileLoop),
     ForLoop(ForLoop),
+    ForEachLoop(ForEachLoop),
+    SwitchStatement(SwitchStatement),
     ReturnStatement(ReturnStatement),
     ExpressionStatement(ExpressionStatement),
 }
 
+impl Statement {
+    pub fn span(&self) -> &Span {
+        match self {
+            Statement::DeclStmt(decl) => &decl.span,
+            Statement::IfStatement(if_stmt) => &if_stmt.span,
+            Statement::Unknown { span, .. } => span,
+            Statement::WhileLoop(w) => &w.span,
+            Statement::ForLoop(f) => &f.span,
+            Statement::ForEachLoop(f) => &f.span,
+            Statement::SwitchStatement(switch) => &switch.span,
+            Statement::ReturnStatement(ret) => &ret.span,
+            Statement::ExpressionStatement(expr) => &expr.span,
+        }
+    }
+}
+
 // --- Top-Level Declarations ---
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum TopLevel {
     Class(ClassDef),
     Function(FunctionDef),
+    Field(FieldDef),
+    Property(PropertyDef),
     Module(ModuleDef),
     Statement(Statement),
     Unknown { source: String, span: Span },
@@ -230,6 +361,7 @@ pub struct ClassDef {
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct FunctionDef {
     pub name: String,
+    pub name_span: Span,
     pub span: Span,
     pub body: Option<Vec<FunctionBodyItems>>,
     pub modifiers: Option<Vec<String>>,
@@ -239,6 +371,27 @@ pub struct FunctionDef {
     pub metadata: Option<Metadata>,
 }
 
+/// A field declaration, which may declare several fields of the same type.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FieldDef {
+    pub span: Span,
+    pub modifiers: Option<Vec<String>>,
+    pub var_decls: Vec<VarDecl>,
+}
+
+/// A property. Each accessor is a function named after its keyword, such as
+/// `get`, and an expression-bodied property has a single `get` accessor.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PropertyDef {
+    pub name: String,
+    pub name_span: Span,
+    pub span: Span,
+    pub modifiers: Option<Vec<String>>,
+    pub var_type: Option<String>,
+    pub accessors: Vec<FunctionDef>,
+    pub value: Option<Box<Expression>>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ModuleDef {
     pub name: String,